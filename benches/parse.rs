@@ -0,0 +1,51 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+
+use sgf_parse::testing::generate;
+use sgf_parse::{parse_with_options, serialize, ParseOptions};
+
+fn bench_parse(c: &mut Criterion, name: &str, sgf: &str, options: &ParseOptions) {
+    let mut group = c.benchmark_group(name);
+    group.throughput(Throughput::Bytes(sgf.len() as u64));
+    group.bench_function("parse", |b| {
+        b.iter(|| parse_with_options(black_box(sgf), options).unwrap())
+    });
+    let gametrees = parse_with_options(sgf, options).unwrap();
+    group.bench_function("serialize", |b| b.iter(|| serialize(black_box(&gametrees))));
+    group.finish();
+}
+
+fn benchmarks(c: &mut Criterion) {
+    let options = ParseOptions::default();
+    bench_parse(c, "large_game", &generate::large_game(10_000), &options);
+    bench_parse(
+        c,
+        "many_small_games",
+        &generate::many_small_games(1_000, 50),
+        &options,
+    );
+    bench_parse(
+        c,
+        "comment_heavy",
+        &generate::comment_heavy_game(500, 2_000),
+        &options,
+    );
+    bench_parse(
+        c,
+        "markup_heavy",
+        &generate::markup_heavy_game(2_000),
+        &options,
+    );
+    let lenient = ParseOptions {
+        recover_missing_nodes: true,
+        ..ParseOptions::default()
+    };
+    bench_parse(
+        c,
+        "broken_lenient",
+        &generate::broken_games(200, 100),
+        &lenient,
+    );
+}
+
+criterion_group!(benches, benchmarks);
+criterion_main!(benches);
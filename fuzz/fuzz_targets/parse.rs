@@ -3,7 +3,5 @@ use libfuzzer_sys::fuzz_target;
 extern crate sgf_parse;
 
 fuzz_target!(|data: &[u8]| {
-    if let Ok(s) = std::str::from_utf8(data) {
-        let _ = sgf_parse::parse(s);
-    }
+    let _ = sgf_parse::parse_bytes(data);
 });
@@ -0,0 +1,244 @@
+//! A conformance checker for SGF parsing behaviour.
+//!
+//! [`run`] parses the [bundled fixtures](crate::testing::fixtures), including the FF\[4\] example
+//! file from the [SGF specification](https://www.red-bean.com/sgf/examples/), along with a set of
+//! curated edge cases, and checks each against the behaviour the specification requires. This
+//! lets forks and tools which configure the parser differently check that they still handle SGF
+//! correctly. Requires the `fixtures` feature.
+//!
+//! # Examples
+//! ```
+//! use sgf_parse::{conformance, ParseOptions};
+//!
+//! let report = conformance::run(&ParseOptions::default());
+//! assert!(report.passed(), "{}", report);
+//!
+//! let options = ParseOptions {
+//!     reject_unknown_properties: true,
+//!     ..ParseOptions::default()
+//! };
+//! let report = conformance::run(&options);
+//! assert!(report.failures().any(|result| result.name == "unknown_property"));
+//! ```
+
+use crate::testing::fixtures::{all_fixtures, Category};
+use crate::{parse_bytes_with_options, serialize, ParseOptions};
+
+/// The result of checking a single case, as returned in a [`ConformanceReport`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CaseResult {
+    /// The name of the fixture or edge case.
+    pub name: &'static str,
+    /// A description of how the parser's behaviour differed from what was expected, or `None` if
+    /// the case passed.
+    pub failure: Option<String>,
+}
+
+impl CaseResult {
+    /// Returns `true` if the case passed.
+    pub fn passed(&self) -> bool {
+        self.failure.is_none()
+    }
+}
+
+/// The results of a conformance run, as returned by [`run`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ConformanceReport {
+    /// The result of each case, in the order they were checked.
+    pub results: Vec<CaseResult>,
+}
+
+impl ConformanceReport {
+    /// Returns `true` if every case passed.
+    pub fn passed(&self) -> bool {
+        self.results.iter().all(CaseResult::passed)
+    }
+
+    /// Returns an iterator over the cases which failed.
+    pub fn failures(&self) -> impl Iterator<Item = &CaseResult> {
+        self.results.iter().filter(|result| !result.passed())
+    }
+}
+
+impl std::fmt::Display for ConformanceReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let passed = self.results.iter().filter(|result| result.passed()).count();
+        write!(f, "{}/{} cases passed", passed, self.results.len())?;
+        for result in self.failures() {
+            let failure = result.failure.as_deref().unwrap_or_default();
+            write!(f, "\n{}: {}", result.name, failure)?;
+        }
+        Ok(())
+    }
+}
+
+// The behaviour expected when parsing a case.
+enum Expected {
+    // Parses, validates, and round trips through serialization.
+    Valid,
+    // Like `Valid`, and serializes to exactly this text.
+    Serializes(&'static str),
+    // Parses, but fails validation.
+    InvalidNode,
+    // Fails to parse.
+    ParseError,
+}
+
+// Edge cases from the SGF specification not covered by the fixtures.
+const EDGE_CASES: &[(&str, &str, Expected)] = &[
+    ("minimal_gametree", "(;)", Expected::Serializes("(;)")),
+    (
+        "collection",
+        "(;B[aa])(;W[bb])",
+        Expected::Serializes("(;B[aa])(;W[bb])"),
+    ),
+    (
+        "whitespace_between_tokens",
+        "( ;B[aa]\n ;W[bb]\t)",
+        Expected::Serializes("(;B[aa];W[bb])"),
+    ),
+    (
+        "whitespace_between_values",
+        "(;AB[aa] [bb]\n[cc])",
+        Expected::Valid,
+    ),
+    (
+        "escaped_text",
+        "(;C[a\\]b\\\\c\\:d])",
+        Expected::Serializes("(;C[a\\]b\\\\c\\:d])"),
+    ),
+    (
+        "soft_line_break",
+        "(;C[soft\\\nbreak])",
+        Expected::Serializes("(;C[softbreak])"),
+    ),
+    (
+        "escaped_compose_value",
+        "(;LB[aa:a\\:b])",
+        Expected::Serializes("(;LB[aa:a\\:b])"),
+    ),
+    ("compressed_point_list", "(;AB[aa:cc])", Expected::Valid),
+    ("pass_move", "(;B[])", Expected::Serializes("(;B[])")),
+    (
+        "unknown_property",
+        "(;XX[kept])",
+        Expected::Serializes("(;XX[kept])"),
+    ),
+    (
+        "variations",
+        "(;B[aa](;W[bb])(;W[cc];B[dd]))",
+        Expected::Valid,
+    ),
+    ("two_moves_in_node", "(;B[aa]W[bb])", Expected::InvalidNode),
+    (
+        "setup_and_move_mixed",
+        "(;AB[aa]B[bb])",
+        Expected::InvalidNode,
+    ),
+    (
+        "root_property_in_child",
+        "(;B[aa];SZ[9])",
+        Expected::InvalidNode,
+    ),
+    ("repeated_identifier", "(;C[a]C[b])", Expected::InvalidNode),
+    ("empty_gametree", "()", Expected::ParseError),
+    ("unclosed_gametree", "(;B[aa]", Expected::ParseError),
+    ("unclosed_value", "(;C[open)", Expected::ParseError),
+    ("unexpected_gametree_end", "(;B[aa]))", Expected::ParseError),
+];
+
+/// Checks the parser's behaviour with the provided options against the bundled fixtures and
+/// curated edge cases.
+///
+/// Valid cases must parse, validate, and parse back to the same game trees after serialization.
+/// Invalid cases must fail to parse or validate. Fixtures in
+/// [`Category::Recoverable`] must be valid if
+/// [`ParseOptions::convert_mixed_case_identifiers`] is set, and invalid otherwise. Options which
+/// intentionally reject or drop content (like
+/// [`ParseOptions::reject_unknown_properties`]) will cause some cases to fail.
+pub fn run(options: &ParseOptions) -> ConformanceReport {
+    let fixtures = all_fixtures().iter().map(|fixture| {
+        let expected = match fixture.category {
+            Category::Valid => Expected::Valid,
+            Category::Recoverable if options.convert_mixed_case_identifiers => Expected::Valid,
+            Category::Recoverable | Category::Invalid => Expected::InvalidNode,
+        };
+        let failure = check(fixture.data, &expected, options, true);
+        CaseResult {
+            name: fixture.name,
+            failure,
+        }
+    });
+    let edge_cases = EDGE_CASES.iter().map(|(name, sgf, expected)| CaseResult {
+        name,
+        failure: check(sgf.as_bytes(), expected, options, false),
+    });
+    ConformanceReport {
+        results: fixtures.chain(edge_cases).collect(),
+    }
+}
+
+// Returns a description of how parsing `data` differed from what was expected, if it did.
+//
+// With `allow_parse_error`, an `InvalidNode` case may also fail to parse.
+fn check(
+    data: &[u8],
+    expected: &Expected,
+    options: &ParseOptions,
+    allow_parse_error: bool,
+) -> Option<String> {
+    let gametrees = match (parse_bytes_with_options(data, options), expected) {
+        (Err(_), Expected::ParseError) => return None,
+        (Err(_), Expected::InvalidNode) if allow_parse_error => return None,
+        (Err(e), _) => return Some(format!("unexpected parse error: {}", e)),
+        (Ok(_), Expected::ParseError) => return Some("parsed successfully".to_string()),
+        (Ok(gametrees), _) => gametrees,
+    };
+    let invalid = gametrees
+        .iter()
+        .find_map(|gametree| gametree.serialize_validated(None).err());
+    match (invalid, expected) {
+        (None, Expected::InvalidNode) => return Some("validated successfully".to_string()),
+        (Some(_), Expected::InvalidNode) => return None,
+        (Some(e), _) => return Some(format!("unexpected validation error: {}", e)),
+        (None, _) => {}
+    }
+    let text = serialize(&gametrees);
+    if let Expected::Serializes(expected_text) = expected {
+        if text != *expected_text {
+            return Some(format!("serialized as {}", text));
+        }
+    }
+    match parse_bytes_with_options(text.as_bytes(), options) {
+        Ok(reparsed) if reparsed == gametrees => None,
+        Ok(_) => Some("serialized game trees parse differently".to_string()),
+        Err(e) => Some(format!("serialized game trees fail to parse: {}", e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::run;
+    use crate::ParseOptions;
+
+    #[test]
+    fn options_change_results() {
+        let options = ParseOptions {
+            convert_mixed_case_identifiers: false,
+            ..ParseOptions::default()
+        };
+        assert!(run(&options).passed());
+
+        let options = ParseOptions {
+            max_main_line_nodes: Some(1),
+            ..ParseOptions::default()
+        };
+        let report = run(&options);
+        let failures: Vec<_> = report.failures().map(|result| result.name).collect();
+        assert!(failures.contains(&"whitespace_between_tokens"));
+        assert!(failures.contains(&"root_property_in_child"));
+        assert!(report
+            .to_string()
+            .contains("root_property_in_child: validated successfully"));
+    }
+}
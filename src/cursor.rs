@@ -0,0 +1,181 @@
+use crate::{NodePath, SgfNode, SgfProp};
+
+/// A position in a tree of [`SgfNode`] values which can move up and down the tree.
+///
+/// A cursor keeps track of the nodes from the root to its current node, so moving to the parent
+/// or a sibling doesn't require callers to keep their own stack. Movement methods return the new
+/// current node, or `None` without moving if there's no such node.
+///
+/// # Examples
+/// ```
+/// use sgf_parse::go::{next_player, parse};
+/// use sgf_parse::{Color, Cursor};
+///
+/// let node = parse("(;SZ[9];B[ee](;W[ce])(;W[gc];B[gd]))").unwrap().remove(0);
+/// let mut cursor = Cursor::new(&node);
+/// cursor.child(0).unwrap();
+/// cursor.child(0).unwrap();
+/// assert_eq!(cursor.node().serialize(), "(;W[ce])");
+/// cursor.next_sibling().unwrap();
+/// cursor.child(0).unwrap();
+/// assert_eq!(cursor.path().to_string(), "0.1.0");
+/// assert_eq!(next_player(cursor.nodes().iter().copied()), Color::White);
+///
+/// cursor.parent().unwrap();
+/// assert!(cursor.next_sibling().is_none());
+/// assert_eq!(cursor.prev_sibling().unwrap().serialize(), "(;W[ce])");
+/// ```
+#[derive(Clone, Debug)]
+pub struct Cursor<'a, Prop: SgfProp> {
+    // Nodes from the root to the current node.
+    nodes: Vec<&'a SgfNode<Prop>>,
+    // Child indices leading from the root to the current node.
+    path: NodePath,
+}
+
+impl<'a, Prop: SgfProp> Cursor<'a, Prop> {
+    /// Returns a new cursor at the provided root node.
+    pub fn new(root: &'a SgfNode<Prop>) -> Self {
+        Self {
+            nodes: vec![root],
+            path: NodePath::root(),
+        }
+    }
+
+    /// Returns the current node.
+    pub fn node(&self) -> &'a SgfNode<Prop> {
+        self.nodes[self.nodes.len() - 1]
+    }
+
+    /// Returns the root node.
+    pub fn root(&self) -> &'a SgfNode<Prop> {
+        self.nodes[0]
+    }
+
+    /// Returns the nodes from the root to the current node, inclusive.
+    pub fn nodes(&self) -> &[&'a SgfNode<Prop>] {
+        &self.nodes
+    }
+
+    /// Returns the path from the root to the current node.
+    pub fn path(&self) -> &NodePath {
+        &self.path
+    }
+
+    /// Returns the game info node in effect for the current node.
+    ///
+    /// See [`SgfNode::effective_game_info`].
+    pub fn game_info_node(&self) -> Option<&'a SgfNode<Prop>> {
+        self.nodes
+            .iter()
+            .rev()
+            .find(|node| node.has_game_info())
+            .copied()
+    }
+
+    /// Returns the number of moves made down from the root to reach the current node.
+    pub fn depth(&self) -> usize {
+        self.path.len()
+    }
+
+    /// Moves to the parent of the current node.
+    pub fn parent(&mut self) -> Option<&'a SgfNode<Prop>> {
+        if self.path.is_root() {
+            return None;
+        }
+        self.path.pop();
+        self.nodes.pop();
+        Some(self.node())
+    }
+
+    /// Moves to the child of the current node with the provided index.
+    pub fn child(&mut self, index: usize) -> Option<&'a SgfNode<Prop>> {
+        let child = self.node().children.get(index)?;
+        self.path.push(index);
+        self.nodes.push(child);
+        Some(child)
+    }
+
+    /// Moves to the next sibling of the current node.
+    pub fn next_sibling(&mut self) -> Option<&'a SgfNode<Prop>> {
+        let index = self.path.last()?.checked_add(1)?;
+        self.move_to_sibling(index)
+    }
+
+    /// Moves to the previous sibling of the current node.
+    pub fn prev_sibling(&mut self) -> Option<&'a SgfNode<Prop>> {
+        let index = self.path.last()?.checked_sub(1)?;
+        self.move_to_sibling(index)
+    }
+
+    /// Moves back to the root node.
+    pub fn to_root(&mut self) -> &'a SgfNode<Prop> {
+        self.nodes.truncate(1);
+        self.path = NodePath::root();
+        self.root()
+    }
+
+    /// Moves to the node at the provided path from the root.
+    ///
+    /// The cursor doesn't move if there's no node at the path.
+    pub fn go_to(&mut self, path: &NodePath) -> Option<&'a SgfNode<Prop>> {
+        let mut nodes = vec![self.root()];
+        for &index in path.iter() {
+            nodes.push(nodes[nodes.len() - 1].children.get(index)?);
+        }
+        self.nodes = nodes;
+        self.path = path.clone();
+        Some(self.node())
+    }
+
+    fn move_to_sibling(&mut self, index: usize) -> Option<&'a SgfNode<Prop>> {
+        let parent = self.nodes[self.nodes.len() - 2];
+        let sibling = parent.children.get(index)?;
+        self.path.pop();
+        self.path.push(index);
+        *self.nodes.last_mut().unwrap() = sibling;
+        Some(sibling)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Cursor;
+    use crate::go::parse;
+    use crate::NodePath;
+
+    #[test]
+    fn navigation() {
+        let node = parse("(;C[root](;B[aa];W[bb])(;B[cc])(;B[dd]))")
+            .unwrap()
+            .remove(0);
+        let mut cursor = Cursor::new(&node);
+        assert!(cursor.parent().is_none());
+        assert!(cursor.next_sibling().is_none());
+        assert!(cursor.prev_sibling().is_none());
+        assert!(cursor.child(3).is_none());
+        assert_eq!(cursor.depth(), 0);
+
+        cursor.child(0).unwrap();
+        assert!(cursor.prev_sibling().is_none());
+        cursor.next_sibling().unwrap();
+        assert_eq!(cursor.next_sibling().unwrap().serialize(), "(;B[dd])");
+        assert!(cursor.next_sibling().is_none());
+        assert_eq!(cursor.path().as_slice(), [2]);
+        assert!(std::ptr::eq(cursor.parent().unwrap(), &node));
+
+        assert!(cursor.go_to(&NodePath::from(vec![0, 1])).is_none());
+        assert_eq!(cursor.depth(), 0);
+        assert_eq!(
+            cursor
+                .go_to(&NodePath::from(vec![0, 0]))
+                .unwrap()
+                .serialize(),
+            "(;W[bb])"
+        );
+        assert_eq!(cursor.nodes().len(), 3);
+        assert!(cursor.game_info_node().is_none());
+        assert!(std::ptr::eq(cursor.to_root(), &node));
+        assert!(cursor.path().is_root());
+    }
+}
@@ -0,0 +1,127 @@
+use std::any::Any;
+use std::sync::Arc;
+
+use crate::{unknown_game, InvalidNodeError, SgfNode, SgfParseError};
+
+/// A game tree for a game this crate doesn't support directly.
+///
+/// Since [`SgfProp`](`crate::SgfProp`) is sealed, other crates can't use [`SgfNode`] for their
+/// own property types. Instead they can implement this trait for their own node type and
+/// register a [`CustomGameParser`] in
+/// [`ParseOptions::custom_games`](`crate::ParseOptions::custom_games`) to have matching game
+/// trees parsed as [`GameTree::Custom`](`crate::GameTree::Custom`).
+///
+/// # Examples
+/// ```
+/// use std::any::Any;
+/// use sgf_parse::{DynGameNode, GameTree};
+///
+/// #[derive(Clone, Debug)]
+/// struct ChessGame {
+///     moves: Vec<String>,
+/// }
+///
+/// impl DynGameNode for ChessGame {
+///     fn serialize(&self) -> String {
+///         let moves: String = self.moves.iter().map(|m| format!(";M[{}]", m)).collect();
+///         format!("(;GM[3]{})", moves)
+///     }
+///
+///     fn as_any(&self) -> &dyn Any {
+///         self
+///     }
+///
+///     fn clone_box(&self) -> Box<dyn DynGameNode> {
+///         Box::new(self.clone())
+///     }
+///
+///     fn node_count(&self) -> usize {
+///         self.moves.len() + 1
+///     }
+///
+///     fn max_depth(&self) -> usize {
+///         self.moves.len()
+///     }
+///
+///     fn variation_count(&self) -> usize {
+///         1
+///     }
+/// }
+///
+/// let gametree = GameTree::Custom(Box::new(ChessGame { moves: vec!["e4".to_string()] }));
+/// assert_eq!(gametree.to_string(), "(;GM[3];M[e4])");
+/// assert_eq!(gametree.node_count(), 2);
+/// ```
+pub trait DynGameNode: std::fmt::Debug + Send + Sync + 'static {
+    /// Returns the game tree serialized as SGF, including the surrounding parentheses.
+    fn serialize(&self) -> String;
+
+    /// Returns `self` as [`Any`] to allow downcasting to the concrete node type.
+    fn as_any(&self) -> &dyn Any;
+
+    /// Returns a boxed copy of `self`.
+    fn clone_box(&self) -> Box<dyn DynGameNode>;
+
+    /// Returns the number of nodes in the game tree.
+    ///
+    /// Used by [`GameTree::node_count`](`crate::GameTree::node_count`).
+    fn node_count(&self) -> usize;
+
+    /// Returns the depth of the deepest node in the game tree, where the root has depth 0.
+    ///
+    /// Used by [`GameTree::max_depth`](`crate::GameTree::max_depth`).
+    fn max_depth(&self) -> usize;
+
+    /// Returns the number of variations (nodes without children) in the game tree.
+    ///
+    /// Used by [`GameTree::variation_count`](`crate::GameTree::variation_count`).
+    fn variation_count(&self) -> usize;
+
+    /// Returns `Ok` if the game tree is valid.
+    ///
+    /// Used by [`GameTree::serialize_validated`](`crate::GameTree::serialize_validated`).
+    /// Defaults to `Ok`, since this crate doesn't know the rules of other games.
+    ///
+    /// # Errors
+    /// Returns an error if the game tree is invalid.
+    fn validate(&self) -> Result<(), InvalidNodeError> {
+        Ok(())
+    }
+
+    /// Returns the approximate number of bytes used by the game tree.
+    ///
+    /// Used by [`GameTree::memory_estimate`](`crate::GameTree::memory_estimate`). Defaults to the
+    /// size of `self` plus the length of the serialized SGF.
+    fn memory_estimate(&self) -> usize {
+        std::mem::size_of_val(self) + self.serialize().len()
+    }
+
+    /// Returns whether `self` and `other` represent the same game tree.
+    ///
+    /// Defaults to comparing the serialized SGF.
+    fn dyn_eq(&self, other: &dyn DynGameNode) -> bool {
+        self.serialize() == other.serialize()
+    }
+}
+
+/// A parser for a custom game, used in [`ParseOptions::custom_games`](`crate::ParseOptions::custom_games`).
+///
+/// The parser receives the game tree as parsed for an unknown game, with property values kept as
+/// raw strings, and converts it into a [`DynGameNode`].
+pub type CustomGameParser = Arc<
+    dyn Fn(SgfNode<unknown_game::Prop>) -> Result<Box<dyn DynGameNode>, SgfParseError>
+        + Send
+        + Sync,
+>;
+
+impl Clone for Box<dyn DynGameNode> {
+    fn clone(&self) -> Self {
+        self.clone_box()
+    }
+}
+
+impl PartialEq for Box<dyn DynGameNode> {
+    fn eq(&self, other: &Self) -> bool {
+        self.dyn_eq(other.as_ref())
+    }
+}
@@ -0,0 +1,308 @@
+use crate::props::parse::split_compose;
+use crate::props::{Sealed, ToSgf};
+use crate::{go, unknown_game, Color, Double, GameType, PropertyType, SgfProp, SimpleText, Text};
+
+/// The type of an SGF property value, following the notation of the SGF specification.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ValueType {
+    None,
+    Number,
+    Real,
+    Double,
+    Color,
+    SimpleText,
+    Text,
+    Point,
+    Move,
+    Stone,
+    /// A non-empty list of values.
+    List(&'static ValueType),
+    /// A possibly empty list of values.
+    EList(&'static ValueType),
+    /// Two values separated by `:`.
+    Compose(&'static ValueType, &'static ValueType),
+    /// Either of two value types.
+    Either(&'static ValueType, &'static ValueType),
+}
+
+/// An entry in the property specification table used by [`DynProp`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PropertySpec {
+    pub identifier: &'static str,
+    pub property_type: Option<PropertyType>,
+    pub value_type: ValueType,
+}
+
+macro_rules! property_specs {
+    (
+        flags: { $($flag:ident [$($flag_type:ident)?];)* }
+        values: {
+            $($ident:ident($getter:ident) [$($property_type:ident)?]:
+                $kind:ident = $parse:path $(, $range:expr)?;)*
+        }
+    ) => {
+        &[
+            $(PropertySpec {
+                identifier: stringify!($flag),
+                property_type: prop_property_type!($($flag_type)?),
+                value_type: ValueType::None,
+            },)*
+            $(PropertySpec {
+                identifier: stringify!($ident),
+                property_type: prop_property_type!($($property_type)?),
+                value_type: prop_spec_value_type!($kind),
+            },)*
+        ]
+    };
+}
+
+const GENERAL_PROPERTIES: &[PropertySpec] = general_properties!(property_specs! {});
+
+const GO_PROPERTIES: &[PropertySpec] = go_properties!(property_specs! {});
+
+/// Returns the specification for a property identifier in the given game.
+///
+/// # Examples
+/// ```
+/// use sgf_parse::{property_spec, GameType, PropertyType, ValueType};
+///
+/// let spec = property_spec(GameType::Go, "KM").unwrap();
+/// assert_eq!(spec.property_type, Some(PropertyType::GameInfo));
+/// assert_eq!(spec.value_type, ValueType::Real);
+/// assert!(property_spec(GameType::Unknown, "KM").is_none());
+/// ```
+pub fn property_spec(gametype: GameType, identifier: &str) -> Option<&'static PropertySpec> {
+    let game_properties = match gametype {
+        GameType::Go => GO_PROPERTIES,
+        _ => &[],
+    };
+    GENERAL_PROPERTIES
+        .iter()
+        .chain(game_properties)
+        .find(|spec| spec.identifier == identifier)
+}
+
+/// A dynamically typed SGF property value.
+///
+/// Points, moves and stones are kept as their SGF strings since their format depends on the
+/// game. Compressed point lists are kept as [`DynValue::Compose`] values of their corners.
+#[derive(Clone, Debug, PartialEq)]
+pub enum DynValue {
+    None,
+    Number(i64),
+    Real(f64),
+    Double(Double),
+    Color(Color),
+    SimpleText(SimpleText),
+    Text(Text),
+    Point(String),
+    Move(String),
+    Stone(String),
+    List(Vec<DynValue>),
+    Compose(Box<DynValue>, Box<DynValue>),
+    /// The raw values of unknown properties or values which don't match the specification.
+    Raw(Vec<String>),
+}
+
+/// An SGF property with a dynamically typed value.
+///
+/// This is an alternative to the game specific `Prop` enums for tools which prefer handling all
+/// properties uniformly. Values are parsed according to the [`property_spec`] table, and
+/// properties can be converted to and from [`go::Prop`] and [`unknown_game::Prop`].
+///
+/// # Examples
+/// ```
+/// use sgf_parse::{DynProp, DynValue, GameType, SgfProp};
+/// use sgf_parse::go::Prop;
+///
+/// let prop = DynProp::new(GameType::Go, "SZ".to_string(), vec!["9:13".to_string()]);
+/// assert_eq!(
+///     prop.value,
+///     DynValue::Compose(Box::new(DynValue::Number(9)), Box::new(DynValue::Number(13)))
+/// );
+/// assert_eq!(Prop::from(prop), Prop::SZ((9, 13)));
+///
+/// let prop = DynProp::from(&Prop::new("AB".to_string(), vec!["aa".to_string()]));
+/// assert_eq!(prop.identifier, "AB");
+/// assert_eq!(prop.value, DynValue::List(vec![DynValue::Stone("aa".to_string())]));
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct DynProp {
+    pub identifier: String,
+    pub value: DynValue,
+}
+
+impl DynProp {
+    /// Returns a new property parsed from the provided identifier and values.
+    ///
+    /// Unknown properties and values which don't match the specification are kept as
+    /// [`DynValue::Raw`].
+    pub fn new(gametype: GameType, identifier: String, values: Vec<String>) -> Self {
+        let value = property_spec(gametype, &identifier)
+            .and_then(|spec| parse_values(&spec.value_type, &values))
+            .unwrap_or(DynValue::Raw(values));
+        Self { identifier, value }
+    }
+
+    /// Returns the [`PropertyType`] of the property in the given game.
+    pub fn property_type(&self, gametype: GameType) -> Option<PropertyType> {
+        property_spec(gametype, &self.identifier).and_then(|spec| spec.property_type)
+    }
+
+    /// Returns the raw values of the property as they'd appear in an SGF file (unescaped).
+    ///
+    /// Compose values keep their escapes so colons in either half can be told apart from the
+    /// separator.
+    pub fn values(&self) -> Vec<String> {
+        match &self.value {
+            DynValue::None => vec![String::new()],
+            DynValue::List(items) if items.is_empty() => vec![String::new()],
+            DynValue::List(items) => items.iter().map(format_value).collect(),
+            DynValue::Raw(values) => values.clone(),
+            value => vec![format_value(value)],
+        }
+    }
+}
+
+fn parse_values(value_type: &ValueType, values: &[String]) -> Option<DynValue> {
+    let is_empty = values.is_empty() || (values.len() == 1 && values[0].is_empty());
+    match value_type {
+        ValueType::None => is_empty.then_some(DynValue::None),
+        ValueType::EList(_) if is_empty => Some(DynValue::List(vec![])),
+        ValueType::List(item_type) | ValueType::EList(item_type) => {
+            if values.is_empty() {
+                return None;
+            }
+            values
+                .iter()
+                .map(|value| parse_list_item(item_type, value))
+                .collect::<Option<Vec<_>>>()
+                .map(DynValue::List)
+        }
+        ValueType::Either(a, b) => parse_values(a, values).or_else(|| parse_values(b, values)),
+        _ if values.len() == 1 => parse_value(value_type, &values[0]),
+        _ => None,
+    }
+}
+
+fn parse_list_item(item_type: &ValueType, value: &str) -> Option<DynValue> {
+    match item_type {
+        ValueType::Point if value.contains(':') => parse_value(
+            &ValueType::Compose(&ValueType::Point, &ValueType::Point),
+            value,
+        ),
+        ValueType::Stone if value.contains(':') => parse_value(
+            &ValueType::Compose(&ValueType::Stone, &ValueType::Stone),
+            value,
+        ),
+        _ => parse_value(item_type, value),
+    }
+}
+
+fn parse_value(value_type: &ValueType, value: &str) -> Option<DynValue> {
+    match value_type {
+        ValueType::None => value.is_empty().then_some(DynValue::None),
+        ValueType::Number => value.parse().ok().map(DynValue::Number),
+        ValueType::Real => value.parse().ok().map(DynValue::Real),
+        ValueType::Double => value.parse().ok().map(DynValue::Double),
+        ValueType::Color => value.parse().ok().map(DynValue::Color),
+        ValueType::SimpleText => Some(DynValue::SimpleText(value.into())),
+        ValueType::Text => Some(DynValue::Text(value.into())),
+        ValueType::Point => Some(DynValue::Point(value.to_string())),
+        ValueType::Move => Some(DynValue::Move(value.to_string())),
+        ValueType::Stone => Some(DynValue::Stone(value.to_string())),
+        ValueType::Compose(a, b) => {
+            let (first, second) = split_compose(value).ok()?;
+            Some(DynValue::Compose(
+                Box::new(parse_value(a, &first)?),
+                Box::new(parse_value(b, &second)?),
+            ))
+        }
+        ValueType::Either(a, b) => parse_value(a, value).or_else(|| parse_value(b, value)),
+        ValueType::List(_) | ValueType::EList(_) => None,
+    }
+}
+
+fn format_value(value: &DynValue) -> String {
+    match value {
+        DynValue::None => String::new(),
+        DynValue::Number(x) => x.to_string(),
+        DynValue::Real(x) => x.to_string(),
+        DynValue::Double(x) => x.to_sgf(),
+        DynValue::Color(x) => x.to_sgf(),
+        DynValue::SimpleText(x) => x.text.clone(),
+        DynValue::Text(x) => x.text.clone(),
+        DynValue::Point(x) | DynValue::Move(x) | DynValue::Stone(x) => x.clone(),
+        DynValue::List(items) => items.iter().map(format_value).collect::<Vec<_>>().join(","),
+        DynValue::Compose(a, b) => {
+            // Colons in either half are escaped to tell them apart from the separator.
+            let escape = |value: &DynValue| {
+                format_value(value)
+                    .replace('\\', "\\\\")
+                    .replace(':', "\\:")
+            };
+            format!("{}:{}", escape(a), escape(b))
+        }
+        DynValue::Raw(values) => values.join(","),
+    }
+}
+
+impl From<&go::Prop> for DynProp {
+    fn from(prop: &go::Prop) -> Self {
+        Self::new(GameType::Go, prop.identifier(), prop.raw_values())
+    }
+}
+
+impl From<&unknown_game::Prop> for DynProp {
+    fn from(prop: &unknown_game::Prop) -> Self {
+        Self::new(GameType::Unknown, prop.identifier(), prop.raw_values())
+    }
+}
+
+impl From<DynProp> for go::Prop {
+    fn from(prop: DynProp) -> Self {
+        let values = prop.values();
+        Self::from_values(prop.identifier, values)
+    }
+}
+
+impl From<DynProp> for unknown_game::Prop {
+    fn from(prop: DynProp) -> Self {
+        let values = prop.values();
+        Self::from_values(prop.identifier, values)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DynProp, DynValue};
+    use crate::{go, unknown_game, SgfProp};
+
+    #[test]
+    fn go_round_trip() {
+        let sgf = "(;SZ[19]KM[6.5]FG[]AP[App:1.0]VW[]C[Escaped \\] bracket];\
+                   B[aa]LB[bb:Label]AR[aa:cc]TB[ab][ba]FOO[bar][baz])";
+        for node in go::parse(sgf).unwrap()[0].main_variation() {
+            for prop in node.properties() {
+                let dyn_prop = DynProp::from(prop);
+                let is_raw = matches!(dyn_prop.value, DynValue::Raw(_));
+                assert_eq!(is_raw, dyn_prop.identifier == "FOO", "{}", prop);
+                assert_eq!(&go::Prop::from(dyn_prop), prop);
+            }
+        }
+    }
+
+    #[test]
+    fn unknown_game_compressed_list() {
+        let prop = unknown_game::Prop::new("MA".to_string(), vec!["a:b".to_string()]);
+        let dyn_prop = DynProp::from(&prop);
+        assert_eq!(
+            dyn_prop.value,
+            DynValue::List(vec![DynValue::Compose(
+                Box::new(DynValue::Point("a".to_string())),
+                Box::new(DynValue::Point("b".to_string()))
+            )])
+        );
+        assert_eq!(unknown_game::Prop::from(dyn_prop), prop);
+    }
+}
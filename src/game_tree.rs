@@ -1,15 +1,22 @@
 use std::fmt::Debug;
 
-use crate::{go, unknown_game, SgfNode, SgfParseError};
+use crate::{
+    go, unknown_game, DynGameNode, InvalidNodeError, SgfNode, SgfParseError, SgfParseErrorKind,
+    SgfProp,
+};
 
 /// The game recorded in a [`GameTree`].
 ///
 /// Any [`GameTree`] retured by [`parse`](`crate::parse`) will have a game type which corresponds to
 /// the SGF `GM` property of the root node.
+///
+/// More games may be supported in the future, so matches must include a wildcard arm.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
 pub enum GameType {
     Go,
     Unknown,
+    Custom,
 }
 
 /// An SGF [GameTree](https://www.red-bean.com/sgf/sgf4.html#ebnf-def) value.
@@ -19,11 +26,16 @@ pub enum GameType {
 /// [`serialize`](`crate::serialize()`) function will need to build these.
 ///
 /// For now, all non-Go games will parse as [`GameTree::Unknown`] which should also be used for any
-/// serialization of non-Go games.
+/// serialization of non-Go games, unless a parser is registered for them in
+/// [`ParseOptions::custom_games`](`crate::ParseOptions::custom_games`).
+///
+/// More games may be supported in the future, so matches must include a wildcard arm.
 #[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
 pub enum GameTree {
     GoGame(SgfNode<go::Prop>),
     Unknown(SgfNode<unknown_game::Prop>),
+    Custom(Box<dyn DynGameNode>),
 }
 
 impl GameTree {
@@ -44,7 +56,7 @@ impl GameTree {
     pub fn into_go_node(self) -> Result<SgfNode<go::Prop>, SgfParseError> {
         match self {
             Self::GoGame(sgf_node) => Ok(sgf_node),
-            _ => Err(SgfParseError::UnexpectedGameType),
+            _ => Err(SgfParseErrorKind::UnexpectedGameType.into()),
         }
     }
 
@@ -61,6 +73,264 @@ impl GameTree {
         match self {
             Self::GoGame(_) => GameType::Go,
             Self::Unknown(_) => GameType::Unknown,
+            Self::Custom(_) => GameType::Custom,
+        }
+    }
+
+    /// Returns the depth along the main variation of the first node with game info properties.
+    ///
+    /// The root node has depth 0. See [`SgfNode::game_info_node`]. Always returns `None` for
+    /// [`GameTree::Custom`] values.
+    ///
+    /// # Examples
+    /// ```
+    /// use sgf_parse::parse;
+    ///
+    /// let gametree = parse("(;GM[1];AB[dd];PB[Lee]PW[Gu];W[pp])").unwrap().remove(0);
+    /// assert_eq!(gametree.game_info_depth(), Some(2));
+    /// ```
+    pub fn game_info_depth(&self) -> Option<usize> {
+        fn depth<Prop: SgfProp>(node: &SgfNode<Prop>) -> Option<usize> {
+            let game_info_node = node.game_info_node()?;
+            node.main_variation()
+                .position(|n| std::ptr::eq(n, game_info_node))
+        }
+
+        match self {
+            Self::GoGame(node) => depth(node),
+            Self::Unknown(node) => depth(node),
+            Self::Custom(_) => None,
+        }
+    }
+
+    /// Returns a one line human readable summary of the game.
+    ///
+    /// The summary includes the game type, board size, players, result, and number of moves
+    /// in the main variation. Missing information is skipped.
+    ///
+    /// # Examples
+    /// ```
+    /// use sgf_parse::parse;
+    ///
+    /// let sgf = "(;GM[1]SZ[19]PB[Lee]PW[Gu]RE[W+R];B[pd];W[dd];B[pp])";
+    /// let gametree = parse(sgf).unwrap().into_iter().next().unwrap();
+    /// assert_eq!(gametree.describe(), "Go, 19x19, Lee vs Gu, W+R, 3 moves");
+    /// ```
+    pub fn describe(&self) -> String {
+        macro_rules! describe_node {
+            ($node:expr, $game:ident, $size:expr) => {{
+                let node = $node;
+                let game_info_node = node.game_info_node().unwrap_or(node);
+                let text_prop = |identifier| match game_info_node.get_property(identifier) {
+                    Some($game::Prop::PB(text) | $game::Prop::PW(text) | $game::Prop::RE(text)) => {
+                        Some(text.to_string())
+                    }
+                    _ => None,
+                };
+                let move_count = node
+                    .main_variation()
+                    .filter(|n| n.get_property("B").is_some() || n.get_property("W").is_some())
+                    .count();
+                (
+                    $size,
+                    text_prop("PB"),
+                    text_prop("PW"),
+                    text_prop("RE"),
+                    move_count,
+                )
+            }};
+        }
+
+        let (size, black, white, result, move_count) = match self {
+            Self::GoGame(node) => {
+                let (width, height) = node.board_size();
+                describe_node!(node, go, Some(format!("{}x{}", width, height)))
+            }
+            Self::Unknown(node) => {
+                let size = match node.get_property("SZ") {
+                    Some(unknown_game::Prop::SZ(size)) => Some(match size.split_once(':') {
+                        Some((width, height)) => format!("{}x{}", width, height),
+                        None => format!("{}x{}", size, size),
+                    }),
+                    _ => None,
+                };
+                describe_node!(node, unknown_game, size)
+            }
+            Self::Custom(_) => return self.gametype().to_string(),
+        };
+        let mut parts = vec![self.gametype().to_string()];
+        parts.extend(size);
+        if black.is_some() || white.is_some() {
+            parts.push(format!(
+                "{} vs {}",
+                black.as_deref().unwrap_or("?"),
+                white.as_deref().unwrap_or("?")
+            ));
+        }
+        parts.extend(result);
+        parts.push(match move_count {
+            1 => "1 move".to_string(),
+            n => format!("{} moves", n),
+        });
+        parts.join(", ")
+    }
+
+    /// Returns the game tree serialized as SGF if it's valid.
+    ///
+    /// The tree is checked with [`SgfNode::validate`] (or [`DynGameNode::validate`] for custom
+    /// games). If `max_label_length` is set, labels longer than that many characters are also
+    /// rejected, as with [`SgfNode::long_labels`].
+    ///
+    /// # Errors
+    /// Returns the first problem found instead of serializing an invalid tree.
+    ///
+    /// # Examples
+    /// ```
+    /// use sgf_parse::{parse, InvalidNodeError};
+    ///
+    /// let gametrees = parse("(;B[de]LB[aa:Label])(;B[de]W[ee])").unwrap();
+    /// assert_eq!(
+    ///     gametrees[0].serialize_validated(None),
+    ///     Ok("(;B[de]LB[aa:Label])".to_string())
+    /// );
+    /// assert!(matches!(
+    ///     gametrees[0].serialize_validated(Some(3)),
+    ///     Err(InvalidNodeError::LabelTooLong(_))
+    /// ));
+    /// assert!(matches!(
+    ///     gametrees[1].serialize_validated(None),
+    ///     Err(InvalidNodeError::MultipleMoves(_))
+    /// ));
+    /// ```
+    pub fn serialize_validated(
+        &self,
+        max_label_length: Option<usize>,
+    ) -> Result<String, InvalidNodeError> {
+        macro_rules! check_node {
+            ($node:expr) => {{
+                let node = $node;
+                node.validate()?;
+                if let Some(max_length) = max_label_length {
+                    if let Some((_, error)) = node.long_labels(max_length).into_iter().next() {
+                        return Err(error);
+                    }
+                }
+            }};
+        }
+
+        match self {
+            Self::GoGame(node) => check_node!(node),
+            Self::Unknown(node) => check_node!(node),
+            Self::Custom(node) => node.validate()?,
+        }
+        Ok(self.to_string())
+    }
+
+    /// Returns the approximate number of bytes used by the game tree.
+    ///
+    /// See [`SgfNode::memory_estimate`] for what's counted. Custom games use
+    /// [`DynGameNode::memory_estimate`].
+    ///
+    /// # Examples
+    /// ```
+    /// use sgf_parse::parse;
+    ///
+    /// let gametrees = parse("(;B[dd];W[pp])(;B[dd];W[pp]C[Long comment])").unwrap();
+    /// assert!(gametrees[0].memory_estimate() < gametrees[1].memory_estimate());
+    /// ```
+    pub fn memory_estimate(&self) -> usize {
+        match self {
+            Self::GoGame(node) => node.memory_estimate(),
+            Self::Unknown(node) => node.memory_estimate(),
+            Self::Custom(node) => node.memory_estimate(),
+        }
+    }
+
+    /// Returns the number of nodes in the game tree.
+    ///
+    /// See [`SgfNode::node_count`].
+    ///
+    /// # Examples
+    /// ```
+    /// use sgf_parse::parse;
+    ///
+    /// let gametree = parse("(;B[dd](;W[pp];B[pd])(;W[dp]))").unwrap().remove(0);
+    /// assert_eq!(gametree.node_count(), 4);
+    /// assert_eq!(gametree.max_depth(), 2);
+    /// assert_eq!(gametree.variation_count(), 2);
+    /// ```
+    pub fn node_count(&self) -> usize {
+        self.tree_metrics().0
+    }
+
+    /// Returns the depth of the deepest node in the game tree.
+    ///
+    /// See [`SgfNode::max_depth`].
+    pub fn max_depth(&self) -> usize {
+        self.tree_metrics().1
+    }
+
+    /// Returns the number of variations in the game tree.
+    ///
+    /// See [`SgfNode::variation_count`].
+    pub fn variation_count(&self) -> usize {
+        self.tree_metrics().2
+    }
+
+    /// Saves the game tree to a file.
+    ///
+    /// The file is replaced atomically and the text is encoded in the charset declared by the
+    /// root `CA` property. See [`save_to`](crate::save_to) for details.
+    ///
+    /// # Errors
+    /// Returns an error if the text can't be represented in the charset, or if writing the file
+    /// fails.
+    ///
+    /// # Examples
+    /// ```
+    /// use sgf_parse::parse;
+    ///
+    /// let gametree = parse("(;B[de]C[Hello])").unwrap().remove(0);
+    /// let path = std::env::temp_dir().join("sgf_parse_gametree_save_to_example.sgf");
+    /// gametree.save_to(&path).unwrap();
+    /// assert_eq!(std::fs::read_to_string(&path).unwrap(), "(;B[de]C[Hello])");
+    /// # std::fs::remove_file(&path).unwrap();
+    /// ```
+    pub fn save_to(&self, path: impl AsRef<std::path::Path>) -> std::io::Result<()> {
+        crate::save_to(std::slice::from_ref(self), path)
+    }
+
+    fn tree_metrics(&self) -> (usize, usize, usize) {
+        match self {
+            Self::GoGame(node) => node.tree_metrics(),
+            Self::Unknown(node) => node.tree_metrics(),
+            Self::Custom(node) => (node.node_count(), node.max_depth(), node.variation_count()),
+        }
+    }
+}
+
+impl std::fmt::Display for GameType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Go => f.write_str("Go"),
+            Self::Unknown => f.write_str("Unknown"),
+            Self::Custom => f.write_str("Custom"),
+        }
+    }
+}
+
+impl std::str::FromStr for GameType {
+    type Err = SgfParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.eq_ignore_ascii_case("go") {
+            Ok(Self::Go)
+        } else if s.eq_ignore_ascii_case("unknown") {
+            Ok(Self::Unknown)
+        } else if s.eq_ignore_ascii_case("custom") {
+            Ok(Self::Custom)
+        } else {
+            Err(SgfParseErrorKind::UnexpectedGameType.into())
         }
     }
 }
@@ -70,6 +340,7 @@ impl std::fmt::Display for GameTree {
         let node_text = match self {
             Self::GoGame(sgf_node) => sgf_node.serialize(),
             Self::Unknown(sgf_node) => sgf_node.serialize(),
+            Self::Custom(node) => node.serialize(),
         };
         std::fmt::Display::fmt(&node_text, f)
     }
@@ -86,3 +357,196 @@ impl std::convert::From<SgfNode<unknown_game::Prop>> for GameTree {
         Self::Unknown(sgf_node)
     }
 }
+
+/// Game trees from a collection, split up by game type.
+///
+/// Returned by [`partition_by_game`].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PartitionedCollection {
+    /// Go games, in their original order.
+    pub go: Vec<SgfNode<go::Prop>>,
+    /// Unknown games, in their original order.
+    pub unknown: Vec<SgfNode<unknown_game::Prop>>,
+    /// Custom games, in their original order.
+    pub custom: Vec<Box<dyn DynGameNode>>,
+    /// The game type and index in the corresponding `Vec` of each original game tree.
+    pub indices: Vec<(GameType, usize)>,
+}
+
+impl PartitionedCollection {
+    /// Returns the original position of the game tree at `index` in the `Vec` for `gametype`.
+    ///
+    /// # Examples
+    /// ```
+    /// use sgf_parse::{parse, partition_by_game, GameType};
+    ///
+    /// let gametrees = parse("(;GM[1])(;GM[2])(;GM[1])").unwrap();
+    /// let partitioned = partition_by_game(gametrees);
+    /// assert_eq!(partitioned.original_index(GameType::Go, 1), Some(2));
+    /// assert_eq!(partitioned.original_index(GameType::Unknown, 1), None);
+    /// ```
+    pub fn original_index(&self, gametype: GameType, index: usize) -> Option<usize> {
+        self.indices
+            .iter()
+            .position(|&entry| entry == (gametype, index))
+    }
+
+    /// Consumes the partitioned collection and returns the game trees in their original order.
+    ///
+    /// # Examples
+    /// ```
+    /// use sgf_parse::{parse, partition_by_game};
+    ///
+    /// let gametrees = parse("(;GM[1])(;GM[2])(;GM[1])").unwrap();
+    /// let partitioned = partition_by_game(gametrees.clone());
+    /// assert_eq!(partitioned.into_gametrees(), gametrees);
+    /// ```
+    pub fn into_gametrees(self) -> Vec<GameTree> {
+        let mut go = self.go.into_iter().map(GameTree::GoGame);
+        let mut unknown = self.unknown.into_iter().map(GameTree::Unknown);
+        let mut custom = self.custom.into_iter().map(GameTree::Custom);
+        self.indices
+            .iter()
+            .map(|(gametype, _)| match gametype {
+                GameType::Go => go.next(),
+                GameType::Unknown => unknown.next(),
+                GameType::Custom => custom.next(),
+            })
+            .collect::<Option<_>>()
+            .expect("Indices should match the partitioned game trees")
+    }
+}
+
+/// Splits a collection of game trees up by game type.
+///
+/// Useful for processing archives with a mix of games without matching on every [`GameTree`].
+///
+/// # Examples
+/// ```
+/// use sgf_parse::{parse, partition_by_game, GameType};
+///
+/// let gametrees = parse("(;GM[1];B[de])(;GM[37])(;GM[1];B[dd])").unwrap();
+/// let partitioned = partition_by_game(gametrees);
+/// assert_eq!(partitioned.go.len(), 2);
+/// assert_eq!(partitioned.unknown.len(), 1);
+/// assert_eq!(partitioned.indices[1], (GameType::Unknown, 0));
+/// assert_eq!(partitioned.indices[2], (GameType::Go, 1));
+/// ```
+pub fn partition_by_game(gametrees: Vec<GameTree>) -> PartitionedCollection {
+    let mut partitioned = PartitionedCollection::default();
+    for gametree in gametrees {
+        let gametype = gametree.gametype();
+        let index = match gametree {
+            GameTree::GoGame(node) => {
+                partitioned.go.push(node);
+                partitioned.go.len() - 1
+            }
+            GameTree::Unknown(node) => {
+                partitioned.unknown.push(node);
+                partitioned.unknown.len() - 1
+            }
+            GameTree::Custom(node) => {
+                partitioned.custom.push(node);
+                partitioned.custom.len() - 1
+            }
+        };
+        partitioned.indices.push((gametype, index));
+    }
+
+    partitioned
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{partition_by_game, GameType};
+    use crate::parse;
+
+    #[test]
+    fn gametype_round_trip() {
+        for gametype in [GameType::Go, GameType::Unknown, GameType::Custom] {
+            assert_eq!(gametype.to_string().parse::<GameType>(), Ok(gametype));
+        }
+        assert!("chess".parse::<GameType>().is_err());
+    }
+
+    #[test]
+    fn describe_partial_info() {
+        let gametrees = parse("(;GM[1]PW[Gu];B[pd])(;GM[37]SZ[8])").unwrap();
+        assert_eq!(gametrees[0].describe(), "Go, 19x19, ? vs Gu, 1 move");
+        assert_eq!(gametrees[1].describe(), "Unknown, 8x8, 0 moves");
+    }
+
+    #[test]
+    fn describe_late_game_info() {
+        let gametrees = parse("(;GM[1]SZ[9];AB[cc];PB[Lee]RE[B+R];W[dd];B[ee])").unwrap();
+        assert_eq!(gametrees[0].game_info_depth(), Some(2));
+        assert_eq!(gametrees[0].describe(), "Go, 9x9, Lee vs ?, B+R, 2 moves");
+    }
+
+    #[test]
+    fn serialize_validated_custom_game() {
+        use crate::{DynGameNode, GameTree, InvalidNodeError};
+
+        #[derive(Clone, Debug)]
+        struct Game(bool);
+
+        impl DynGameNode for Game {
+            fn serialize(&self) -> String {
+                "(;GM[3])".to_string()
+            }
+
+            fn as_any(&self) -> &dyn std::any::Any {
+                self
+            }
+
+            fn clone_box(&self) -> Box<dyn DynGameNode> {
+                Box::new(self.clone())
+            }
+
+            fn node_count(&self) -> usize {
+                1
+            }
+
+            fn max_depth(&self) -> usize {
+                0
+            }
+
+            fn variation_count(&self) -> usize {
+                1
+            }
+
+            fn validate(&self) -> Result<(), InvalidNodeError> {
+                match self.0 {
+                    true => Ok(()),
+                    false => Err(InvalidNodeError::InvalidProperty(String::new())),
+                }
+            }
+        }
+
+        let valid = GameTree::Custom(Box::new(Game(true)));
+        assert_eq!(
+            valid.serialize_validated(Some(1)),
+            Ok("(;GM[3])".to_string())
+        );
+        let invalid = GameTree::Custom(Box::new(Game(false)));
+        assert!(invalid.serialize_validated(None).is_err());
+    }
+
+    #[test]
+    fn partition_mixed_collection() {
+        let gametrees = parse("(;GM[37])(;GM[1])(;GM[37];C[x])(;GM[1];B[aa])").unwrap();
+        let partitioned = partition_by_game(gametrees.clone());
+        assert_eq!(
+            partitioned.indices,
+            vec![
+                (GameType::Unknown, 0),
+                (GameType::Go, 0),
+                (GameType::Unknown, 1),
+                (GameType::Go, 1),
+            ]
+        );
+        assert_eq!(partitioned.unknown[1].serialize(), "(;GM[37];C[x])");
+        assert_eq!(partitioned.original_index(GameType::Go, 1), Some(3));
+        assert_eq!(partitioned.into_gametrees(), gametrees);
+    }
+}
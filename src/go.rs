@@ -1,19 +1,32 @@
 //! Types specific to the game of Go.
 //!
 //! This module contains a go-specific [`SgfProp`] implementation which
-//! includes go specific properties (HA, KM, TB, TW). Point and Stone values
-//! map to [`Point`], and Move values map to [`Move`]. Properties with
+//! includes go specific properties (HA, KM, TB, TW). Point values map to
+//! [`Point`], Stone values map to [`Stone`], and Move values map to [`Move`]. Properties with
 //! invalid moves or points map to [`Prop::Invalid`] (as do any invalid
 //! [general properties](https://www.red-bean.com/sgf/properties.html)).
 //!
 //! This module also includes a convenience [`parse`] function which fails
 //! on non-go games and returns the [`SgfNode`] values directly instead of
 //! returning [`GameTree`](crate::GameTree) values.
+mod game_builder;
+mod merge;
+mod overlay;
+mod position;
+mod random;
+
 use std::collections::HashSet;
+use std::convert::TryFrom;
+
+use crate::props::parse::FromCompressedList;
+use crate::props::{PropertyType, Sealed, SgfPropError, ToSgf};
+use crate::{Color, InvalidNodeError, NodePath, SgfNode, SgfParseError, SgfProp};
 
-use crate::props::parse::{parse_elist, parse_single_value, FromCompressedList};
-use crate::props::{PropertyType, SgfPropError, ToSgf};
-use crate::{InvalidNodeError, SgfNode, SgfParseError, SgfProp};
+pub use game_builder::{GameBuilder, GameBuilderError};
+pub use merge::{merge_review, MergeReport};
+pub use overlay::{point_statuses, PointStatus};
+pub use position::{IllegalMoveError, Position};
+pub use random::{generate_random_game, RandomGameOptions};
 
 /// Returns the [`SgfNode`] values for Go games parsed from the provided text.
 ///
@@ -42,14 +55,215 @@ pub fn parse(text: &str) -> Result<Vec<SgfNode<Prop>>, SgfParseError> {
         .collect::<Result<Vec<_>, _>>()
 }
 
+/// Returns the color of the player to move after the last of the provided nodes.
+///
+/// The nodes should be the path from the root node to the node of interest. Black moves first
+/// unless there's a handicap (`HA` of 2 or more) in which case white moves first. After that
+/// players alternate from the last `B` or `W` move, and any `PL` property takes precedence.
+///
+/// # Examples
+/// ```
+/// use sgf_parse::Color;
+/// use sgf_parse::go::{next_player, parse};
+///
+/// let node = parse("(;HA[2]AB[dd][pp];W[pd];B[dp])").unwrap().into_iter().next().unwrap();
+/// assert_eq!(next_player(node.main_variation().take(1)), Color::White);
+/// assert_eq!(next_player(node.main_variation()), Color::White);
+///
+/// let node = parse("(;AB[dd]PL[W];AW[pp]PL[B])").unwrap().into_iter().next().unwrap();
+/// assert_eq!(next_player(node.main_variation()), Color::Black);
+/// ```
+pub fn next_player<'a>(nodes: impl IntoIterator<Item = &'a SgfNode<Prop>>) -> Color {
+    nodes
+        .into_iter()
+        .fold(Color::Black, |next, node| player_after(node, next))
+}
+
+// Returns the color of the player to move after the node, given the player to move before it.
+fn player_after(node: &SgfNode<Prop>, mut next: Color) -> Color {
+    if let Some(Prop::HA(handicap)) = node.get_property("HA") {
+        if *handicap >= 2 {
+            next = Color::White;
+        }
+    }
+    if let Some((color, _)) = node.get_move_with_color() {
+        next = match color {
+            Color::Black => Color::White,
+            Color::White => Color::Black,
+        };
+    }
+    if let Some(Prop::PL(color)) = node.get_property("PL") {
+        next = *color;
+    }
+    next
+}
+
+/// Returns the star points (hoshi) for a board of the given size, ordered by row then column.
+///
+/// Boards of 13 lines or more have star points on the 4-4 points, and smaller boards (of at
+/// least 7 lines) on the 3-3 points. Odd dimensions of 15 lines or more add side star points,
+/// and tengen is included when both dimensions are odd. This gives the standard 9 points on
+/// 19x19 and 5 points on 13x13 and 9x9.
+///
+/// # Examples
+/// ```
+/// use sgf_parse::go::{star_points, Point};
+///
+/// assert_eq!(star_points((19, 19)).len(), 9);
+/// assert_eq!(
+///     star_points((9, 9)),
+///     vec![
+///         Point { x: 2, y: 2 },
+///         Point { x: 6, y: 2 },
+///         Point { x: 4, y: 4 },
+///         Point { x: 2, y: 6 },
+///         Point { x: 6, y: 6 },
+///     ]
+/// );
+/// ```
+pub fn star_points(board_size: (u8, u8)) -> Vec<Point> {
+    fn lines(size: u8) -> Vec<u8> {
+        let edge = match size {
+            0..=6 => return vec![],
+            7..=12 => 2,
+            _ => 3,
+        };
+        let mut lines = vec![edge, size - 1 - edge];
+        if size >= 15 && size % 2 == 1 {
+            lines.insert(1, size / 2);
+        }
+        lines
+    }
+
+    let (width, height) = board_size;
+    let mut points = vec![];
+    for y in lines(height) {
+        for x in lines(width) {
+            points.push(Point { x, y });
+        }
+    }
+    if width % 2 == 1 && height % 2 == 1 {
+        let tengen = Point {
+            x: width / 2,
+            y: height / 2,
+        };
+        if !points.contains(&tengen) {
+            points.push(tengen);
+        }
+    }
+    points.sort();
+    points
+}
+
+/// Returns a minimal SGF game record for a sequence of moves.
+///
+/// The root node has `GM`, `FF`, `SZ`, and `KM` properties, followed by one node per move.
+///
+/// # Examples
+/// ```
+/// use sgf_parse::Color;
+/// use sgf_parse::go::{sgf_from_moves, Move};
+///
+/// let moves = [
+///     (Color::Black, Move::Move("pd".parse().unwrap())),
+///     (Color::White, Move::Move("dp".parse().unwrap())),
+///     (Color::Black, Move::Pass),
+/// ];
+/// assert_eq!(
+///     sgf_from_moves((19, 19), 6.5, &moves),
+///     "(;GM[1]FF[4]SZ[19:19]KM[6.5];B[pd];W[dp];B[])"
+/// );
+/// ```
+pub fn sgf_from_moves(board_size: (u8, u8), komi: f64, moves: &[(Color, Move)]) -> String {
+    // Build from the last move back so each node can own its child.
+    let mut children = vec![];
+    for (color, mv) in moves.iter().rev() {
+        let prop = match color {
+            Color::Black => Prop::B(*mv),
+            Color::White => Prop::W(*mv),
+        };
+        children = vec![SgfNode::new(vec![prop], children, false)];
+    }
+    let properties = vec![
+        Prop::GM(1),
+        Prop::FF(4),
+        Prop::SZ(board_size),
+        Prop::KM(komi),
+    ];
+    SgfNode::new(properties, children, true).serialize()
+}
+
+/// Returns the color of the first player to move in a game with the provided handicap.
+///
+/// White moves first in games with a handicap (`HA`) of 2 or more, since black's handicap stones
+/// are placed as setup. Otherwise black moves first. This matches [`next_player`] at the root.
+///
+/// # Examples
+/// ```
+/// use sgf_parse::Color;
+/// use sgf_parse::go::first_player_for_handicap;
+///
+/// assert_eq!(first_player_for_handicap(0), Color::Black);
+/// assert_eq!(first_player_for_handicap(4), Color::White);
+/// ```
+pub fn first_player_for_handicap(handicap: i64) -> Color {
+    if handicap >= 2 {
+        Color::White
+    } else {
+        Color::Black
+    }
+}
+
+/// Returns the provided moves paired with alternating colors, starting with `first_player`.
+///
+/// This is useful for importing formats which record moves without colors. Use
+/// [`first_player_for_handicap`] when the format only records the handicap. The result can be
+/// passed to [`sgf_from_moves`].
+///
+/// # Examples
+/// ```
+/// use sgf_parse::Color;
+/// use sgf_parse::go::{alternate_colors, first_player_for_handicap, Move};
+///
+/// let moves = vec![Move::Move("pd".parse().unwrap()), Move::Pass];
+/// let colored = alternate_colors(moves, first_player_for_handicap(2));
+/// assert_eq!(
+///     colored,
+///     vec![
+///         (Color::White, Move::Move("pd".parse().unwrap())),
+///         (Color::Black, Move::Pass),
+///     ]
+/// );
+/// ```
+pub fn alternate_colors(
+    moves: impl IntoIterator<Item = Move>,
+    first_player: Color,
+) -> Vec<(Color, Move)> {
+    let mut color = first_player;
+    moves
+        .into_iter()
+        .map(|mv| {
+            let colored = (color, mv);
+            color = match color {
+                Color::Black => Color::White,
+                Color::White => Color::Black,
+            };
+            colored
+        })
+        .collect()
+}
+
 /// An SGF [Point](https://www.red-bean.com/sgf/go.html#types) value for the Game of Go.
 ///
+/// Points are ordered row by row: by `y`, then by `x`.
+///
 /// # Examples
 /// ```
 /// use sgf_parse::go::{Prop, Move, Point};
 ///
 /// let point = Point {x: 10, y: 10};
 /// let prop = Prop::B(Move::Move(point));
+/// assert!(Point { x: 0, y: 1 } > Point { x: 1, y: 0 });
 /// ```
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
 pub struct Point {
@@ -58,10 +272,47 @@ pub struct Point {
 }
 
 /// An SGF [Stone](https://www.red-bean.com/sgf/go.html#types) value for the Game of Go.
-pub type Stone = Point;
+///
+/// For standard Go a stone is just the [`Point`] it's placed on. `Stone` dereferences to its
+/// [`Point`], and converts to and from [`Point`] with [`From`].
+///
+/// # Examples
+/// ```
+/// use sgf_parse::go::{Point, Prop, Stone};
+///
+/// let stone = Stone::from(Point { x: 3, y: 3 });
+/// assert_eq!(stone.x, 3);
+/// let prop = Prop::AB(vec![stone].into_iter().collect());
+/// ```
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct Stone(pub Point);
+
+/// A rectangle of points as represented in an SGF
+/// [compressed point list](https://www.red-bean.com/sgf/sgf4.html#3.5.1).
+///
+/// Point list properties are stored as expanded sets of points. Use [`PointRect::cover`] to
+/// recover rectangles from a set (for instance to draw shaded regions) and
+/// [`PointRect::iter_points`] to expand a rectangle back into points.
+///
+/// # Examples
+/// ```
+/// use sgf_parse::go::{Point, PointRect};
+///
+/// let rect: PointRect = "aa:bc".parse().unwrap();
+/// assert_eq!(rect.ul, Point { x: 0, y: 0 });
+/// assert_eq!(rect.lr, Point { x: 1, y: 2 });
+/// assert_eq!(rect.iter_points().count(), 6);
+/// ```
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct PointRect {
+    pub ul: Point,
+    pub lr: Point,
+}
 
 /// An SGF [Move](https://www.red-bean.com/sgf/go.html#types) value for the Game of Go.
 ///
+/// Moves are ordered with [`Move::Pass`] first, followed by moves in [`Point`] order.
+///
 /// # Examples
 /// ```
 /// use sgf_parse::go::{parse, Move, Prop};
@@ -74,19 +325,36 @@ pub type Stone = Point;
 ///     }
 /// }
 /// ```
-#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, PartialOrd, Ord)]
 pub enum Move {
     Pass,
     Move(Point),
 }
 
 sgf_prop! {
-    Prop, Move, Point, Point,
-    {
-        HA(i64),
-        KM(f64),
-        TB(HashSet<Point>),
-        TW(HashSet<Point>),
+    Prop, Move, Point, Stone, (u8, u8), go_properties
+}
+
+fn parse_size(values: &[String]) -> Result<(u8, u8), SgfPropError> {
+    if values.len() != 1 {
+        return Err(SgfPropError {});
+    }
+    let value = &values[0];
+    if value.contains(':') {
+        crate::props::parse::parse_tuple(value)
+    } else {
+        let size = value.parse().map_err(|_| SgfPropError {})?;
+        Ok((size, size))
+    }
+}
+
+impl Sealed for Prop {
+    fn from_values(identifier: String, values: Vec<String>) -> Self {
+        Self::parse_prop(identifier, values)
+    }
+
+    fn raw_values(&self) -> Vec<String> {
+        self.prop_values()
     }
 }
 
@@ -95,54 +363,24 @@ impl SgfProp for Prop {
     type Stone = Stone;
     type Move = Move;
 
-    fn new(identifier: String, values: Vec<String>) -> Self {
-        match Prop::parse_general_prop(identifier, values) {
-            Self::Unknown(identifier, values) => match &identifier[..] {
-                "KM" => parse_single_value(&values)
-                    .map_or_else(|_| Self::Invalid(identifier, values), Self::KM),
-
-                "HA" => match parse_single_value(&values) {
-                    Ok(value) => {
-                        if value < 2 {
-                            Self::Invalid(identifier, values)
-                        } else {
-                            Self::HA(value)
-                        }
-                    }
-                    _ => Self::Invalid(identifier, values),
-                },
-                "TB" => parse_elist(&values)
-                    .map_or_else(|_| Self::Invalid(identifier, values), Self::TB),
-                "TW" => parse_elist(&values)
-                    .map_or_else(|_| Self::Invalid(identifier, values), Self::TW),
-                _ => Self::Unknown(identifier, values),
-            },
-            prop => prop,
-        }
+    fn identifier(&self) -> String {
+        self.identifier_str().to_string()
     }
 
-    fn identifier(&self) -> String {
-        match self.general_identifier() {
-            Some(identifier) => identifier,
-            None => match self {
-                Self::KM(_) => "KM".to_string(),
-                Self::HA(_) => "HA".to_string(),
-                Self::TB(_) => "TB".to_string(),
-                Self::TW(_) => "TW".to_string(),
-                _ => panic!("Unimplemented identifier for {:?}", self),
-            },
-        }
+    fn identifier_str(&self) -> &str {
+        self.prop_identifier()
     }
 
     fn property_type(&self) -> Option<PropertyType> {
-        match self.general_property_type() {
-            Some(property_type) => Some(property_type),
-            None => match self {
-                Self::HA(_) => Some(PropertyType::GameInfo),
-                Self::KM(_) => Some(PropertyType::GameInfo),
-                _ => None,
-            },
-        }
+        self.prop_type()
+    }
+
+    fn is_unknown(&self) -> bool {
+        matches!(self, Self::Unknown(_, _))
+    }
+
+    fn is_invalid(&self) -> bool {
+        matches!(self, Self::Invalid(_, _))
     }
 
     fn validate_properties(properties: &[Self], is_root: bool) -> Result<(), InvalidNodeError> {
@@ -150,38 +388,539 @@ impl SgfProp for Prop {
     }
 }
 
+impl Prop {
+    /// Returns the color of the player making the move for `B` and `W` properties.
+    ///
+    /// # Examples
+    /// ```
+    /// use sgf_parse::{Color, SgfProp};
+    /// use sgf_parse::go::Prop;
+    ///
+    /// let prop = Prop::new("W".to_string(), vec!["de".to_string()]);
+    /// assert_eq!(prop.move_color(), Some(Color::White));
+    /// let prop = Prop::new("C".to_string(), vec!["comment".to_string()]);
+    /// assert_eq!(prop.move_color(), None);
+    /// ```
+    pub fn move_color(&self) -> Option<Color> {
+        match self {
+            Self::B(_) => Some(Color::Black),
+            Self::W(_) => Some(Color::White),
+            _ => None,
+        }
+    }
+
+    /// Returns a `B` or `W` property for a move, checking that it's on a board of the given size.
+    ///
+    /// # Errors
+    /// Returns an error if the move is off the board.
+    ///
+    /// # Examples
+    /// ```
+    /// use sgf_parse::Color;
+    /// use sgf_parse::go::{Move, Point, Prop};
+    ///
+    /// let mv = Move::Move(Point { x: 2, y: 2 });
+    /// assert_eq!(Prop::new_move(Color::White, mv, (9, 9)), Ok(Prop::W(mv)));
+    /// assert_eq!(Prop::new_move(Color::Black, Move::Pass, (9, 9)), Ok(Prop::B(Move::Pass)));
+    /// assert!(Prop::new_move(Color::Black, mv, (2, 2)).is_err());
+    /// ```
+    pub fn new_move(color: Color, mv: Move, board_size: (u8, u8)) -> Result<Self, SgfPropError> {
+        if let Move::Move(point) = mv {
+            if !point.is_on_board(board_size) {
+                return Err(SgfPropError {});
+            }
+        }
+        Ok(match color {
+            Color::Black => Self::B(mv),
+            Color::White => Self::W(mv),
+        })
+    }
+
+    /// Returns an `AB` or `AW` property adding stones, checking that they're on a board of the
+    /// given size.
+    ///
+    /// # Errors
+    /// Returns an error if there are no stones, or any stone is off the board.
+    ///
+    /// # Examples
+    /// ```
+    /// use sgf_parse::Color;
+    /// use sgf_parse::go::{Point, Prop, Stone};
+    ///
+    /// let stones = vec![Stone(Point { x: 2, y: 2 }), Stone(Point { x: 6, y: 6 })];
+    /// let prop = Prop::new_setup_stones(Color::Black, stones.clone(), (9, 9)).unwrap();
+    /// assert_eq!(prop, Prop::AB(stones.iter().copied().collect()));
+    /// assert!(Prop::new_setup_stones(Color::White, stones, (5, 5)).is_err());
+    /// assert!(Prop::new_setup_stones(Color::White, vec![], (5, 5)).is_err());
+    /// ```
+    pub fn new_setup_stones(
+        color: Color,
+        stones: impl IntoIterator<Item = Stone>,
+        board_size: (u8, u8),
+    ) -> Result<Self, SgfPropError> {
+        let stones: HashSet<Stone> = stones.into_iter().collect();
+        if stones.is_empty() || stones.iter().any(|stone| !stone.is_on_board(board_size)) {
+            return Err(SgfPropError {});
+        }
+        Ok(match color {
+            Color::Black => Self::AB(stones),
+            Color::White => Self::AW(stones),
+        })
+    }
+}
+
+impl SgfNode<Prop> {
+    /// Returns the color and value of the move on the node (if present).
+    ///
+    /// # Examples
+    /// ```
+    /// use sgf_parse::Color;
+    /// use sgf_parse::go::{parse, Move};
+    ///
+    /// let node = parse("(;B[de]C[Comment])").unwrap().into_iter().next().unwrap();
+    /// assert_eq!(
+    ///     node.get_move_with_color(),
+    ///     Some((Color::Black, Move::Move("de".parse().unwrap())))
+    /// );
+    /// ```
+    pub fn get_move_with_color(&self) -> Option<(Color, Move)> {
+        self.properties().find_map(|prop| match prop {
+            Prop::B(mv) => Some((Color::Black, *mv)),
+            Prop::W(mv) => Some((Color::White, *mv)),
+            _ => None,
+        })
+    }
+
+    /// Adds a child node with a `B` or `W` move and returns a reference to it.
+    ///
+    /// # Examples
+    /// ```
+    /// use sgf_parse::Color;
+    /// use sgf_parse::go::{parse, Move};
+    ///
+    /// let mut node = parse("(;SZ[9])").unwrap().remove(0);
+    /// node.append_move(Color::Black, Move::Move("ee".parse().unwrap()))
+    ///     .append_move(Color::White, Move::Pass);
+    /// assert_eq!(node.serialize(), "(;SZ[9:9];B[ee];W[])");
+    /// ```
+    pub fn append_move(&mut self, color: Color, mv: Move) -> &mut Self {
+        let prop = match color {
+            Color::Black => Prop::B(mv),
+            Color::White => Prop::W(mv),
+        };
+        self.add_child(SgfNode::new(vec![prop], vec![], false))
+    }
+
+    /// Like [`SgfNode::append_move`], but appends to the node at `path` and checks that the move
+    /// is by the player to move there.
+    ///
+    /// The expected player is found with [`next_player`] from the nodes along the path, so
+    /// handicaps, earlier moves and `PL` properties are all taken into account.
+    ///
+    /// # Errors
+    /// Returns [`AppendMoveError::InvalidPath`] if there's no node at the path, or
+    /// [`AppendMoveError::OutOfTurn`] with the expected player if the move is out of turn. The
+    /// node is left unchanged.
+    ///
+    /// # Examples
+    /// ```
+    /// use sgf_parse::{Color, NodePath};
+    /// use sgf_parse::go::{parse, AppendMoveError, Move};
+    ///
+    /// let mut node = parse("(;HA[2]AB[dd][pp];W[dp])").unwrap().remove(0);
+    /// assert_eq!(
+    ///     node.append_move_checked(&NodePath::root(), Color::Black, Move::Pass).err(),
+    ///     Some(AppendMoveError::OutOfTurn(Color::White))
+    /// );
+    /// let path = NodePath::from(vec![0]);
+    /// assert!(node.append_move_checked(&path, Color::Black, Move::Pass).is_ok());
+    /// assert_eq!(node.main_variation().count(), 3);
+    /// ```
+    pub fn append_move_checked(
+        &mut self,
+        path: &NodePath,
+        color: Color,
+        mv: Move,
+    ) -> Result<&mut Self, AppendMoveError> {
+        let mut nodes = vec![&*self];
+        for &index in path.iter() {
+            let node = nodes[nodes.len() - 1].children.get(index);
+            nodes.push(node.ok_or(AppendMoveError::InvalidPath)?);
+        }
+        let expected = next_player(nodes);
+        if expected != color {
+            return Err(AppendMoveError::OutOfTurn(expected));
+        }
+        let node = self.node_at_mut(path).expect("Path should lead to a node");
+        Ok(node.append_move(color, mv))
+    }
+
+    /// Returns the komi from the `KM` property (if present).
+    ///
+    /// The property is read from the [game info node](`SgfNode::game_info_node`).
+    ///
+    /// # Examples
+    /// ```
+    /// use sgf_parse::go::parse;
+    ///
+    /// let node = parse("(;KM[6.5])").unwrap().into_iter().next().unwrap();
+    /// assert_eq!(node.komi(), Some(6.5));
+    /// ```
+    pub fn komi(&self) -> Option<f64> {
+        match self.game_info_node()?.get_property("KM") {
+            Some(Prop::KM(komi)) => Some(*komi),
+            _ => None,
+        }
+    }
+
+    /// Returns the board size from the `SZ` property.
+    ///
+    /// Defaults to 19x19 when `SZ` is missing, as specified by the SGF standard.
+    ///
+    /// # Examples
+    /// ```
+    /// use sgf_parse::go::parse;
+    ///
+    /// let nodes = parse("(;SZ[9])(;B[dd])").unwrap();
+    /// assert_eq!(nodes[0].board_size(), (9, 9));
+    /// assert_eq!(nodes[1].board_size(), (19, 19));
+    /// ```
+    pub fn board_size(&self) -> (u8, u8) {
+        match self.get_property("SZ") {
+            Some(Prop::SZ(size)) => *size,
+            _ => (19, 19),
+        }
+    }
+
+    /// Returns the number of handicap stones from the `HA` property (if present).
+    ///
+    /// The property is read from the [game info node](`SgfNode::game_info_node`).
+    ///
+    /// # Examples
+    /// ```
+    /// use sgf_parse::go::parse;
+    ///
+    /// let node = parse("(;HA[3])").unwrap().into_iter().next().unwrap();
+    /// assert_eq!(node.handicap(), Some(3));
+    /// ```
+    pub fn handicap(&self) -> Option<i64> {
+        match self.game_info_node()?.get_property("HA") {
+            Some(Prop::HA(handicap)) => Some(*handicap),
+            _ => None,
+        }
+    }
+
+    /// Returns the setup properties (`AB`, `AW`, `AE` and `PL`) of the node.
+    ///
+    /// Missing or invalid properties leave the corresponding field empty.
+    ///
+    /// # Examples
+    /// ```
+    /// use sgf_parse::Color;
+    /// use sgf_parse::go::parse;
+    ///
+    /// let node = parse("(;AB[aa][bb]AE[cc]PL[W])").unwrap().remove(0);
+    /// let setup = node.get_setup();
+    /// assert!(setup.black.contains(&"bb".parse().unwrap()));
+    /// assert!(setup.white.is_empty());
+    /// assert_eq!(setup.empty.len(), 1);
+    /// assert_eq!(setup.player, Some(Color::White));
+    /// ```
+    pub fn get_setup(&self) -> Setup {
+        let mut setup = Setup::default();
+        for prop in self.properties() {
+            match prop {
+                Prop::AB(stones) => setup.black.extend(stones.iter().map(|stone| stone.0)),
+                Prop::AW(stones) => setup.white.extend(stones.iter().map(|stone| stone.0)),
+                Prop::AE(points) => setup.empty.extend(points.iter().copied()),
+                Prop::PL(color) => setup.player = Some(*color),
+                _ => {}
+            }
+        }
+        setup
+    }
+}
+
+/// The setup properties of a node, as returned by [`SgfNode::get_setup`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Setup {
+    /// Points where black stones are added, from `AB`.
+    pub black: HashSet<Point>,
+    /// Points where white stones are added, from `AW`.
+    pub white: HashSet<Point>,
+    /// Points which are cleared, from `AE`.
+    pub empty: HashSet<Point>,
+    /// The player to move next, from `PL`.
+    pub player: Option<Color>,
+}
+
+impl Setup {
+    /// Returns `true` if the node has no setup properties.
+    pub fn is_empty(&self) -> bool {
+        self.black.is_empty()
+            && self.white.is_empty()
+            && self.empty.is_empty()
+            && self.player.is_none()
+    }
+}
+
+/// Error type for moves rejected by [`SgfNode::append_move_checked`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AppendMoveError {
+    /// There's no node at the provided path.
+    InvalidPath,
+    /// The move was made by the wrong player. Contains the player expected to move.
+    OutOfTurn(Color),
+}
+
+impl std::fmt::Display for AppendMoveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AppendMoveError::InvalidPath => write!(f, "No node at the provided path"),
+            AppendMoveError::OutOfTurn(color) => {
+                write!(f, "Move out of turn, {:?} to play", color)
+            }
+        }
+    }
+}
+
+impl std::error::Error for AppendMoveError {}
+
 impl std::fmt::Display for Prop {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let prop_string = match self.serialize_prop_value() {
-            Some(s) => s,
-            None => match self {
-                Self::HA(x) => x.to_sgf(),
-                Self::KM(x) => x.to_sgf(),
-                Self::TB(x) => x.to_sgf(),
-                Self::TW(x) => x.to_sgf(),
-                _ => panic!("Unimplemented identifier for {:?}", self),
-            },
-        };
-        write!(f, "{}[{}]", self.identifier(), prop_string)
+        write!(
+            f,
+            "{}[{}]",
+            self.identifier_str(),
+            self.serialize_prop_value()
+        )
     }
 }
 
-impl FromCompressedList for Point {
-    fn from_compressed_list(ul: &Self, lr: &Self) -> Result<HashSet<Self>, SgfPropError> {
-        let mut points = HashSet::new();
+impl PartialOrd for Point {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Point {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        (self.y, self.x).cmp(&(other.y, other.x))
+    }
+}
+
+impl Point {
+    /// Returns a new point, checking that it's on a board of the given size.
+    ///
+    /// # Errors
+    /// Returns an error if the point is off the board.
+    ///
+    /// # Examples
+    /// ```
+    /// use sgf_parse::go::Point;
+    ///
+    /// assert_eq!(Point::new_checked(3, 4, (9, 9)), Ok(Point { x: 3, y: 4 }));
+    /// assert!(Point::new_checked(9, 4, (9, 9)).is_err());
+    /// ```
+    pub fn new_checked(x: u8, y: u8, board_size: (u8, u8)) -> Result<Self, SgfPropError> {
+        let point = Self { x, y };
+        if !point.is_on_board(board_size) {
+            return Err(SgfPropError {});
+        }
+        Ok(point)
+    }
+
+    /// Returns whether the point is on a board of the given size.
+    ///
+    /// # Examples
+    /// ```
+    /// use sgf_parse::go::Point;
+    ///
+    /// assert!(Point { x: 8, y: 0 }.is_on_board((9, 9)));
+    /// assert!(!Point { x: 9, y: 0 }.is_on_board((9, 9)));
+    /// ```
+    pub fn is_on_board(&self, board_size: (u8, u8)) -> bool {
+        self.x < board_size.0 && self.y < board_size.1
+    }
+
+    /// Returns the point offset by `dx` and `dy`, or `None` if the coordinates don't fit.
+    ///
+    /// # Examples
+    /// ```
+    /// use sgf_parse::go::Point;
+    ///
+    /// let point = Point { x: 3, y: 3 };
+    /// assert_eq!(point.offset(-1, 2), Some(Point { x: 2, y: 5 }));
+    /// assert_eq!(point.offset(-4, 0), None);
+    /// ```
+    pub fn offset(&self, dx: i32, dy: i32) -> Option<Self> {
+        let x = u8::try_from(i32::from(self.x) + dx).ok()?;
+        let y = u8::try_from(i32::from(self.y) + dy).ok()?;
+        Some(Self { x, y })
+    }
+
+    /// Returns the [Manhattan distance](https://en.wikipedia.org/wiki/Taxicab_geometry) between
+    /// two points.
+    ///
+    /// # Examples
+    /// ```
+    /// use sgf_parse::go::Point;
+    ///
+    /// assert_eq!(Point { x: 3, y: 3 }.manhattan_distance(Point { x: 1, y: 6 }), 5);
+    /// ```
+    pub fn manhattan_distance(&self, other: Self) -> u32 {
+        u32::from(self.x.abs_diff(other.x)) + u32::from(self.y.abs_diff(other.y))
+    }
+
+    /// Returns whether the point is a star point on a board of the given size.
+    ///
+    /// See [`star_points`] for which points count.
+    ///
+    /// # Examples
+    /// ```
+    /// use sgf_parse::go::Point;
+    ///
+    /// assert!(Point { x: 3, y: 15 }.is_hoshi((19, 19)));
+    /// assert!(!Point { x: 3, y: 15 }.is_hoshi((13, 13)));
+    /// ```
+    pub fn is_hoshi(&self, board_size: (u8, u8)) -> bool {
+        star_points(board_size).contains(self)
+    }
+
+    /// Returns an iterator over the orthogonally adjacent points on a board of the given size.
+    ///
+    /// # Examples
+    /// ```
+    /// use sgf_parse::go::Point;
+    ///
+    /// let neighbors: Vec<_> = Point { x: 0, y: 0 }.neighbors((19, 19)).collect();
+    /// assert_eq!(neighbors, vec![Point { x: 1, y: 0 }, Point { x: 0, y: 1 }]);
+    /// ```
+    pub fn neighbors(&self, board_size: (u8, u8)) -> impl Iterator<Item = Self> {
+        let point = *self;
+        IntoIterator::into_iter([(-1, 0), (1, 0), (0, -1), (0, 1)])
+            .filter_map(move |(dx, dy)| point.offset(dx, dy))
+            .filter(move |p| p.is_on_board(board_size))
+    }
+}
+
+impl PointRect {
+    /// Returns a new rectangle from its upper left and lower right corners.
+    ///
+    /// # Errors
+    /// Returns an error if `ul` isn't above and to the left of `lr`.
+    ///
+    /// # Examples
+    /// ```
+    /// use sgf_parse::go::{Point, PointRect};
+    ///
+    /// let rect = PointRect::new(Point { x: 0, y: 0 }, Point { x: 2, y: 2 }).unwrap();
+    /// assert!(PointRect::new(Point { x: 2, y: 0 }, Point { x: 0, y: 2 }).is_err());
+    /// ```
+    pub fn new(ul: Point, lr: Point) -> Result<Self, SgfPropError> {
         if ul.x > lr.x || ul.y > lr.y {
             return Err(SgfPropError {});
         }
-        for x in ul.x..=lr.x {
-            for y in ul.y..=lr.y {
-                let point = Self { x, y };
-                if points.contains(&point) {
-                    return Err(SgfPropError {});
-                }
-                points.insert(point);
+        Ok(Self { ul, lr })
+    }
+
+    /// Returns an iterator over the points in the rectangle.
+    pub fn iter_points(&self) -> impl Iterator<Item = Point> {
+        let (ul, lr) = (self.ul, self.lr);
+        (ul.y..=lr.y).flat_map(move |y| (ul.x..=lr.x).map(move |x| Point { x, y }))
+    }
+
+    /// Returns whether the point falls within the rectangle.
+    pub fn contains(&self, point: &Point) -> bool {
+        (self.ul.x..=self.lr.x).contains(&point.x) && (self.ul.y..=self.lr.y).contains(&point.y)
+    }
+
+    /// Returns a list of disjoint rectangles covering exactly the provided points.
+    ///
+    /// Rectangles are found greedily scanning rows from the top left, so the result is
+    /// deterministic, but not necessarily minimal.
+    ///
+    /// # Examples
+    /// ```
+    /// use sgf_parse::go::{parse, Prop, PointRect};
+    ///
+    /// let node = parse("(;SQ[aa:cc][ee])").unwrap().into_iter().next().unwrap();
+    /// if let Some(Prop::SQ(points)) = node.get_property("SQ") {
+    ///     let rects = PointRect::cover(points);
+    ///     assert_eq!(rects, vec!["aa:cc".parse().unwrap(), "ee".parse().unwrap()]);
+    /// }
+    /// ```
+    pub fn cover(points: &HashSet<Point>) -> Vec<Self> {
+        let mut remaining = points.clone();
+        let mut sorted: Vec<Point> = points.iter().copied().collect();
+        sorted.sort_by_key(|p| (p.y, p.x));
+        let mut rects = vec![];
+        for start in sorted {
+            if !remaining.contains(&start) {
+                continue;
+            }
+            let mut lr = start;
+            while lr.x < u8::MAX
+                && remaining.contains(&Point {
+                    x: lr.x + 1,
+                    y: lr.y,
+                })
+            {
+                lr.x += 1;
+            }
+            while lr.y < u8::MAX
+                && (start.x..=lr.x).all(|x| remaining.contains(&Point { x, y: lr.y + 1 }))
+            {
+                lr.y += 1;
             }
+            let rect = Self { ul: start, lr };
+            for point in rect.iter_points() {
+                remaining.remove(&point);
+            }
+            rects.push(rect);
         }
-        Ok(points)
+        rects
+    }
+}
+
+impl FromCompressedList for Point {
+    fn from_compressed_list(ul: &Self, lr: &Self) -> Result<HashSet<Self>, SgfPropError> {
+        Ok(PointRect::new(*ul, *lr)?.iter_points().collect())
+    }
+}
+
+impl FromCompressedList for Stone {
+    fn from_compressed_list(ul: &Self, lr: &Self) -> Result<HashSet<Self>, SgfPropError> {
+        Ok(PointRect::new(ul.0, lr.0)?
+            .iter_points()
+            .map(Self)
+            .collect())
+    }
+}
+
+impl std::ops::Deref for Stone {
+    type Target = Point;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl From<Point> for Stone {
+    fn from(point: Point) -> Self {
+        Self(point)
+    }
+}
+
+impl From<Stone> for Point {
+    fn from(stone: Stone) -> Self {
+        stone.0
+    }
+}
+
+impl ToSgf for Stone {
+    fn to_sgf(&self) -> String {
+        self.0.to_sgf()
     }
 }
 
@@ -200,6 +939,47 @@ impl ToSgf for Point {
     }
 }
 
+impl ToSgf for PointRect {
+    fn to_sgf(&self) -> String {
+        if self.ul == self.lr {
+            self.ul.to_sgf()
+        } else {
+            format!("{}:{}", self.ul.to_sgf(), self.lr.to_sgf())
+        }
+    }
+}
+
+impl std::fmt::Display for PointRect {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.to_sgf())
+    }
+}
+
+impl std::str::FromStr for PointRect {
+    type Err = SgfPropError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.contains(':') {
+            let (ul, lr) = crate::props::parse::parse_tuple(s)?;
+            Self::new(ul, lr)
+        } else {
+            let point = s.parse()?;
+            Ok(Self {
+                ul: point,
+                lr: point,
+            })
+        }
+    }
+}
+
+impl std::str::FromStr for Stone {
+    type Err = SgfPropError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Self(s.parse()?))
+    }
+}
+
 impl std::str::FromStr for Move {
     type Err = SgfPropError;
 
@@ -239,7 +1019,272 @@ impl std::str::FromStr for Point {
 
 #[cfg(test)]
 mod tests {
-    use super::Point;
+    use std::collections::HashSet;
+
+    use super::{
+        alternate_colors, first_player_for_handicap, next_player, parse, sgf_from_moves,
+        star_points, Move, Point, PointRect, Prop, Setup, Stone,
+    };
+    use crate::{Color, SgfProp, SimpleText};
+
+    #[test]
+    fn append_moves() {
+        use super::AppendMoveError;
+        use crate::NodePath;
+
+        let mut node = parse("(;SZ[9](;B[aa])(;AB[ee]PL[W]))").unwrap().remove(0);
+        let root = NodePath::root();
+        assert_eq!(
+            node.append_move_checked(&root, Color::White, Move::Pass)
+                .err(),
+            Some(AppendMoveError::OutOfTurn(Color::Black))
+        );
+        assert!(node
+            .append_move_checked(&root, Color::Black, Move::Pass)
+            .unwrap()
+            .append_move_checked(&root, Color::Black, Move::Pass)
+            .is_err());
+        for index in 0..2 {
+            assert_eq!(
+                node.append_move_checked(&NodePath::from(vec![index]), Color::Black, Move::Pass)
+                    .err(),
+                Some(AppendMoveError::OutOfTurn(Color::White))
+            );
+        }
+        assert_eq!(
+            node.append_move_checked(&NodePath::from(vec![3]), Color::Black, Move::Pass)
+                .err(),
+            Some(AppendMoveError::InvalidPath)
+        );
+        node.append_move_checked(&NodePath::from(vec![1]), Color::White, Move::Pass)
+            .unwrap();
+        assert_eq!(
+            node.serialize(),
+            "(;SZ[9:9](;B[aa])(;AB[ee]PL[W];W[])(;B[]))"
+        );
+        assert!(node.validate().is_ok());
+
+        // Setup only nodes follow the moves before them.
+        let mut node = parse("(;HA[2]AB[dd][pp];AE[dd];W[aa];AB[bb])")
+            .unwrap()
+            .remove(0);
+        let path = NodePath::from(vec![0, 0, 0]);
+        assert_eq!(
+            node.append_move_checked(&path, Color::White, Move::Pass)
+                .err(),
+            Some(AppendMoveError::OutOfTurn(Color::Black))
+        );
+    }
+
+    #[test]
+    fn new_prop_escapes() {
+        let label = |value: &str| {
+            let values = vec![value.to_string()];
+            (
+                Prop::new("LB".to_string(), values.clone()),
+                Prop::new_escaped("LB".to_string(), values),
+            )
+        };
+        let text = |text: &str| SimpleText {
+            text: text.to_string(),
+        };
+        let (unescaped, escaped) = label("aa:a\\b");
+        let point = Point { x: 0, y: 0 };
+        assert_eq!(
+            unescaped,
+            Prop::LB(vec![(point, text("a\\b"))].into_iter().collect())
+        );
+        assert_eq!(
+            escaped,
+            Prop::LB(vec![(point, text("ab"))].into_iter().collect())
+        );
+        let (unescaped, escaped) = label("aa:a\\:b");
+        assert_eq!(
+            unescaped,
+            Prop::LB(vec![(point, text("a\\:b"))].into_iter().collect())
+        );
+        assert_eq!(
+            escaped,
+            Prop::LB(vec![(point, text("a:b"))].into_iter().collect())
+        );
+        assert_eq!(unescaped.to_string(), "LB[aa:a\\\\\\:b]");
+        let (unescaped, _) = label("aa:a:b");
+        assert_eq!(
+            unescaped,
+            Prop::LB(vec![(point, text("a:b"))].into_iter().collect())
+        );
+    }
+
+    #[test]
+    fn raw_values_round_trip() {
+        use crate::props::Sealed;
+
+        let sgf = "(;SZ[9:13]AP[My\\: App:1.0]FG[]KM[6.5]C[A \\] bracket]\
+                   LB[aa:a\\:b][bb:c\\]d]AR[aa:bb]TB[]KO[]HA[1]FOO[x\\]])";
+        let node = parse(sgf).unwrap().remove(0);
+        for prop in node.properties() {
+            let copy = Prop::from_values(prop.identifier(), prop.raw_values());
+            assert_eq!(&copy, prop);
+        }
+        assert_eq!(
+            node.get_property("C").unwrap().raw_values(),
+            vec!["A ] bracket"]
+        );
+        assert_eq!(node.get_property("TB").unwrap().raw_values(), vec![""]);
+    }
+
+    #[test]
+    fn annotation_accessors() {
+        use crate::{Double, MoveAnnotation, PositionEvaluation};
+
+        let node = parse("(;C[Start]UC[1]V[-2.5];B[dd]BM[2]DO[])")
+            .unwrap()
+            .remove(0);
+        let annotations = node.get_node_annotations();
+        assert_eq!(node.get_comment().unwrap().text, "Start");
+        assert_eq!(
+            annotations.evaluation,
+            Some(PositionEvaluation::Unclear(Double::One))
+        );
+        assert_eq!(annotations.value, Some(-2.5));
+        assert!(annotations.name.is_none());
+        assert!(node.get_move_annotation().is_none());
+
+        let child = &node.children[0];
+        assert!(child.get_comment().is_none());
+        assert_eq!(child.get_node_annotations().evaluation, None);
+        assert_eq!(
+            child.get_move_annotation(),
+            Some(MoveAnnotation::Bad(Double::Two))
+        );
+    }
+
+    #[test]
+    fn point_and_move_order() {
+        let mut moves: Vec<Move> = ["ba", "ab", "aa", "", "bb"]
+            .iter()
+            .map(|s| s.parse().unwrap())
+            .collect();
+        moves.sort();
+        let sorted: Vec<_> = moves
+            .iter()
+            .map(|mv| match mv {
+                Move::Pass => "pass".to_string(),
+                Move::Move(point) => format!("{},{}", point.x, point.y),
+            })
+            .collect();
+        assert_eq!(sorted, ["pass", "0,0", "1,0", "0,1", "1,1"]);
+    }
+
+    #[test]
+    fn memory_estimate_counts_values() {
+        let empty = parse("(;)").unwrap().remove(0);
+        let base = empty.memory_estimate();
+        assert_eq!(base, std::mem::size_of_val(&empty));
+
+        let comment = "x".repeat(1000);
+        let sgf = format!("(;C[{}]AB[aa:jj];B[dd])", comment);
+        let node = parse(&sgf).unwrap().remove(0);
+        let estimate = node.memory_estimate();
+        assert!(estimate > base + 1000 + 100 * std::mem::size_of::<Point>());
+        assert!(estimate < base + 20_000, "{}", estimate);
+
+        let gametree = crate::parse(&sgf).unwrap().remove(0);
+        assert_eq!(gametree.memory_estimate(), estimate);
+    }
+
+    #[test]
+    fn setup_extraction() {
+        let node = parse("(;AW[aa:bb]AE[cc];B[dd])").unwrap().remove(0);
+        let setup = node.get_setup();
+        assert_eq!(setup.white.len(), 4);
+        assert!(setup.white.contains(&Point { x: 1, y: 1 }));
+        assert_eq!(
+            setup.empty,
+            vec![Point { x: 2, y: 2 }].into_iter().collect()
+        );
+        assert_eq!(setup.player, None);
+        assert!(!setup.is_empty());
+        assert_eq!(node.children[0].get_setup(), Setup::default());
+        assert!(Setup::default().is_empty());
+    }
+
+    #[test]
+    fn alternating_colors_match_next_player() {
+        let moves: Vec<_> = ["dd", "pp", "dp"]
+            .iter()
+            .map(|point| Move::Move(point.parse().unwrap()))
+            .collect();
+        for handicap in 0..3 {
+            let colored = alternate_colors(moves.clone(), first_player_for_handicap(handicap));
+            let sgf = sgf_from_moves((19, 19), 0.5, &colored);
+            let mut node = parse(&sgf).unwrap().remove(0);
+            node.properties.push(super::Prop::HA(handicap));
+            let nodes: Vec<_> = node.main_variation().collect();
+            for (i, (color, _)) in colored.iter().enumerate() {
+                assert_eq!(next_player(nodes[..=i].iter().copied()), *color);
+            }
+        }
+        assert!(alternate_colors(vec![], Color::White).is_empty());
+    }
+
+    #[test]
+    fn sgf_from_moves_round_trip() {
+        let moves = vec![
+            (Color::Black, Move::Move(Point { x: 2, y: 2 })),
+            (Color::White, Move::Pass),
+            (Color::White, Move::Move(Point { x: 6, y: 6 })),
+        ];
+        let node = parse(&sgf_from_moves((9, 9), 7.0, &moves))
+            .unwrap()
+            .remove(0);
+        assert!(node.validate().is_ok());
+        assert_eq!(node.board_size(), (9, 9));
+        assert_eq!(node.komi(), Some(7.0));
+        let parsed: Vec<_> = node
+            .main_variation()
+            .filter_map(|node| node.get_move_with_color())
+            .collect();
+        assert_eq!(parsed, moves);
+        assert_eq!(
+            sgf_from_moves((19, 19), 0.0, &[]),
+            "(;GM[1]FF[4]SZ[19:19]KM[0])"
+        );
+    }
+
+    #[test]
+    fn typed_getters() {
+        let sgf = "(;SZ[13]KM[6.5]HA[x]PB[Black]AB[aa][bb];B[cc])";
+        let node = parse(sgf).unwrap().into_iter().next().unwrap();
+        assert_eq!(node.get_sz(), Some(&(13, 13)));
+        assert_eq!(node.get_km(), Some(&6.5));
+        assert_eq!(node.get_pb(), Some(&SimpleText::from("Black")));
+        let expected: HashSet<_> = vec![Stone(Point { x: 0, y: 0 }), Stone(Point { x: 1, y: 1 })]
+            .into_iter()
+            .collect();
+        assert_eq!(node.get_ab(), Some(&expected));
+        // Invalid values aren't returned.
+        assert_eq!(node.get_ha(), None);
+        assert_eq!(node.get_b(), None);
+        let child = node.children().next().unwrap();
+        assert_eq!(child.get_b(), Some(&Move::Move(Point { x: 2, y: 2 })));
+    }
+
+    #[test]
+    fn star_points_by_size() {
+        let to_sgf = |size| {
+            star_points(size)
+                .iter()
+                .map(crate::props::ToSgf::to_sgf)
+                .collect::<Vec<_>>()
+                .join(",")
+        };
+        assert_eq!(to_sgf((19, 19)), "dd,jd,pd,dj,jj,pj,dp,jp,pp");
+        assert_eq!(to_sgf((13, 13)), "dd,jd,gg,dj,jj");
+        assert_eq!(to_sgf((5, 5)), "cc");
+        assert_eq!(to_sgf((4, 4)), "");
+        assert_eq!(to_sgf((19, 9)), "dc,jc,pc,je,dg,jg,pg");
+    }
 
     #[test]
     fn large_move_numbers() {
@@ -247,4 +1292,17 @@ mod tests {
         let expected = Point { x: 0, y: 28 };
         assert_eq!(point, expected);
     }
+
+    #[test]
+    fn point_rect_cover_round_trip() {
+        let points: HashSet<Point> = ["aa:cb", "ac", "ee:ff", "gf"]
+            .iter()
+            .flat_map(|s| s.parse::<PointRect>().unwrap().iter_points())
+            .collect();
+        let rects = PointRect::cover(&points);
+        let serialized: Vec<String> = rects.iter().map(|r| r.to_string()).collect();
+        assert_eq!(serialized, vec!["aa:cb", "ac", "ee:ff", "gf"]);
+        let expanded: HashSet<Point> = rects.iter().flat_map(|r| r.iter_points()).collect();
+        assert_eq!(expanded, points);
+    }
 }
@@ -0,0 +1,290 @@
+use super::{next_player, player_after, Move, Point, Prop, Stone};
+use crate::{Color, GameTree, PropertyType, SgfNode, SgfProp, SimpleText};
+
+/// A builder for recording a Go game move by move.
+///
+/// The root node gets `GM`, `FF`, `CA` and `SZ` properties, and each move is recorded in its own
+/// node along the main variation. Moves must alternate between players (taking any handicap and
+/// `PL` properties into account, like [`next_player`]), and moves and stones must be on the board,
+/// so the resulting game tree is always valid.
+///
+/// # Examples
+/// ```
+/// use sgf_parse::Color;
+/// use sgf_parse::go::{GameBuilder, Move, Prop};
+///
+/// let mut builder = GameBuilder::new((9, 9));
+/// builder.set_game_info(Prop::KM(7.0)).unwrap();
+/// builder
+///     .play(Color::Black, Move::Move("ee".parse().unwrap()))
+///     .unwrap()
+///     .play(Color::White, Move::Pass)
+///     .unwrap();
+/// assert!(builder.play(Color::White, Move::Pass).is_err());
+/// assert_eq!(
+///     builder.build().serialize_validated(None).unwrap(),
+///     "(;GM[1]FF[4]CA[UTF-8]SZ[9:9]KM[7];B[ee];W[])"
+/// );
+/// ```
+#[derive(Clone, Debug)]
+pub struct GameBuilder {
+    // Nodes of the main variation from the root, linked together by `build`.
+    nodes: Vec<SgfNode<Prop>>,
+    // The player to move after every node but the last.
+    player_before_last: Color,
+}
+
+impl GameBuilder {
+    /// Returns a new builder for a game on a board of the given size.
+    pub fn new(board_size: (u8, u8)) -> Self {
+        let properties = vec![
+            Prop::GM(1),
+            Prop::FF(4),
+            Prop::CA(SimpleText {
+                text: "UTF-8".to_string(),
+            }),
+            Prop::SZ(board_size),
+        ];
+        Self {
+            nodes: vec![SgfNode::new(properties, vec![], true)],
+            player_before_last: Color::Black,
+        }
+    }
+
+    /// Returns the size of the board.
+    pub fn board_size(&self) -> (u8, u8) {
+        self.nodes[0].board_size()
+    }
+
+    /// Returns the player expected to make the next move.
+    pub fn next_player(&self) -> Color {
+        player_after(self.last_node(), self.player_before_last)
+    }
+
+    /// Sets a game info property (like `PB`, `KM` or `HA`) on the root node.
+    ///
+    /// Any existing property with the same identifier is replaced.
+    ///
+    /// # Errors
+    /// Returns an error if the property isn't a game info property.
+    ///
+    /// # Examples
+    /// ```
+    /// use sgf_parse::SimpleText;
+    /// use sgf_parse::go::{GameBuilder, Prop};
+    ///
+    /// let mut builder = GameBuilder::new((19, 19));
+    /// let player = Prop::PB(SimpleText { text: "Honinbo Shusaku".to_string() });
+    /// builder.set_game_info(player).unwrap();
+    /// assert!(builder.set_game_info(Prop::SZ((9, 9))).is_err());
+    /// ```
+    pub fn set_game_info(&mut self, prop: Prop) -> Result<&mut Self, GameBuilderError> {
+        if prop.property_type() != Some(PropertyType::GameInfo) {
+            return Err(GameBuilderError::NotGameInfo);
+        }
+        self.nodes[0].set_property(prop);
+        self.player_before_last = next_player(&self.nodes[..self.nodes.len() - 1]);
+        Ok(self)
+    }
+
+    /// Adds setup stones of the provided color.
+    ///
+    /// Stones are added to the last node if it has no move (like the root node before the first
+    /// move), and to a new node otherwise.
+    ///
+    /// # Errors
+    /// Returns an error if there are no stones, any stone is off the board, or any stone is already
+    /// added with the other color in the same node.
+    ///
+    /// # Examples
+    /// ```
+    /// use sgf_parse::Color;
+    /// use sgf_parse::go::{star_points, GameBuilder, Prop};
+    ///
+    /// let mut builder = GameBuilder::new((9, 9));
+    /// builder.set_game_info(Prop::HA(2)).unwrap();
+    /// let mut stones = star_points((9, 9));
+    /// stones.truncate(2);
+    /// builder.add_setup(Color::Black, stones).unwrap();
+    /// assert_eq!(builder.next_player(), Color::White);
+    /// ```
+    pub fn add_setup(
+        &mut self,
+        color: Color,
+        stones: impl IntoIterator<Item = Point>,
+    ) -> Result<&mut Self, GameBuilderError> {
+        let board_size = self.board_size();
+        let prop = Prop::new_setup_stones(color, stones.into_iter().map(Stone), board_size)
+            .map_err(|_| GameBuilderError::InvalidSetup)?;
+        let node = self.setup_node();
+        let mut setup = node.get_setup();
+        let (added, same, other) = match &prop {
+            Prop::AB(stones) => (stones, &mut setup.black, &setup.white),
+            Prop::AW(stones) => (stones, &mut setup.white, &setup.black),
+            _ => unreachable!(),
+        };
+        if added.iter().any(|stone| other.contains(&stone.0)) {
+            return Err(GameBuilderError::InvalidSetup);
+        }
+        same.extend(added.iter().map(|stone| stone.0));
+        let stones = same.iter().copied().map(Stone);
+        let prop = Prop::new_setup_stones(color, stones, board_size)
+            .map_err(|_| GameBuilderError::InvalidSetup)?;
+        node.set_property(prop);
+        Ok(self)
+    }
+
+    /// Sets the player to move next with a `PL` property.
+    ///
+    /// Like setup stones, the property is added to the last node if it has no move, and to a new
+    /// node otherwise.
+    ///
+    /// # Examples
+    /// ```
+    /// use sgf_parse::Color;
+    /// use sgf_parse::go::{GameBuilder, Move};
+    ///
+    /// let mut builder = GameBuilder::new((19, 19));
+    /// builder.set_player(Color::White);
+    /// assert!(builder.play(Color::White, Move::Pass).is_ok());
+    /// ```
+    pub fn set_player(&mut self, color: Color) -> &mut Self {
+        self.setup_node().set_property(Prop::PL(color));
+        self
+    }
+
+    /// Records a move in a new node at the end of the main variation.
+    ///
+    /// # Errors
+    /// Returns an error if it isn't the provided player's turn, or the move is off the board.
+    pub fn play(&mut self, color: Color, mv: Move) -> Result<&mut Self, GameBuilderError> {
+        let expected = self.next_player();
+        if color != expected {
+            return Err(GameBuilderError::OutOfTurn(expected));
+        }
+        let prop = Prop::new_move(color, mv, self.board_size())
+            .map_err(|_| GameBuilderError::OutOfBounds)?;
+        self.push_node(SgfNode::new(vec![prop], vec![], false));
+        Ok(self)
+    }
+
+    /// Returns the recorded game.
+    pub fn build(mut self) -> GameTree {
+        let mut node = self.nodes.pop().unwrap();
+        while let Some(mut parent) = self.nodes.pop() {
+            parent.children.push(node);
+            node = parent;
+        }
+        GameTree::GoGame(node)
+    }
+
+    fn last_node(&self) -> &SgfNode<Prop> {
+        &self.nodes[self.nodes.len() - 1]
+    }
+
+    fn push_node(&mut self, node: SgfNode<Prop>) {
+        self.player_before_last = self.next_player();
+        self.nodes.push(node);
+    }
+
+    // Returns the last node, or a new node after it if it has a move.
+    fn setup_node(&mut self) -> &mut SgfNode<Prop> {
+        if self.last_node().get_move().is_some() {
+            self.push_node(SgfNode::default());
+        }
+        self.nodes.last_mut().unwrap()
+    }
+}
+
+/// Error type for changes rejected by a [`GameBuilder`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameBuilderError {
+    /// The move was made by the wrong player. Contains the player expected to move.
+    OutOfTurn(Color),
+    /// The move was off the board.
+    OutOfBounds,
+    /// The setup stones were empty, off the board, or already added with the other color.
+    InvalidSetup,
+    /// The property wasn't a game info property.
+    NotGameInfo,
+}
+
+impl std::fmt::Display for GameBuilderError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GameBuilderError::OutOfTurn(color) => {
+                write!(f, "Move out of turn, {:?} to play", color)
+            }
+            GameBuilderError::OutOfBounds => write!(f, "Move outside the board"),
+            GameBuilderError::InvalidSetup => write!(f, "Invalid setup stones"),
+            GameBuilderError::NotGameInfo => write!(f, "Not a game info property"),
+        }
+    }
+}
+
+impl std::error::Error for GameBuilderError {}
+
+#[cfg(test)]
+mod tests {
+    use super::{GameBuilder, GameBuilderError};
+    use crate::go::{parse, Move, Point, Prop};
+    use crate::Color;
+
+    #[test]
+    fn records_valid_game() {
+        let mut builder = GameBuilder::new((9, 9));
+        builder
+            .set_game_info(Prop::HA(2))
+            .unwrap()
+            .add_setup(Color::Black, vec![Point { x: 2, y: 2 }])
+            .unwrap()
+            .add_setup(Color::Black, vec![Point { x: 6, y: 6 }])
+            .unwrap();
+        assert_eq!(
+            builder
+                .add_setup(Color::White, vec![Point { x: 2, y: 2 }])
+                .err(),
+            Some(GameBuilderError::InvalidSetup)
+        );
+        assert_eq!(
+            builder.play(Color::Black, Move::Pass).err(),
+            Some(GameBuilderError::OutOfTurn(Color::White))
+        );
+        let off_board = Move::Move(Point { x: 9, y: 0 });
+        assert_eq!(
+            builder.play(Color::White, off_board).err(),
+            Some(GameBuilderError::OutOfBounds)
+        );
+        builder
+            .play(Color::White, Move::Move(Point { x: 4, y: 4 }))
+            .unwrap()
+            .add_setup(Color::White, vec![Point { x: 0, y: 0 }])
+            .unwrap()
+            .set_player(Color::White)
+            .play(Color::White, Move::Pass)
+            .unwrap();
+
+        let node = builder.build().into_go_node().unwrap();
+        assert!(node.validate().is_ok());
+        let mut setup = node.get_setup();
+        assert_eq!(setup.black.len(), 2);
+        setup = node.children[0].children[0].get_setup();
+        assert_eq!(setup.player, Some(Color::White));
+        let expected = parse("(;GM[1]FF[4]CA[UTF-8]SZ[9]HA[2]AB[cc][gg];W[ee];AW[aa]PL[W];W[])")
+            .unwrap()
+            .remove(0);
+        assert_eq!(node, expected);
+    }
+
+    #[test]
+    fn records_long_game() {
+        let moves = 100_000;
+        let mut builder = GameBuilder::new((19, 19));
+        for _ in 0..moves {
+            builder.play(builder.next_player(), Move::Pass).unwrap();
+        }
+        assert_eq!(builder.next_player(), Color::Black);
+        let node = builder.build().into_go_node().unwrap();
+        assert_eq!(node.main_variation().count(), moves + 1);
+    }
+}
@@ -0,0 +1,236 @@
+use super::Prop;
+use crate::{NodePath, SgfNode, SgfProp, Text};
+
+// Identifiers of properties copied from review nodes into aligned original nodes.
+const ANNOTATION_IDENTIFIERS: [&str; 21] = [
+    "C", "N", "DM", "GB", "GW", "HO", "UC", "V", "BM", "DO", "IT", "TE", "AR", "CR", "DD", "LB",
+    "LN", "MA", "SL", "SQ", "TR",
+];
+
+/// Summary of the changes made by [`merge_review`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MergeReport {
+    /// Number of aligned nodes which had annotations copied from the review.
+    pub annotated_nodes: usize,
+    /// Number of variations from the review added to the original.
+    pub added_variations: usize,
+    /// Paths from the review's root node to review nodes which couldn't be aligned with the
+    /// original.
+    pub unmatched: Vec<NodePath>,
+    /// Review properties which weren't copied because the aligned original node already has a
+    /// different value for them, along with the path to the review node.
+    pub dropped: Vec<(NodePath, Prop)>,
+}
+
+/// Merges comments, markup, and variations from a reviewed copy of a game into the original.
+///
+/// Nodes are aligned starting from the roots by matching the `B` or `W` move of each child.
+/// Aligned nodes have their annotation and markup properties copied over (comments present in
+/// both are joined). Other properties present in both with different values keep the original's
+/// value, and the review's is reported in [`MergeReport::dropped`]. Review variations missing
+/// from the original are added. If the review's main line diverges from the original's, the
+/// divergent node is left out and reported in [`MergeReport::unmatched`].
+///
+/// # Examples
+/// ```
+/// use sgf_parse::go::{merge_review, parse};
+///
+/// let mut original = parse("(;SZ[9];B[ee];W[ce])").unwrap().remove(0);
+/// let review = parse("(;SZ[9];B[ee]C[Good](;W[ce]TR[dd])(;W[gc]))").unwrap().remove(0);
+/// let report = merge_review(&mut original, &review);
+/// assert_eq!(report.added_variations, 1);
+/// assert_eq!(original.serialize(), "(;SZ[9:9];B[ee]C[Good](;W[ce]TR[dd])(;W[gc]))");
+/// ```
+pub fn merge_review(original: &mut SgfNode<Prop>, review: &SgfNode<Prop>) -> MergeReport {
+    let mut report = MergeReport::default();
+    // Review nodes seen so far, as their parent's index here and their child index, so paths are
+    // only built for reported nodes.
+    let mut review_nodes: Vec<(usize, usize)> = vec![(usize::MAX, 0)];
+    let path = |review_nodes: &[(usize, usize)], mut index: usize| {
+        let mut path = vec![];
+        while index != 0 {
+            let (parent, child_index) = review_nodes[index];
+            path.push(child_index);
+            index = parent;
+        }
+        path.reverse();
+        NodePath::from(path)
+    };
+
+    // Each original node with the (usually single) review nodes aligned with it.
+    let mut stack = vec![(original, vec![(review, 0)])];
+    while let Some((original, reviews)) = stack.pop() {
+        let mut aligned: Vec<Vec<(&SgfNode<Prop>, usize)>> = vec![];
+        for (review, review_index) in reviews {
+            let mut dropped = vec![];
+            if merge_annotations(original, review, &mut dropped) {
+                report.annotated_nodes += 1;
+            }
+            for prop in dropped {
+                report
+                    .dropped
+                    .push((path(&review_nodes, review_index), prop));
+            }
+            for (i, review_child) in review.children().enumerate() {
+                let child_index = review_nodes.len();
+                review_nodes.push((review_index, i));
+                let matching_child = original
+                    .children
+                    .iter()
+                    .position(|child| node_move(child) == node_move(review_child));
+                match matching_child {
+                    Some(j) => {
+                        if aligned.len() <= j {
+                            aligned.resize_with(j + 1, Vec::new);
+                        }
+                        aligned[j].push((review_child, child_index));
+                    }
+                    None if i == 0 && !original.children.is_empty() => {
+                        report.unmatched.push(path(&review_nodes, child_index))
+                    }
+                    None => {
+                        original.add_child(review_child.clone());
+                        report.added_variations += 1;
+                    }
+                }
+            }
+        }
+        for (child, reviews) in original.children.iter_mut().zip(aligned).rev() {
+            if !reviews.is_empty() {
+                stack.push((child, reviews));
+            }
+        }
+    }
+    // Nodes are visited in a different order than a depth first search, which lists paths in
+    // order.
+    report.unmatched.sort();
+    report.dropped.sort_by(|a, b| a.0.cmp(&b.0));
+    report
+}
+
+// Copies annotation properties from `review` to `original` and returns whether anything changed.
+//
+// Properties which conflict with the original are added to `dropped` instead.
+fn merge_annotations(
+    original: &mut SgfNode<Prop>,
+    review: &SgfNode<Prop>,
+    dropped: &mut Vec<Prop>,
+) -> bool {
+    let mut changed = false;
+    for prop in review.properties() {
+        let identifier = prop.identifier_str();
+        if !ANNOTATION_IDENTIFIERS.contains(&identifier) {
+            continue;
+        }
+        let position = original
+            .properties
+            .iter()
+            .position(|p| p.identifier_str() == identifier);
+        match (position, prop) {
+            (None, _) => {
+                original.properties.push(prop.clone());
+                changed = true;
+            }
+            (Some(i), Prop::C(review_comment)) => {
+                if let Prop::C(comment) = &original.properties[i] {
+                    if !contains_paragraphs(&comment.text, &review_comment.text) {
+                        original.properties[i] = Prop::C(Text {
+                            text: format!("{}\n\n{}", comment.text, review_comment.text),
+                        });
+                        changed = true;
+                    }
+                }
+            }
+            (Some(i), _) if original.properties[i] == *prop => {}
+            (Some(_), _) => dropped.push(prop.clone()),
+        }
+    }
+    changed
+}
+
+// Returns whether `text` has all the paragraphs of `other` in a row, as added by an earlier merge.
+fn contains_paragraphs(text: &str, other: &str) -> bool {
+    let paragraphs: Vec<_> = text.split("\n\n").collect();
+    let other: Vec<_> = other.split("\n\n").collect();
+    paragraphs
+        .windows(other.len())
+        .any(|window| window == other.as_slice())
+}
+
+fn node_move(node: &SgfNode<Prop>) -> Option<&Prop> {
+    node.get_property("B").or_else(|| node.get_property("W"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::merge_review;
+    use crate::go::{parse, Point, Prop};
+    use crate::NodePath;
+
+    #[test]
+    fn merge_joins_comments_and_reports_divergence() {
+        let mut original = parse("(;C[Original];B[ee];W[ce])").unwrap().remove(0);
+        let review = parse("(;C[Review];B[ee];W[gc]C[Better])")
+            .unwrap()
+            .remove(0);
+        let report = merge_review(&mut original, &review);
+        assert_eq!(report.annotated_nodes, 1);
+        assert_eq!(report.unmatched, vec![NodePath::from(vec![0, 0])]);
+        assert_eq!(original.serialize(), "(;C[Original\n\nReview];B[ee];W[ce])");
+    }
+
+    #[test]
+    fn merge_compares_whole_paragraphs() {
+        let mut original = parse("(;C[Good shape\n\nKo];B[ee])").unwrap().remove(0);
+        let review = parse("(;C[Good];B[ee]C[Why?\n\nTenuki])")
+            .unwrap()
+            .remove(0);
+        merge_review(&mut original, &review);
+        merge_review(&mut original, &review);
+        assert_eq!(
+            original.serialize(),
+            "(;C[Good shape\n\nKo\n\nGood];B[ee]C[Why?\n\nTenuki])"
+        );
+    }
+
+    #[test]
+    fn merge_reports_conflicting_markup() {
+        let mut original = parse("(;SZ[9];B[ee]TR[aa];W[ce]LB[cc:A])")
+            .unwrap()
+            .remove(0);
+        let review = parse("(;SZ[9];B[ee]TR[aa];W[ce]LB[cc:B]SQ[dd])")
+            .unwrap()
+            .remove(0);
+        let report = merge_review(&mut original, &review);
+        assert_eq!(report.annotated_nodes, 1);
+        assert_eq!(
+            report.dropped,
+            vec![(
+                NodePath::from(vec![0, 0]),
+                Prop::LB(
+                    vec![(Point { x: 2, y: 2 }, "B".into())]
+                        .into_iter()
+                        .collect()
+                )
+            )]
+        );
+        assert_eq!(
+            original.serialize(),
+            "(;SZ[9:9];B[ee]TR[aa];W[ce]LB[cc:A]SQ[dd])"
+        );
+    }
+
+    #[test]
+    fn merge_deep_review() {
+        let depth = 100_000;
+        let moves = ";B[aa];W[bb]".repeat(depth / 2);
+        let mut original = parse(&format!("(;SZ[9]{})", moves)).unwrap().remove(0);
+        let review = parse(&format!("(;SZ[9]{}C[End];B[cc])", moves))
+            .unwrap()
+            .remove(0);
+        let report = merge_review(&mut original, &review);
+        assert_eq!(report.annotated_nodes, 1);
+        assert_eq!(report.added_variations, 1);
+        assert_eq!(original.main_variation().count(), depth + 2);
+    }
+}
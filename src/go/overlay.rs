@@ -0,0 +1,105 @@
+use std::collections::{HashMap, HashSet};
+
+use super::{Point, Prop};
+use crate::SgfNode;
+
+/// How a point should be drawn, as resolved by [`point_statuses`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum PointStatus {
+    /// Black territory from `TB`.
+    BlackTerritory,
+    /// White territory from `TW`.
+    WhiteTerritory,
+    /// Dimmed from `DD`.
+    Dimmed,
+    /// Selected from `SL`.
+    Selected,
+}
+
+/// Returns the status of every point with territory, dimming, or selection markup at the last of
+/// the provided nodes.
+///
+/// The nodes should be the path from the root node to the node of interest. `DD` is inherited, so
+/// dimmed points come from the last node on the path with a `DD` property (and `DD[]` clears
+/// them). `TB`, `TW`, and `SL` only apply to the last node.
+///
+/// Where a point has more than one status, selection takes precedence over dimming, which takes
+/// precedence over territory. Points in both `TB` and `TW` are illegal according to the SGF
+/// specification, so their territory is ignored.
+///
+/// # Examples
+/// ```
+/// use sgf_parse::go::{parse, point_statuses, PointStatus};
+///
+/// let node = parse("(;DD[aa][ab];TB[aa][ba][bb]TW[bb]SL[ab])").unwrap().remove(0);
+/// let statuses = point_statuses(node.main_variation());
+/// assert_eq!(statuses.get(&"aa".parse().unwrap()), Some(&PointStatus::Dimmed));
+/// assert_eq!(statuses.get(&"ab".parse().unwrap()), Some(&PointStatus::Selected));
+/// assert_eq!(statuses.get(&"ba".parse().unwrap()), Some(&PointStatus::BlackTerritory));
+/// assert_eq!(statuses.get(&"bb".parse().unwrap()), None);
+/// ```
+pub fn point_statuses<'a>(
+    nodes: impl IntoIterator<Item = &'a SgfNode<Prop>>,
+) -> HashMap<Point, PointStatus> {
+    let mut dimmed = None;
+    let mut last = None;
+    for node in nodes {
+        if let Some(Prop::DD(points)) = node.get_property("DD") {
+            dimmed = Some(points);
+        }
+        last = Some(node);
+    }
+    let node = match last {
+        Some(node) => node,
+        None => return HashMap::new(),
+    };
+    let points = |identifier| match node.get_property(identifier) {
+        Some(Prop::TB(points)) | Some(Prop::TW(points)) | Some(Prop::SL(points)) => Some(points),
+        _ => None,
+    };
+    let empty = HashSet::new();
+    let black = points("TB").unwrap_or(&empty);
+    let white = points("TW").unwrap_or(&empty);
+
+    let mut statuses = HashMap::new();
+    for point in black.symmetric_difference(white) {
+        let status = if black.contains(point) {
+            PointStatus::BlackTerritory
+        } else {
+            PointStatus::WhiteTerritory
+        };
+        statuses.insert(*point, status);
+    }
+    for point in dimmed.into_iter().flatten() {
+        statuses.insert(*point, PointStatus::Dimmed);
+    }
+    for point in points("SL").into_iter().flatten() {
+        statuses.insert(*point, PointStatus::Selected);
+    }
+    statuses
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{point_statuses, PointStatus};
+    use crate::go::parse;
+
+    #[test]
+    fn dimming_is_inherited_until_cleared() {
+        let node = parse("(;DD[aa];TW[aa][bb];DD[];TW[aa])").unwrap().remove(0);
+        let statuses: Vec<_> = (1..=4)
+            .map(|depth| point_statuses(node.main_variation().take(depth)))
+            .map(|statuses| statuses.get(&"aa".parse().unwrap()).copied())
+            .collect();
+        assert_eq!(
+            statuses,
+            vec![
+                Some(PointStatus::Dimmed),
+                Some(PointStatus::Dimmed),
+                None,
+                Some(PointStatus::WhiteTerritory),
+            ]
+        );
+        assert!(point_statuses(std::iter::empty()).is_empty());
+    }
+}
@@ -0,0 +1,292 @@
+use super::{Move, Point};
+use crate::sgf_node::StableHasher;
+use crate::{Color, GamePosition};
+
+/// A Go board position.
+///
+/// Moves are checked for legality (including captures, suicide, and simple ko) as they're
+/// applied.
+///
+/// # Examples
+/// ```
+/// use sgf_parse::{Color, GamePosition};
+/// use sgf_parse::go::{IllegalMoveError, Move, Position};
+///
+/// let mut position = Position::new(19, 19);
+/// let point = "dd".parse().unwrap();
+/// position.apply_move(Color::Black, &Move::Move(point)).unwrap();
+/// assert_eq!(position.stone_at(point), Some(Color::Black));
+/// assert_eq!(
+///     position.apply_move(Color::White, &Move::Move(point)),
+///     Err(IllegalMoveError::Occupied)
+/// );
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Position {
+    width: u8,
+    height: u8,
+    stones: Vec<Option<Color>>,
+    to_play: Color,
+    ko_point: Option<Point>,
+}
+
+impl Position {
+    /// Returns a new empty position with black to play.
+    pub fn new(width: u8, height: u8) -> Self {
+        Self {
+            width,
+            height,
+            stones: vec![None; usize::from(width) * usize::from(height)],
+            to_play: Color::Black,
+            ko_point: None,
+        }
+    }
+
+    /// Returns the board dimensions as `(width, height)`.
+    pub fn size(&self) -> (u8, u8) {
+        (self.width, self.height)
+    }
+
+    /// Returns the color of the stone at the point (if any).
+    pub fn stone_at(&self, point: Point) -> Option<Color> {
+        self.index(point).and_then(|i| self.stones[i])
+    }
+
+    /// Places or removes a stone without checking legality or capturing, as for setup properties.
+    ///
+    /// Points off the board are ignored.
+    pub fn set_stone(&mut self, point: Point, stone: Option<Color>) {
+        if let Some(i) = self.index(point) {
+            self.stones[i] = stone;
+            self.ko_point = None;
+        }
+    }
+
+    /// Sets the player to move next, as for the `PL` property.
+    pub fn set_to_play(&mut self, color: Color) {
+        self.to_play = color;
+    }
+
+    fn index(&self, point: Point) -> Option<usize> {
+        if point.is_on_board(self.size()) {
+            Some(usize::from(point.y) * usize::from(self.width) + usize::from(point.x))
+        } else {
+            None
+        }
+    }
+
+    fn neighbors(&self, point: Point) -> impl Iterator<Item = Point> {
+        point.neighbors(self.size())
+    }
+
+    // Returns the number of empty points next to the point.
+    fn liberty_count(&self, point: Point) -> usize {
+        self.neighbors(point)
+            .filter(|&neighbor| self.stone_at(neighbor).is_none())
+            .count()
+    }
+
+    // Returns the stones in the group containing `point` and whether the group has liberties.
+    fn group(&self, point: Point) -> (Vec<Point>, bool) {
+        let color = self.stone_at(point);
+        let mut group = vec![point];
+        let mut seen = vec![false; self.stones.len()];
+        seen[self.index(point).unwrap()] = true;
+        let mut has_liberties = false;
+        let mut i = 0;
+        while i < group.len() {
+            for neighbor in self.neighbors(group[i]) {
+                let index = self.index(neighbor).unwrap();
+                match self.stones[index] {
+                    None => has_liberties = true,
+                    c if c == color && !seen[index] => {
+                        seen[index] = true;
+                        group.push(neighbor);
+                    }
+                    _ => {}
+                }
+            }
+            i += 1;
+        }
+        (group, has_liberties)
+    }
+}
+
+impl GamePosition for Position {
+    type Move = Move;
+    type Error = IllegalMoveError;
+
+    fn apply_move(&mut self, color: Color, mv: &Move) -> Result<(), IllegalMoveError> {
+        let opponent = match color {
+            Color::Black => Color::White,
+            Color::White => Color::Black,
+        };
+        let point = match mv {
+            Move::Pass => {
+                self.ko_point = None;
+                self.to_play = opponent;
+                return Ok(());
+            }
+            Move::Move(point) => *point,
+        };
+        let index = self.index(point).ok_or(IllegalMoveError::OutOfBounds)?;
+        if self.stones[index].is_some() {
+            return Err(IllegalMoveError::Occupied);
+        }
+        if self.ko_point == Some(point) {
+            return Err(IllegalMoveError::Ko);
+        }
+        let mut next = self.clone();
+        next.stones[index] = Some(color);
+        let mut captured = vec![];
+        for neighbor in self.neighbors(point) {
+            if next.stone_at(neighbor) == Some(opponent) {
+                let (group, has_liberties) = next.group(neighbor);
+                if !has_liberties {
+                    for stone in &group {
+                        let i = next.index(*stone).unwrap();
+                        next.stones[i] = None;
+                    }
+                    captured.extend(group);
+                }
+            }
+        }
+        let (group, has_liberties) = next.group(point);
+        if !has_liberties {
+            return Err(IllegalMoveError::Suicide);
+        }
+        // Only a single stone capturing a single stone and left with just the captured point as
+        // a liberty can be immediately recaptured.
+        next.ko_point = match (captured.as_slice(), group.len()) {
+            ([captured_point], 1) if next.liberty_count(point) == 1 => Some(*captured_point),
+            _ => None,
+        };
+        next.to_play = opponent;
+        *self = next;
+        Ok(())
+    }
+
+    fn to_play(&self) -> Color {
+        self.to_play
+    }
+
+    // Hashed with a stable hash function so hashes can be stored.
+    fn position_hash(&self) -> u64 {
+        fn color_byte(color: Option<Color>) -> u8 {
+            match color {
+                None => 0,
+                Some(Color::Black) => 1,
+                Some(Color::White) => 2,
+            }
+        }
+
+        let mut hasher = StableHasher::new();
+        hasher.write(&[self.width, self.height]);
+        let stones: Vec<u8> = self.stones.iter().map(|&stone| color_byte(stone)).collect();
+        hasher.write(&stones);
+        hasher.write(&[color_byte(Some(self.to_play))]);
+        match self.ko_point {
+            Some(point) => hasher.write(&[1, point.x, point.y]),
+            None => hasher.write(&[0]),
+        }
+        hasher.finish()
+    }
+}
+
+/// Error type for illegal moves applied to a [`Position`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IllegalMoveError {
+    OutOfBounds,
+    Occupied,
+    Suicide,
+    Ko,
+}
+
+impl std::fmt::Display for IllegalMoveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IllegalMoveError::OutOfBounds => write!(f, "Move outside the board"),
+            IllegalMoveError::Occupied => write!(f, "Move on an occupied point"),
+            IllegalMoveError::Suicide => write!(f, "Suicide move"),
+            IllegalMoveError::Ko => write!(f, "Move retakes a ko"),
+        }
+    }
+}
+
+impl std::error::Error for IllegalMoveError {}
+
+#[cfg(test)]
+mod tests {
+    use super::{IllegalMoveError, Position};
+    use crate::go::Move;
+    use crate::{Color, GamePosition};
+
+    fn play(position: &mut Position, color: Color, point: &str) -> Result<(), IllegalMoveError> {
+        position.apply_move(color, &Move::Move(point.parse().unwrap()))
+    }
+
+    #[test]
+    fn capture_and_ko() {
+        let mut position = Position::new(9, 9);
+        for (color, point) in [
+            (Color::Black, "ba"),
+            (Color::White, "ca"),
+            (Color::Black, "ab"),
+            (Color::White, "bb"),
+            (Color::Black, "ee"),
+            (Color::White, "aa"),
+        ] {
+            play(&mut position, color, point).unwrap();
+        }
+        assert_eq!(position.stone_at("ba".parse().unwrap()), None);
+        assert_eq!(
+            play(&mut position, Color::Black, "ba"),
+            Err(IllegalMoveError::Ko)
+        );
+        play(&mut position, Color::Black, "ff").unwrap();
+        play(&mut position, Color::White, "gg").unwrap();
+        play(&mut position, Color::Black, "ba").unwrap();
+        assert_eq!(position.stone_at("aa".parse().unwrap()), None);
+    }
+
+    #[test]
+    fn capture_with_extra_liberties_is_not_ko() {
+        let mut position = Position::new(9, 9);
+        // Black captures on `ca` leaving liberties on `da` and `cb`, so retaking isn't a ko.
+        for (color, point) in [
+            (Color::Black, "aa"),
+            (Color::White, "ba"),
+            (Color::Black, "bb"),
+            (Color::White, "ee"),
+            (Color::Black, "ca"),
+        ] {
+            play(&mut position, color, point).unwrap();
+        }
+        assert_eq!(position.stone_at("ba".parse().unwrap()), None);
+        assert_eq!(
+            play(&mut position, Color::White, "ba"),
+            Err(IllegalMoveError::Suicide)
+        );
+    }
+
+    #[test]
+    fn position_hash_is_stable() {
+        let mut position = Position::new(9, 9);
+        play(&mut position, Color::Black, "ee").unwrap();
+        assert_eq!(position.position_hash(), 0x4a68_ca7d_1e92_4180);
+    }
+
+    #[test]
+    fn suicide_is_illegal() {
+        let mut position = Position::new(9, 9);
+        play(&mut position, Color::Black, "ba").unwrap();
+        play(&mut position, Color::White, "ee").unwrap();
+        play(&mut position, Color::Black, "ab").unwrap();
+        let hash = position.position_hash();
+        assert_eq!(
+            play(&mut position, Color::White, "aa"),
+            Err(IllegalMoveError::Suicide)
+        );
+        assert_eq!(position.position_hash(), hash);
+    }
+}
@@ -0,0 +1,213 @@
+use std::collections::HashSet;
+
+use super::{Move, Point, Position, Prop};
+use crate::rng::Rng;
+use crate::{Color, GamePosition, SgfNode, SimpleText, Text};
+
+/// Options for [`generate_random_game`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct RandomGameOptions {
+    /// Size of the board. Defaults to 19x19.
+    pub board_size: (u8, u8),
+    /// Number of moves in the main variation. Defaults to 100.
+    pub moves: usize,
+    /// Probability of starting a variation at each main variation node. Defaults to `0.05`.
+    pub variation_probability: f64,
+    /// Maximum number of moves in a variation. Defaults to 10.
+    pub max_variation_length: usize,
+    /// Probability of adding a comment to each node. Defaults to `0.1`.
+    pub comment_probability: f64,
+    /// Probability of adding markup to each node. Defaults to `0.1`.
+    pub markup_probability: f64,
+}
+
+impl Default for RandomGameOptions {
+    fn default() -> Self {
+        Self {
+            board_size: (19, 19),
+            moves: 100,
+            variation_probability: 0.05,
+            max_variation_length: 10,
+            comment_probability: 0.1,
+            markup_probability: 0.1,
+        }
+    }
+}
+
+/// Returns a pseudo-random Go game tree.
+///
+/// All moves are legal, and the same seed and options always produce the same game. Useful for
+/// demos and stress tests without shipping SGF files.
+///
+/// # Examples
+/// ```
+/// use sgf_parse::go::{generate_random_game, RandomGameOptions};
+///
+/// let options = RandomGameOptions {
+///     board_size: (9, 9),
+///     moves: 40,
+///     ..RandomGameOptions::default()
+/// };
+/// let node = generate_random_game(7, &options);
+/// assert_eq!(node.main_variation().count(), 41);
+/// assert!(node.validate().is_ok());
+/// assert_eq!(node, generate_random_game(7, &options));
+/// ```
+pub fn generate_random_game(seed: u64, options: &RandomGameOptions) -> SgfNode<Prop> {
+    let mut rng = Rng::new(seed);
+    let (width, height) = options.board_size;
+    let (first, variations) = generate_line(
+        &mut rng,
+        Position::new(width, height),
+        options.moves,
+        options,
+        true,
+    );
+    let children = first.into_iter().chain(variations).collect();
+    let properties = vec![
+        Prop::GM(1),
+        Prop::FF(4),
+        Prop::SZ(options.board_size),
+        Prop::AP((SimpleText::from("sgf-parse"), SimpleText::from("random"))),
+    ];
+    SgfNode::new(properties, children, true)
+}
+
+// Returns the first node of a line of moves and any variations on that first node.
+fn generate_line(
+    rng: &mut Rng,
+    mut position: Position,
+    length: usize,
+    options: &RandomGameOptions,
+    allow_variations: bool,
+) -> (Option<SgfNode<Prop>>, Vec<SgfNode<Prop>>) {
+    // Nodes of the line along with variations which are alternatives to them.
+    let mut line: Vec<(SgfNode<Prop>, Vec<SgfNode<Prop>>)> = vec![];
+    for move_number in 1..=length {
+        let mut variations = vec![];
+        if allow_variations && rng.chance(options.variation_probability) {
+            let variation_length = 1 + rng.below(options.max_variation_length.max(1) as u64);
+            let (variation, _) = generate_line(
+                rng,
+                position.clone(),
+                variation_length as usize,
+                options,
+                false,
+            );
+            variations.extend(variation);
+        }
+        let color = position.to_play();
+        let mv = random_move(rng, &mut position, color);
+        line.push((
+            random_node(rng, color, mv, move_number, options),
+            variations,
+        ));
+    }
+
+    let mut next: Option<(SgfNode<Prop>, Vec<SgfNode<Prop>>)> = None;
+    while let Some((mut node, variations)) = line.pop() {
+        if let Some((child, child_variations)) = next.take() {
+            node.children.push(child);
+            node.children.extend(child_variations);
+        }
+        next = Some((node, variations));
+    }
+    match next {
+        Some((node, variations)) => (Some(node), variations),
+        None => (None, vec![]),
+    }
+}
+
+// Plays and returns a random legal move, passing if none is found quickly.
+fn random_move(rng: &mut Rng, position: &mut Position, color: Color) -> Move {
+    const ATTEMPTS: usize = 100;
+    let (width, height) = position.size();
+    if width > 0 && height > 0 {
+        for _ in 0..ATTEMPTS {
+            let point = Point {
+                x: rng.below(width.into()) as u8,
+                y: rng.below(height.into()) as u8,
+            };
+            if position.apply_move(color, &Move::Move(point)).is_ok() {
+                return Move::Move(point);
+            }
+        }
+    }
+    position.apply_move(color, &Move::Pass).unwrap();
+    Move::Pass
+}
+
+fn random_node(
+    rng: &mut Rng,
+    color: Color,
+    mv: Move,
+    move_number: usize,
+    options: &RandomGameOptions,
+) -> SgfNode<Prop> {
+    let mut properties = vec![match color {
+        Color::Black => Prop::B(mv),
+        Color::White => Prop::W(mv),
+    }];
+    if rng.chance(options.comment_probability) {
+        properties.push(Prop::C(Text::from(
+            format!("Comment on move {}.", move_number).as_str(),
+        )));
+    }
+    if let Move::Move(point) = mv {
+        if rng.chance(options.markup_probability) {
+            let markup: HashSet<_> = std::iter::once(point).collect();
+            properties.push(match rng.below(3) {
+                0 => Prop::TR(markup),
+                1 => Prop::CR(markup),
+                _ => Prop::SQ(markup),
+            });
+        }
+    }
+    SgfNode::new(properties, vec![], false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{generate_random_game, RandomGameOptions};
+    use crate::go::{Position, Prop};
+    use crate::{Color, GamePosition, SgfNode};
+
+    // Replays every variation checking all moves are legal.
+    fn check_legal(node: &SgfNode<Prop>, position: &Position) -> usize {
+        let mut variations = 0;
+        for child in node.children() {
+            let mut position = position.clone();
+            let (color, mv) = child.get_move_with_color().unwrap();
+            assert_eq!(color, position.to_play());
+            position.apply_move(color, &mv).unwrap();
+            variations += check_legal(child, &position);
+        }
+        variations + node.children().count().saturating_sub(1)
+    }
+
+    #[test]
+    fn random_games_are_legal() {
+        let options = RandomGameOptions {
+            board_size: (5, 5),
+            moves: 200,
+            variation_probability: 0.2,
+            ..RandomGameOptions::default()
+        };
+        for seed in 0..10 {
+            let node = generate_random_game(seed, &options);
+            assert!(node.validate().is_ok());
+            let variations = check_legal(&node, &Position::new(5, 5));
+            assert!(variations > 0);
+            assert_eq!(node.main_variation().count(), 201);
+            assert_eq!(
+                node.children()
+                    .next()
+                    .unwrap()
+                    .get_move_with_color()
+                    .unwrap()
+                    .0,
+                Color::Black
+            );
+        }
+    }
+}
@@ -0,0 +1,263 @@
+//! Constants for SGF property identifiers.
+//!
+//! Using these instead of string literals catches typos at compile time.
+//!
+//! # Examples
+//! ```
+//! use sgf_parse::go::{parse, Prop};
+//! use sgf_parse::idents;
+//!
+//! let node = parse("(;SZ[9]KM[6.5])").unwrap().remove(0);
+//! assert_eq!(node.get_property(idents::SZ), Some(&Prop::SZ((9, 9))));
+//! assert_eq!(node.get_property(idents::KM), Some(&Prop::KM(6.5)));
+//! ```
+//!
+//! [`SgfProp::shared_identifier`](crate::SgfProp::shared_identifier) returns identifiers as
+//! [`Identifier`] values, which share the static strings defined here for known identifiers
+//! instead of allocating.
+
+use crate::{property_spec, GameType};
+use std::borrow::Borrow;
+use std::sync::Arc;
+
+macro_rules! identifiers {
+    (
+        $list:ident
+        flags: { $($flag:ident [$($flag_type:ident)?];)* }
+        values: {
+            $($ident:ident($getter:ident) [$($property_type:ident)?]:
+                $kind:ident = $parse:path $(, $range:expr)?;)*
+        }
+    ) => {
+        $(
+            #[doc = concat!("The `", stringify!($flag), "` property identifier.")]
+            pub const $flag: &str = stringify!($flag);
+        )*
+        $(
+            #[doc = concat!("The `", stringify!($ident), "` property identifier.")]
+            pub const $ident: &str = stringify!($ident);
+        )*
+
+        const $list: &[&str] = &[$($flag,)* $($ident),*];
+    };
+}
+
+general_properties!(identifiers! { GENERAL });
+go_properties!(identifiers! { GO });
+
+/// A property identifier.
+///
+/// Identifiers of the properties defined by the SGF specification (including Go specific ones)
+/// refer to shared static strings, so creating and cloning them never allocates. Other
+/// identifiers are reference counted, so clones of a property share their identifier.
+///
+/// `Identifier` dereferences to [`str`] and compares equal to strings with the same text.
+///
+/// # Examples
+/// ```
+/// use sgf_parse::go::Prop;
+/// use sgf_parse::{Identifier, SgfProp};
+///
+/// use sgf_parse::GameType;
+///
+/// let prop = Prop::new("KM".to_string(), vec!["6.5".to_string()]);
+/// assert_eq!(prop.shared_identifier(), "KM");
+/// assert_eq!(prop.shared_identifier(), Identifier::from("KM"));
+/// assert!(prop.shared_identifier().is_known(GameType::Go));
+/// assert!(!prop.shared_identifier().is_known(GameType::Unknown));
+/// assert!(!Identifier::from("FOO").is_known(GameType::Go));
+/// ```
+#[derive(Clone)]
+pub struct Identifier(Repr);
+
+#[derive(Clone)]
+enum Repr {
+    Static(&'static str),
+    Shared(Arc<str>),
+}
+
+impl Identifier {
+    /// Returns an identifier for a static string without checking whether it's known.
+    ///
+    /// Meant for property implementations returning their own identifiers.
+    pub const fn from_static(identifier: &'static str) -> Self {
+        Self(Repr::Static(identifier))
+    }
+
+    /// Returns the identifier as a string slice.
+    pub fn as_str(&self) -> &str {
+        match &self.0 {
+            Repr::Static(identifier) => identifier,
+            Repr::Shared(identifier) => identifier,
+        }
+    }
+
+    /// Returns `true` if the identifier is defined by the SGF specification for the given game.
+    pub fn is_known(&self, gametype: GameType) -> bool {
+        property_spec(gametype, self.as_str()).is_some()
+    }
+
+    // Returns whether the identifier refers to a static string rather than owning its text.
+    pub(crate) fn is_static(&self) -> bool {
+        matches!(self.0, Repr::Static(_))
+    }
+}
+
+fn known(identifier: &str) -> Option<&'static str> {
+    GENERAL
+        .iter()
+        .chain(GO)
+        .find(|known| **known == identifier)
+        .copied()
+}
+
+impl From<&str> for Identifier {
+    fn from(identifier: &str) -> Self {
+        match known(identifier) {
+            Some(known) => Self(Repr::Static(known)),
+            None => Self(Repr::Shared(identifier.into())),
+        }
+    }
+}
+
+impl From<String> for Identifier {
+    fn from(identifier: String) -> Self {
+        match known(&identifier) {
+            Some(known) => Self(Repr::Static(known)),
+            None => Self(Repr::Shared(identifier.into())),
+        }
+    }
+}
+
+impl From<Identifier> for String {
+    fn from(identifier: Identifier) -> Self {
+        identifier.as_str().to_string()
+    }
+}
+
+impl std::ops::Deref for Identifier {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl AsRef<str> for Identifier {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl Borrow<str> for Identifier {
+    fn borrow(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl std::fmt::Debug for Identifier {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl std::fmt::Display for Identifier {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl PartialEq for Identifier {
+    fn eq(&self, other: &Self) -> bool {
+        match (&self.0, &other.0) {
+            (Repr::Static(a), Repr::Static(b)) if std::ptr::eq(*a, *b) => true,
+            _ => self.as_str() == other.as_str(),
+        }
+    }
+}
+
+impl Eq for Identifier {}
+
+impl std::hash::Hash for Identifier {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.as_str().hash(state)
+    }
+}
+
+impl PartialOrd for Identifier {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Identifier {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.as_str().cmp(other.as_str())
+    }
+}
+
+impl PartialEq<str> for Identifier {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<&str> for Identifier {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl PartialEq<String> for Identifier {
+    fn eq(&self, other: &String) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<Identifier> for str {
+    fn eq(&self, other: &Identifier) -> bool {
+        self == other.as_str()
+    }
+}
+
+impl PartialEq<Identifier> for &str {
+    fn eq(&self, other: &Identifier) -> bool {
+        *self == other.as_str()
+    }
+}
+
+impl PartialEq<Identifier> for String {
+    fn eq(&self, other: &Identifier) -> bool {
+        self == other.as_str()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Identifier;
+    use crate::{property_spec, GameType};
+
+    #[test]
+    fn known_identifiers_are_shared() {
+        let owned = Identifier::from("KM".to_string());
+        assert!(owned.is_known(GameType::Go));
+        assert!(owned.is_static());
+        let other = Identifier::from("FOO".to_string());
+        assert!(!other.is_static());
+        assert!(std::ptr::eq(other.as_str(), other.clone().as_str()));
+        assert_eq!(other, Identifier::from("FOO"));
+        assert_ne!(other, owned);
+    }
+
+    #[test]
+    fn identifiers_match_specs() {
+        for identifier in super::GENERAL {
+            assert!(property_spec(GameType::Unknown, identifier).is_some());
+        }
+        for identifier in super::GO {
+            assert!(property_spec(GameType::Unknown, identifier).is_none());
+            assert!(property_spec(GameType::Go, identifier).is_some());
+        }
+        assert_eq!(super::GENERAL.len() + super::GO.len(), 67);
+    }
+}
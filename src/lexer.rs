@@ -1,23 +1,77 @@
-pub fn tokenize(
-    text: &str,
-) -> impl Iterator<Item = Result<(Token, std::ops::Range<usize>), LexerError>> + '_ {
-    Lexer { text, cursor: 0 }
+use std::borrow::Cow;
+
+// Property values are left as raw bytes borrowed from `text`, since they may not be valid UTF-8 and
+// escapes can only be removed once the property is known (see `unescape`). The parser decodes them
+// once it knows the game tree's charset.
+//
+// A leading UTF-8 byte order mark is skipped. Spans are still relative to the start of `text`.
+pub fn tokenize(text: &[u8]) -> Lexer<'_> {
+    let mut lexer = Lexer {
+        text,
+        cursor: 0,
+        max_identifier_length: None,
+        letters_only: false,
+    };
+    if text.starts_with(UTF8_BOM) {
+        lexer.cursor = UTF8_BOM.len();
+    }
+    lexer.trim_leading_whitespace();
+    lexer
 }
 
-#[derive(Debug, PartialEq)]
-pub enum Token {
+const UTF8_BOM: &[u8] = b"\xef\xbb\xbf";
+
+// Removes soft line breaks and escapes from a raw property value.
+//
+// With `keep_escapes` only soft line breaks are removed. This is used for compose values, which are
+// split on their unescaped `:` before the escapes in each half are removed.
+pub fn unescape(value: &[u8], keep_escapes: bool) -> Cow<'_, [u8]> {
+    if !value.contains(&b'\\') {
+        return Cow::Borrowed(value);
+    }
+    let mut unescaped = Vec::with_capacity(value.len());
+    let mut bytes = value.iter().copied().peekable();
+    while let Some(c) = bytes.next() {
+        if c != b'\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match bytes.next() {
+            // Soft line breaks are removed entirely.
+            Some(c @ (b'\n' | b'\r')) => {
+                let other = if c == b'\n' { b'\r' } else { b'\n' };
+                if bytes.peek() == Some(&other) {
+                    bytes.next();
+                }
+            }
+            Some(c) if keep_escapes => unescaped.extend([b'\\', c].iter()),
+            Some(c) => unescaped.push(c),
+            None => {}
+        }
+    }
+
+    Cow::Owned(unescaped)
+}
+
+// Raw property values, borrowed from the input unless they contained escapes.
+pub type PropValues<'a> = Vec<Cow<'a, [u8]>>;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Token<'a> {
     StartGameTree,
     EndGameTree,
     StartNode,
-    Property((String, Vec<String>)),
+    Property((String, PropValues<'a>)),
 }
 
 /// Error type for failures to tokenize text.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
 pub enum LexerError {
     UnexpectedPropertyIdentifier,
     MissingPropertyIdentifier,
     UnexpectedEndOfProperty,
+    PropertyIdentifierTooLong,
 }
 
 impl std::fmt::Display for LexerError {
@@ -30,64 +84,112 @@ impl std::fmt::Display for LexerError {
                 write!(f, "Missing property identifier")
             }
             LexerError::UnexpectedEndOfProperty => write!(f, "Unexpected end of property"),
+            LexerError::PropertyIdentifierTooLong => write!(f, "Property identifier too long"),
         }
     }
 }
 
 impl std::error::Error for LexerError {}
 
-struct Lexer<'a> {
-    text: &'a str,
+pub struct Lexer<'a> {
+    text: &'a [u8],
     cursor: usize,
+    max_identifier_length: Option<usize>,
+    letters_only: bool,
 }
 
 impl<'a> Lexer<'a> {
+    // Restricts the property identifiers accepted by the lexer.
+    //
+    // Identifiers longer than `max_length` fail with `PropertyIdentifierTooLong`. With
+    // `letters_only`, identifiers with anything other than ASCII letters (and whitespace before the
+    // values) fail with `UnexpectedPropertyIdentifier`.
+    pub fn with_identifier_limits(mut self, max_length: Option<usize>, letters_only: bool) -> Self {
+        self.max_identifier_length = max_length;
+        self.letters_only = letters_only;
+        self
+    }
+
+    // Skips the rest of a property after an identifier error, including its values.
+    //
+    // Used to recover from identifiers rejected by `with_identifier_limits`.
+    pub fn skip_property(&mut self) {
+        while let Some(c) = self.peek_byte() {
+            if c == b'[' {
+                break;
+            }
+            self.cursor += 1;
+        }
+        if self.get_prop_values().is_err() {
+            self.cursor = self.text.len();
+        }
+        self.trim_leading_whitespace();
+    }
+
+    // Byte offset of the lexer in the text. After an error this is where the error was found.
+    pub fn position(&self) -> usize {
+        self.cursor
+    }
+
     fn trim_leading_whitespace(&mut self) {
-        while self.cursor < self.text.len()
-            && (self.text.as_bytes()[self.cursor] as char).is_ascii_whitespace()
-        {
+        while self.cursor < self.text.len() && self.text[self.cursor].is_ascii_whitespace() {
             self.cursor += 1;
         }
     }
 
-    fn get_char(&mut self) -> Option<char> {
-        let result = self.text[self.cursor..].chars().next();
-        result.iter().for_each(|c| self.cursor += c.len_utf8());
+    fn get_byte(&mut self) -> Option<u8> {
+        let result = self.peek_byte();
+        if result.is_some() {
+            self.cursor += 1;
+        }
 
         result
     }
 
-    fn peek_char(&self) -> Option<char> {
-        self.text[self.cursor..].chars().next()
+    fn peek_byte(&self) -> Option<u8> {
+        self.text.get(self.cursor).copied()
     }
 
-    fn get_property(&mut self) -> Result<(String, Vec<String>), LexerError> {
+    fn get_property(&mut self) -> Result<(String, PropValues<'a>), LexerError> {
         Ok((self.get_prop_ident()?, self.get_prop_values()?))
     }
 
     fn get_prop_ident(&mut self) -> Result<String, LexerError> {
-        let mut prop_ident = vec![];
+        let mut prop_ident = String::new();
+        let max_length = self.max_identifier_length.unwrap_or(usize::MAX);
+        // Whether whitespace has been seen, which must be followed by the values with `letters_only`.
+        let mut ident_ended = false;
         loop {
-            match self.peek_char() {
-                Some('[') => break,
+            match self.peek_byte() {
+                Some(b'[') => break,
+                Some(c) if self.letters_only && c.is_ascii_whitespace() => {
+                    self.cursor += 1;
+                    ident_ended = true;
+                }
+                Some(c) if self.letters_only && (ident_ended || !c.is_ascii_alphabetic()) => {
+                    return Err(LexerError::UnexpectedPropertyIdentifier)
+                }
+                Some(_) if prop_ident.len() >= max_length => {
+                    return Err(LexerError::PropertyIdentifierTooLong)
+                }
                 Some(c) if c.is_ascii() => {
                     self.cursor += 1;
-                    prop_ident.push(c);
+                    prop_ident.push(c as char);
                 }
                 Some(_c) => return Err(LexerError::UnexpectedEndOfProperty),
                 None => return Err(LexerError::MissingPropertyIdentifier),
             }
         }
 
-        Ok(prop_ident.iter().collect())
+        Ok(prop_ident)
     }
 
-    fn get_prop_values(&mut self) -> Result<Vec<String>, LexerError> {
+    fn get_prop_values(&mut self) -> Result<PropValues<'a>, LexerError> {
         let mut prop_values = vec![];
         loop {
             self.trim_leading_whitespace();
-            match self.peek_char() {
-                Some('[') => {
+            match self.peek_byte() {
+                Some(b'[') => {
                     self.cursor += 1;
                     prop_values.push(self.get_prop_value()?);
                 }
@@ -98,40 +200,37 @@ impl<'a> Lexer<'a> {
         Ok(prop_values)
     }
 
-    fn get_prop_value(&mut self) -> Result<String, LexerError> {
-        let mut prop_value = vec![];
+    fn get_prop_value(&mut self) -> Result<Cow<'a, [u8]>, LexerError> {
+        let start = self.cursor;
         let mut escaped = false;
         loop {
-            match self.get_char() {
-                Some(']') if !escaped => break,
-                Some('\\') if !escaped => escaped = true,
-                Some(c) => {
-                    escaped = false;
-                    prop_value.push(c);
-                }
+            match self.get_byte() {
+                Some(b']') if !escaped => break,
+                Some(b'\\') if !escaped => escaped = true,
+                Some(_) => escaped = false,
                 None => return Err(LexerError::UnexpectedEndOfProperty),
             }
         }
 
-        Ok(prop_value.iter().collect())
+        Ok(Cow::Borrowed(&self.text[start..self.cursor - 1]))
     }
 }
 
 impl<'a> Iterator for Lexer<'a> {
-    type Item = Result<(Token, std::ops::Range<usize>), LexerError>;
+    type Item = Result<(Token<'a>, std::ops::Range<usize>), LexerError>;
 
     fn next(&mut self) -> Option<Self::Item> {
         let span_start = self.cursor;
-        let token = match self.peek_char() {
-            Some('(') => {
+        let token = match self.peek_byte() {
+            Some(b'(') => {
                 self.cursor += 1;
                 Token::StartGameTree
             }
-            Some(')') => {
+            Some(b')') => {
                 self.cursor += 1;
                 Token::EndGameTree
             }
-            Some(';') => {
+            Some(b';') => {
                 self.cursor += 1;
                 Token::StartNode
             }
@@ -150,8 +249,10 @@ impl<'a> Iterator for Lexer<'a> {
 
 #[cfg(test)]
 mod test {
-    use super::tokenize;
+    use std::borrow::Cow;
+
     use super::Token::*;
+    use super::{tokenize, unescape, LexerError};
 
     #[test]
     fn lexer() {
@@ -159,24 +260,39 @@ mod test {
         let expected = vec![
             (StartGameTree, 0..1),
             (StartNode, 1..2),
-            (Property(("SZ".to_string(), vec!["9".to_string()])), 2..7),
             (
-                Property(("C".to_string(), vec!["Some comment".to_string()])),
+                Property(("SZ".to_string(), vec![Cow::from(&b"9"[..])])),
+                2..7,
+            ),
+            (
+                Property(("C".to_string(), vec![Cow::from(&b"Some comment"[..])])),
                 7..22,
             ),
             (StartNode, 22..23),
-            (Property(("B".to_string(), vec!["de".to_string()])), 23..28),
+            (
+                Property(("B".to_string(), vec![Cow::from(&b"de"[..])])),
+                23..28,
+            ),
             (StartNode, 28..29),
-            (Property(("W".to_string(), vec!["fe".to_string()])), 29..34),
+            (
+                Property(("W".to_string(), vec![Cow::from(&b"fe"[..])])),
+                29..34,
+            ),
             (EndGameTree, 34..35),
             (StartGameTree, 35..36),
             (StartNode, 36..37),
-            (Property(("B".to_string(), vec!["de".to_string()])), 37..42),
+            (
+                Property(("B".to_string(), vec![Cow::from(&b"de"[..])])),
+                37..42,
+            ),
             (StartNode, 42..43),
-            (Property(("W".to_string(), vec!["ff".to_string()])), 43..48),
+            (
+                Property(("W".to_string(), vec![Cow::from(&b"ff"[..])])),
+                43..48,
+            ),
             (EndGameTree, 48..49),
         ];
-        let tokens: Vec<_> = tokenize(sgf).collect::<Result<_, _>>().unwrap();
+        let tokens: Vec<_> = tokenize(sgf.as_bytes()).collect::<Result<_, _>>().unwrap();
 
         assert_eq!(tokens, expected);
     }
@@ -188,13 +304,101 @@ mod test {
             (StartGameTree, 0..1),
             (StartNode, 1..2),
             (
-                Property(("CoPyright".to_string(), vec!["text".to_string()])),
+                Property(("CoPyright".to_string(), vec![Cow::from(&b"text"[..])])),
                 2..17,
             ),
             (EndGameTree, 17..18),
         ];
+        let tokens: Vec<_> = tokenize(sgf.as_bytes()).collect::<Result<_, _>>().unwrap();
+
+        assert_eq!(tokens, expected);
+    }
+
+    #[test]
+    fn invalid_utf8_values() {
+        let sgf = b"(;C[\x82\xa0])";
+        let expected = vec![
+            (StartGameTree, 0..1),
+            (StartNode, 1..2),
+            (
+                Property(("C".to_string(), vec![Cow::from(vec![0x82, 0xa0])])),
+                2..7,
+            ),
+            (EndGameTree, 7..8),
+        ];
         let tokens: Vec<_> = tokenize(sgf).collect::<Result<_, _>>().unwrap();
 
         assert_eq!(tokens, expected);
     }
+
+    #[test]
+    fn soft_line_breaks() {
+        let value = b"soft\\\nbreak\\\r\nand\nhard";
+        assert_eq!(&unescape(value, false)[..], b"softbreakand\nhard");
+        assert_eq!(&unescape(value, true)[..], b"softbreakand\nhard");
+    }
+
+    #[test]
+    fn raw_values() {
+        let sgf = "(;LB[aa:x\\:y][bb:\\\\])";
+        let tokens: Vec<_> = tokenize(sgf.as_bytes())
+            .map(|result| result.unwrap().0)
+            .collect();
+        let values = match &tokens[2] {
+            Property((_, values)) => values,
+            token => panic!("Unexpected token {:?}", token),
+        };
+        assert!(matches!(&values[0], Cow::Borrowed(b"aa:x\\:y")));
+        assert!(matches!(&values[1], Cow::Borrowed(b"bb:\\\\")));
+        assert_eq!(&unescape(&values[0], false)[..], b"aa:x:y");
+        assert_eq!(&unescape(&values[0], true)[..], b"aa:x\\:y");
+        assert_eq!(&unescape(&values[1], false)[..], b"bb:\\");
+        assert!(matches!(unescape(b"plain", false), Cow::Borrowed(b"plain")));
+    }
+
+    #[test]
+    fn identifier_limits() {
+        let sgf = b"(;B[aa]\x01\x02garbage[x][y]C [ok])";
+        let mut lexer = tokenize(sgf).with_identifier_limits(Some(2), true);
+        assert_eq!(lexer.nth(2).unwrap().unwrap().1, 2..7);
+        assert_eq!(
+            lexer.next().unwrap().unwrap_err(),
+            LexerError::UnexpectedPropertyIdentifier
+        );
+        lexer.skip_property();
+        assert_eq!(lexer.position(), 22);
+        let expected = (
+            Property(("C".to_string(), vec![Cow::from(&b"ok"[..])])),
+            22..28,
+        );
+        assert_eq!(lexer.next().unwrap().unwrap(), expected);
+
+        let mut lexer = tokenize(b"(;ABC[x])").with_identifier_limits(Some(2), false);
+        assert_eq!(
+            lexer.nth(2).unwrap().unwrap_err(),
+            LexerError::PropertyIdentifierTooLong
+        );
+        let mut lexer = tokenize(b"(;B C[x])").with_identifier_limits(None, true);
+        assert_eq!(
+            lexer.nth(2).unwrap().unwrap_err(),
+            LexerError::UnexpectedPropertyIdentifier
+        );
+    }
+
+    #[test]
+    fn leading_bom_and_whitespace() {
+        let sgf = b"\xef\xbb\xbf\r\n(;B[aa])";
+        let tokens: Vec<_> = tokenize(sgf).collect::<Result<_, _>>().unwrap();
+        let expected = vec![
+            (StartGameTree, 5..6),
+            (StartNode, 6..7),
+            (
+                Property(("B".to_string(), vec![Cow::from(&b"aa"[..])])),
+                7..12,
+            ),
+            (EndGameTree, 12..13),
+        ];
+
+        assert_eq!(tokens, expected);
+    }
 }
@@ -5,23 +5,70 @@
 //!
 //! For writing SGFs check out [`SgfNode::serialize`] for writing single game trees or
 //! [`serialize`](`serialize()`) for writing whole collections.
+//!
+//! # Features
+//!
+//! - `encoding`: decode property values from any charset declared by the root `CA` property
+//!   when using [`parse_bytes`], and encode text in that charset when using [`save_to`].
+//! - `fixtures`: bundle the crate's sample SGF files as `testing::fixtures` for use in test
+//!   suites, along with the `conformance` checker built from them.
+//! - `sorted-serialization`: serialize the values of list properties (like `AB` or `LB`) in sorted
+//!   order. Lists are stored in hash sets, so otherwise their order varies between runs, which
+//!   makes snapshot tests of serialized output unstable.
 
 #[macro_use]
 mod prop_macro;
 
+#[cfg(feature = "fixtures")]
+pub mod conformance;
 pub mod go;
+pub mod idents;
+pub mod testing;
 pub mod unknown_game;
 
+mod cursor;
+mod custom_game;
+mod dyn_prop;
 mod game_tree;
 mod lexer;
+mod node_builder;
+mod node_path;
 mod parser;
+mod pipeline;
+mod position;
 mod props;
+mod rng;
 mod serialize;
 mod sgf_node;
+mod sgf_tree;
+mod shared_tree;
+mod stats;
 
-pub use game_tree::{GameTree, GameType};
+pub use cursor::Cursor;
+pub use custom_game::{CustomGameParser, DynGameNode};
+pub use dyn_prop::{property_spec, DynProp, DynValue, PropertySpec, ValueType};
+pub use game_tree::{partition_by_game, GameTree, GameType, PartitionedCollection};
+pub use idents::Identifier;
 pub use lexer::LexerError;
-pub use parser::{parse, parse_with_options, ParseOptions, SgfParseError};
-pub use props::{Color, Double, PropertyType, SgfProp, SimpleText, Text};
-pub use serialize::serialize;
-pub use sgf_node::{InvalidNodeError, SgfNode};
+pub use node_builder::NodeBuilder;
+pub use node_path::{NodePath, TraversalPath};
+pub use parser::{
+    find_misplaced_root_properties, load_flexible, parse, parse_bytes, parse_bytes_with_options,
+    parse_events, parse_flexible, parse_from_reader, parse_lazy, parse_with_options,
+    parse_with_report, parse_with_spans, parse_with_trailing_content, ErrorLocation,
+    MisplacedRootProperty, ParseOptions, ParseReport, PropertyFilter, Recovery, RecoveryAction,
+    RejectedProperty, SgfEvent, SgfParseError, SgfParseErrorKind, SgfReader, TrailingContent,
+};
+pub use pipeline::{Pipeline, PipelineError};
+pub use position::GamePosition;
+pub use props::{
+    Color, Double, MoveAnnotation, NodeAnnotations, PositionEvaluation, PropertyType, SgfProp,
+    SgfPropError, SimpleText, Text,
+};
+pub use serialize::{
+    save_to, serialize, serialize_to, serialize_with_options, LineEnding, SerializeOptions,
+};
+pub use sgf_node::{InvalidNodeError, PropDiff, SearchControl, SgfNode, TraversalOrder};
+pub use sgf_tree::{NodeId, SgfTree};
+pub use shared_tree::{SharedNode, SubtreeInterner};
+pub use stats::{stats, InvalidValuePattern, PropertyStats, StatsDrift};
@@ -0,0 +1,113 @@
+use crate::{SgfNode, SgfProp};
+
+/// A builder for constructing [`SgfNode`] trees by hand.
+///
+/// The node returned by [`NodeBuilder::build`] is marked as a root, and nodes added with
+/// [`NodeBuilder::child`] aren't, so root status always matches the shape of the tree.
+///
+/// # Examples
+/// ```
+/// use sgf_parse::go::{Move, Point, Prop};
+/// use sgf_parse::NodeBuilder;
+///
+/// let node = NodeBuilder::new()
+///     .prop(Prop::SZ((9, 9)))
+///     .comment("Opening")
+///     .child(NodeBuilder::new().prop(Prop::B(Move::Move(Point { x: 4, y: 4 }))))
+///     .child(NodeBuilder::new().prop(Prop::B(Move::Move(Point { x: 2, y: 2 }))))
+///     .build();
+/// assert!(node.is_root());
+/// assert!(node.validate().is_ok());
+/// assert_eq!(node.serialize(), "(;SZ[9:9]C[Opening](;B[ee])(;B[cc]))");
+/// ```
+#[derive(Clone, Debug)]
+pub struct NodeBuilder<Prop: SgfProp> {
+    node: SgfNode<Prop>,
+}
+
+impl<Prop: SgfProp> NodeBuilder<Prop> {
+    /// Returns a new builder for a node with no properties or children.
+    pub fn new() -> Self {
+        Self {
+            node: SgfNode::new(vec![], vec![], false),
+        }
+    }
+
+    /// Adds a property to the node.
+    ///
+    /// If the node already has a property with the same identifier, it's replaced as by
+    /// [`SgfNode::set_property`].
+    ///
+    /// # Examples
+    /// ```
+    /// use sgf_parse::go::Prop;
+    /// use sgf_parse::NodeBuilder;
+    ///
+    /// let node = NodeBuilder::new().prop(Prop::KM(6.5)).prop(Prop::KM(7.5)).build();
+    /// assert_eq!(node.serialize(), "(;KM[7.5])");
+    /// ```
+    pub fn prop(mut self, prop: Prop) -> Self {
+        self.node.set_property(prop);
+        self
+    }
+
+    /// Sets the node's comment (the `C` property).
+    ///
+    /// # Examples
+    /// ```
+    /// use sgf_parse::go::Prop;
+    /// use sgf_parse::NodeBuilder;
+    ///
+    /// let node = NodeBuilder::<Prop>::new().comment("Look at [this]").build();
+    /// assert_eq!(node.serialize(), "(;C[Look at [this\\]])");
+    /// ```
+    pub fn comment(self, text: impl Into<String>) -> Self {
+        self.prop(Prop::new("C".to_string(), vec![text.into()]))
+    }
+
+    /// Adds a child built from the provided builder after any existing children.
+    pub fn child(mut self, child: NodeBuilder<Prop>) -> Self {
+        self.node.children.push(child.node);
+        self
+    }
+
+    /// Returns the built node, marked as a root node.
+    pub fn build(mut self) -> SgfNode<Prop> {
+        self.node.is_root = true;
+        self.node
+    }
+}
+
+impl<Prop: SgfProp> Default for NodeBuilder<Prop> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::NodeBuilder;
+    use crate::go::{parse, Prop};
+    use crate::SgfProp;
+
+    #[test]
+    fn builds_same_tree_as_parser() {
+        let node = NodeBuilder::new()
+            .prop(Prop::new("GM".to_string(), vec!["1".to_string()]))
+            .child(
+                NodeBuilder::new()
+                    .prop(Prop::new("B".to_string(), vec!["dd".to_string()]))
+                    .comment("First")
+                    .child(
+                        NodeBuilder::new().prop(Prop::new("W".to_string(), vec!["pp".to_string()])),
+                    ),
+            )
+            .child(NodeBuilder::new().comment("Empty"))
+            .build();
+        let expected = parse("(;GM[1](;B[dd]C[First];W[pp])(;C[Empty]))")
+            .unwrap()
+            .remove(0);
+        assert_eq!(node, expected);
+        assert!(node.children().all(|child| !child.is_root()));
+    }
+}
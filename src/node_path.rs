@@ -0,0 +1,247 @@
+use std::sync::Arc;
+
+/// The location of a node in a tree, as the child indices leading to it from the root.
+///
+/// The empty path refers to the root itself. Paths are plain data, so they stay valid after a
+/// tree is serialized and parsed again, which makes them suitable for storing positions between
+/// sessions. Use [`SgfNode::node_at`](crate::SgfNode::node_at) to look up the node at a path and
+/// [`SgfNode::path_of`](crate::SgfNode::path_of) to find the path of a node.
+///
+/// Paths display as their indices separated by `.` (with the root as the empty string), and can
+/// be parsed back from that form.
+///
+/// # Examples
+/// ```
+/// use sgf_parse::go::parse;
+/// use sgf_parse::NodePath;
+///
+/// let node = parse("(;SZ[9](;B[ee];W[ce])(;B[gc];W[gd]))").unwrap().remove(0);
+/// let path = NodePath::from(vec![1, 0]);
+/// assert_eq!(path.to_string(), "1.0");
+/// assert_eq!(node.node_at(&path).unwrap().serialize(), "(;W[gd])");
+///
+/// let stored: NodePath = "1.0".parse().unwrap();
+/// assert_eq!(stored, path);
+/// assert_eq!(stored.parent(), Some(NodePath::from(vec![1])));
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct NodePath(Vec<usize>);
+
+impl NodePath {
+    /// Returns the path of the root node.
+    pub fn root() -> Self {
+        Self(vec![])
+    }
+
+    /// Returns `true` if this is the path of the root node.
+    pub fn is_root(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Returns the child indices in the path.
+    pub fn as_slice(&self) -> &[usize] {
+        &self.0
+    }
+
+    /// Returns the path of the node's parent, or `None` for the root.
+    pub fn parent(&self) -> Option<Self> {
+        let (_, indices) = self.0.split_last()?;
+        Some(Self(indices.to_vec()))
+    }
+
+    /// Returns the path of the node's child with the provided index.
+    pub fn child(&self, index: usize) -> Self {
+        let mut indices = self.0.clone();
+        indices.push(index);
+        Self(indices)
+    }
+
+    /// Adds a child index to the end of the path.
+    pub fn push(&mut self, index: usize) {
+        self.0.push(index);
+    }
+
+    /// Removes the last child index from the path and returns it, or `None` for the root.
+    pub fn pop(&mut self) -> Option<usize> {
+        self.0.pop()
+    }
+}
+
+impl std::ops::Deref for NodePath {
+    type Target = [usize];
+
+    fn deref(&self) -> &[usize] {
+        &self.0
+    }
+}
+
+impl AsRef<[usize]> for NodePath {
+    fn as_ref(&self) -> &[usize] {
+        &self.0
+    }
+}
+
+impl From<Vec<usize>> for NodePath {
+    fn from(indices: Vec<usize>) -> Self {
+        Self(indices)
+    }
+}
+
+impl From<&[usize]> for NodePath {
+    fn from(indices: &[usize]) -> Self {
+        Self(indices.to_vec())
+    }
+}
+
+impl From<NodePath> for Vec<usize> {
+    fn from(path: NodePath) -> Self {
+        path.0
+    }
+}
+
+impl std::fmt::Display for NodePath {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, index) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str(".")?;
+            }
+            write!(f, "{}", index)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::str::FromStr for NodePath {
+    type Err = std::num::ParseIntError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.is_empty() {
+            return Ok(Self::root());
+        }
+        s.split('.')
+            .map(str::parse)
+            .collect::<Result<_, _>>()
+            .map(Self)
+    }
+}
+
+/// The path of a node yielded by [`SgfNode::traverse`](crate::SgfNode::traverse).
+///
+/// Each path shares its ancestors' indices with the paths yielded before it, so traversing a
+/// tree takes constant time per node. Use [`TraversalPath::to_node_path`] to get a [`NodePath`]
+/// for the nodes you need.
+#[derive(Clone)]
+pub struct TraversalPath(Option<Arc<PathLink>>);
+
+struct PathLink {
+    parent: Option<Arc<PathLink>>,
+    index: usize,
+    depth: usize,
+}
+
+impl TraversalPath {
+    pub(crate) fn root() -> Self {
+        Self(None)
+    }
+
+    pub(crate) fn child(&self, index: usize) -> Self {
+        Self(Some(Arc::new(PathLink {
+            parent: self.0.clone(),
+            index,
+            depth: self.depth() + 1,
+        })))
+    }
+
+    /// Returns the number of child indices in the path.
+    pub fn depth(&self) -> usize {
+        self.0.as_ref().map_or(0, |link| link.depth)
+    }
+
+    /// Returns the node's index among its siblings, or `None` for the root.
+    pub fn last(&self) -> Option<usize> {
+        self.0.as_ref().map(|link| link.index)
+    }
+
+    /// Returns the path as a [`NodePath`].
+    pub fn to_node_path(&self) -> NodePath {
+        let mut indices = vec![0; self.depth()];
+        let mut link = self.0.as_deref();
+        while let Some(current) = link {
+            indices[current.depth - 1] = current.index;
+            link = current.parent.as_deref();
+        }
+        NodePath(indices)
+    }
+}
+
+impl From<TraversalPath> for NodePath {
+    fn from(path: TraversalPath) -> Self {
+        path.to_node_path()
+    }
+}
+
+impl std::fmt::Debug for TraversalPath {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("TraversalPath")
+            .field(&self.to_node_path().0)
+            .finish()
+    }
+}
+
+impl std::fmt::Display for TraversalPath {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.to_node_path().fmt(f)
+    }
+}
+
+impl Drop for PathLink {
+    // Drop the ancestors iteratively, since long games would overflow the stack otherwise.
+    fn drop(&mut self) {
+        let mut parent = self.parent.take();
+        while let Some(link) = parent {
+            parent = match Arc::try_unwrap(link) {
+                Ok(mut link) => link.parent.take(),
+                Err(_) => None,
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{NodePath, TraversalPath};
+
+    #[test]
+    fn string_round_trip() {
+        for indices in [vec![], vec![0], vec![3, 0, 12]] {
+            let path = NodePath::from(indices);
+            assert_eq!(path.to_string().parse::<NodePath>().unwrap(), path);
+        }
+        assert_eq!(NodePath::root().to_string(), "");
+        assert!("1..2".parse::<NodePath>().is_err());
+        assert!("1.-2".parse::<NodePath>().is_err());
+        assert!(".".parse::<NodePath>().is_err());
+    }
+
+    #[test]
+    fn parent_and_child() {
+        let mut path = NodePath::root().child(2).child(0);
+        assert_eq!(path.as_slice(), [2, 0]);
+        assert_eq!(path.parent().unwrap().parent(), Some(NodePath::root()));
+        assert!(NodePath::root().parent().is_none());
+        assert_eq!(path.pop(), Some(0));
+        path.push(5);
+        assert_eq!(Vec::from(path), vec![2, 5]);
+    }
+
+    #[test]
+    fn traversal_path() {
+        let path = TraversalPath::root().child(2).child(0).child(7);
+        assert_eq!(path.depth(), 3);
+        assert_eq!(path.last(), Some(7));
+        assert_eq!(path.to_node_path().as_slice(), [2, 0, 7]);
+        assert_eq!(path.to_string(), "2.0.7");
+        assert_eq!(TraversalPath::root().to_node_path(), NodePath::root());
+        assert!(TraversalPath::root().last().is_none());
+    }
+}
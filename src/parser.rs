@@ -1,9 +1,16 @@
-use std::ptr::NonNull;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashSet};
+use std::io::{self, BufRead};
+use std::ops::Range;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 
 use crate::go;
-use crate::lexer::{tokenize, LexerError, Token};
+use crate::lexer::{tokenize, unescape, Lexer, LexerError, Token};
+use crate::props::escape_raw_values;
+use crate::props::parse::{has_compose_values, parse_tuple};
 use crate::unknown_game;
-use crate::{GameTree, GameType, SgfNode, SgfProp};
+use crate::{CustomGameParser, GameTree, NodePath, SgfNode, SgfProp};
 
 /// Returns the [`GameTree`] values parsed from the provided text using default parsing options.
 ///
@@ -29,56 +36,1584 @@ pub fn parse(text: &str) -> Result<Vec<GameTree>, SgfParseError> {
 /// Returns the [`GameTree`] values parsed from the provided text.
 ///
 /// # Errors
+/// If the text can't be parsed as an SGF FF\[4\] collection, then an error is returned. The
+/// error's [`location`](SgfParseError::location) gives where it was found, which is useful for
+/// user facing error messages. Lexer errors and unbalanced parentheses are located precisely. For
+/// other errors the location is the span of the game tree containing the error.
+///
+/// # Examples
+/// ```
+/// use sgf_parse::{parse_with_options, ParseOptions, GameType, SgfParseErrorKind};
+///
+/// let sgf = "(;SZ[9]C[Some comment];B[de];W[fe])(;B[de];W[ff])";
+/// let gametrees = parse_with_options(sgf, &ParseOptions::default()).unwrap();
+/// assert!(gametrees.len() == 2);
+/// assert!(gametrees.iter().all(|gametree| gametree.gametype() == GameType::Go));
+///
+/// let sgf = "(;B[de]C[Comment]\n;W[ef]))";
+/// let error = parse_with_options(sgf, &ParseOptions::default()).unwrap_err();
+/// assert_eq!(error.kind, SgfParseErrorKind::UnexpectedGameTreeEnd);
+/// let location = error.location.as_ref().unwrap();
+/// assert_eq!(location.span, 25..26);
+/// assert_eq!((location.line, location.column), (2, 8));
+/// assert_eq!(error.to_string(), "Unexpected end of game tree at line 2, column 8");
+/// ```
+pub fn parse_with_options(
+    text: &str,
+    options: &ParseOptions,
+) -> Result<Vec<GameTree>, SgfParseError> {
+    parse_data(text.as_bytes(), Input::Text, options)
+}
+
+/// Returns the [`GameTree`] values parsed from the provided bytes using default parsing options.
+///
+/// Property values are decoded according to each game tree's `CA` property. See
+/// [`parse_bytes_with_options`] for details.
+///
+/// # Errors
+/// If the data can't be parsed as an SGF FF\[4\] collection, then an error is returned.
+///
+/// # Examples
+/// ```
+/// use sgf_parse::{parse_bytes, GameTree, SgfProp};
+/// use sgf_parse::go::Prop;
+///
+/// let sgf = b"(;CA[ISO-8859-1]C[Caf\xe9])";
+/// let gametree = parse_bytes(sgf).unwrap().into_iter().next().unwrap();
+/// let node = gametree.into_go_node().unwrap();
+/// assert_eq!(node.get_property("C"), Some(&Prop::C("Café".into())));
+///
+/// let sgf = b"(;C[\x93Quoted\x94 \x80100])";
+/// let gametree = parse_bytes(sgf).unwrap().into_iter().next().unwrap();
+/// let node = gametree.into_go_node().unwrap();
+/// assert_eq!(node.get_property("C"), Some(&Prop::C("“Quoted” €100".into())));
+/// ```
+pub fn parse_bytes(data: &[u8]) -> Result<Vec<GameTree>, SgfParseError> {
+    parse_bytes_with_options(data, &ParseOptions::default())
+}
+
+/// Returns the [`GameTree`] values parsed from the provided bytes.
+///
+/// Data starting with a UTF-16 byte order mark is decoded as UTF-16 before parsing. A UTF-8 byte
+/// order mark is ignored. Otherwise property values are decoded with the charset declared by the
+/// game tree's root `CA` property. ISO-8859-1 and UTF-8 are always supported. Like web browsers,
+/// ISO-8859-1 is treated as Windows-1252, which is what most such files actually use. With the
+/// `encoding` feature enabled, values are decoded from any other charset supported by
+/// [`encoding_rs`](https://docs.rs/encoding_rs). Values which can't be decoded are decoded lossily
+/// if [`ParseOptions::lossy_decoding`] is set. Without a `CA` property, values are decoded as
+/// UTF-8 if valid and as ISO-8859-1 (the SGF default) otherwise.
+///
+/// # Errors
+/// If the data can't be parsed as an SGF FF\[4\] collection, or a property value can't be
+/// decoded, then an error is returned.
+///
+/// # Examples
+/// ```
+/// use sgf_parse::{parse_bytes_with_options, ParseOptions, SgfParseErrorKind};
+///
+/// let sgf = b"(;CA[x-unknown]C[\x82\xa0])";
+/// let error = parse_bytes_with_options(sgf, &ParseOptions::default()).unwrap_err();
+/// assert_eq!(error.kind, SgfParseErrorKind::InvalidTextEncoding);
+///
+/// let options = ParseOptions {
+///     lossy_decoding: true,
+///     ..ParseOptions::default()
+/// };
+/// assert!(parse_bytes_with_options(sgf, &options).is_ok());
+/// ```
+pub fn parse_bytes_with_options(
+    data: &[u8],
+    options: &ParseOptions,
+) -> Result<Vec<GameTree>, SgfParseError> {
+    match decode_utf16(data, options)? {
+        Some(text) => parse_data(text.as_bytes(), Input::Text, options),
+        None => parse_data(data, Input::Bytes, options),
+    }
+}
+
+// Parse the data, dropping the spans of the gametrees.
+fn parse_data(
+    data: &[u8],
+    input: Input,
+    options: &ParseOptions,
+) -> Result<Vec<GameTree>, SgfParseError> {
+    Ok(parse_spanned(data, input, options)?
+        .into_iter()
+        .map(|(gametree, _span)| gametree)
+        .collect())
+}
+
+/// Returns the [`GameTree`] values parsed from the provided reader.
+///
+/// Input is read incrementally, one game tree at a time, so files, sockets or decompressing
+/// readers can be parsed without first reading everything into a `String`. Use [`SgfReader`]
+/// to process game trees one at a time instead.
+///
+/// # Errors
+/// If the input can't be read or parsed as SGF FF\[4\], then an error is returned. Error
+/// locations are relative to the start of the input, as they are for [`parse`].
+///
+/// # Examples
+/// ```
+/// use std::io::BufReader;
+/// use sgf_parse::{parse_from_reader, ParseOptions};
+///
+/// let reader = BufReader::new("(;B[de];W[fe])\n(;B[dd])".as_bytes());
+/// let gametrees = parse_from_reader(reader, &ParseOptions::default()).unwrap();
+/// assert_eq!(gametrees.len(), 2);
+/// ```
+pub fn parse_from_reader(
+    mut reader: impl BufRead,
+    options: &ParseOptions,
+) -> Result<Vec<GameTree>, SgfParseError> {
+    let mut gametrees = vec![];
+    let mut position = StreamPosition::default();
+    while let Some((data, start)) = read_gametree(&mut reader, options, &mut position)? {
+        gametrees.push(parse_single_gametree(&data, start, options)?);
+    }
+
+    Ok(gametrees)
+}
+
+/// Returns the [`GameTree`] values parsed from the provided text along with their byte ranges.
+///
+/// Each range covers the game tree's text in the input, which is useful for error reporting or
+/// for selectively rewriting parts of a collection.
+///
+/// # Errors
 /// If the text can't be parsed as an SGF FF\[4\] collection, then an error is returned.
 ///
 /// # Examples
 /// ```
-/// use sgf_parse::{parse_with_options, ParseOptions, GameType};
+/// use sgf_parse::{parse_with_spans, ParseOptions};
+///
+/// let sgf = "(;B[de];W[fe])\n(;B[dd])";
+/// let gametrees = parse_with_spans(sgf, &ParseOptions::default()).unwrap();
+/// let spans: Vec<_> = gametrees.iter().map(|(_, span)| &sgf[span.clone()]).collect();
+/// assert_eq!(spans, vec!["(;B[de];W[fe])", "(;B[dd])"]);
+/// ```
+pub fn parse_with_spans(
+    text: &str,
+    options: &ParseOptions,
+) -> Result<Vec<(GameTree, Range<usize>)>, SgfParseError> {
+    parse_spanned(text.as_bytes(), Input::Text, options)
+}
+
+/// Content following the last complete game tree as returned by
+/// [`parse_with_trailing_content`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TrailingContent {
+    /// Byte range of the trailing content in the input.
+    pub span: Range<usize>,
+    /// The first few characters of the trailing content.
+    pub preview: String,
+}
+
+/// Returns the [`GameTree`] values parsed from the provided text, and any trailing content.
+///
+/// Parsing stops after the last complete game tree which is followed only by other complete game
+/// trees. Anything after that (unbalanced parentheses, stray nodes, unterminated game trees) is
+/// returned as [`TrailingContent`] instead of failing, so callers can decide whether to accept
+/// the file.
+///
+/// # Errors
+/// If the text before the trailing content can't be parsed, then an error is returned.
+///
+/// # Examples
+/// ```
+/// use sgf_parse::{parse_with_trailing_content, ParseOptions};
+///
+/// let sgf = "(;B[de];W[fe]) ) garbage";
+/// let (gametrees, trailing) = parse_with_trailing_content(sgf, &ParseOptions::default()).unwrap();
+/// assert_eq!(gametrees.len(), 1);
+/// let trailing = trailing.unwrap();
+/// assert_eq!(trailing.span, 15..24);
+/// assert_eq!(trailing.preview, ") garbage");
+/// ```
+pub fn parse_with_trailing_content(
+    text: &str,
+    options: &ParseOptions,
+) -> Result<(Vec<GameTree>, Option<TrailingContent>), SgfParseError> {
+    const PREVIEW_LENGTH: usize = 32;
+    let data = text.as_bytes();
+    let mut gametree_depth: u64 = 0;
+    let mut end = 0;
+    for result in tokenize_with_options(data, options) {
+        match result {
+            Ok((Token::StartGameTree, _)) => gametree_depth += 1,
+            Ok((Token::EndGameTree, span)) if gametree_depth > 0 => {
+                gametree_depth -= 1;
+                if gametree_depth == 0 {
+                    end = span.end;
+                }
+            }
+            Ok((_, _)) if gametree_depth > 0 => {}
+            _ => break,
+        }
+    }
+    let gametrees = parse_data(&data[..end], Input::Text, options)?;
+    let trailing_start = end + (text.len() - end - text[end..].trim_start().len());
+    let trailing = if trailing_start < text.len() {
+        Some(TrailingContent {
+            span: trailing_start..text.len(),
+            preview: text[trailing_start..]
+                .chars()
+                .take(PREVIEW_LENGTH)
+                .collect(),
+        })
+    } else {
+        None
+    };
+
+    Ok((gametrees, trailing))
+}
+
+/// A `GM` or `FF` property found outside a game tree's root node.
+///
+/// Game type and file format are only detected from the root node, so these properties are
+/// otherwise ignored. Returned by [`find_misplaced_root_properties`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MisplacedRootProperty {
+    /// Index of the game tree in the collection.
+    pub gametree: usize,
+    /// Path to the node from the game tree's root node.
+    pub path: NodePath,
+    /// Identifier of the property.
+    pub identifier: String,
+    /// Values of the property.
+    pub values: Vec<String>,
+}
+
+/// Returns any `GM` or `FF` properties found in non-root nodes of the provided text.
+///
+/// Files with these properties are likely to have their game type misdetected. Set
+/// [`ParseOptions::hoist_misplaced_root_properties`] to move them to the root node while
+/// parsing.
+///
+/// # Errors
+/// If the text can't be tokenized or split into game trees, then an error is returned.
+///
+/// # Examples
+/// ```
+/// use sgf_parse::find_misplaced_root_properties;
+///
+/// let misplaced = find_misplaced_root_properties("(;GM[1])(;C[Intro](;B[aa])(;GM[2]))").unwrap();
+/// assert_eq!(misplaced.len(), 1);
+/// assert_eq!(misplaced[0].gametree, 1);
+/// assert_eq!(misplaced[0].path.to_string(), "1");
+/// assert_eq!(misplaced[0].identifier, "GM");
+/// assert_eq!(misplaced[0].values, vec!["2"]);
+/// ```
+pub fn find_misplaced_root_properties(
+    text: &str,
+) -> Result<Vec<MisplacedRootProperty>, SgfParseError> {
+    let tokens: Vec<SpannedToken> = tokenize(text.as_bytes())
+        .collect::<Result<_, _>>()
+        .map_err(SgfParseErrorKind::LexerError)?;
+    let mut misplaced = vec![];
+    let gametree_ranges = split_by_gametree(&tokens).map_err(|(e, _)| e)?;
+    for (gametree, range) in gametree_ranges.into_iter().enumerate() {
+        for (path, token) in misplaced_root_properties(&tokens[range]) {
+            let (identifier, values) = match token {
+                Token::Property(prop) => prop,
+                _ => unreachable!(),
+            };
+            misplaced.push(MisplacedRootProperty {
+                gametree,
+                path,
+                identifier: identifier.clone(),
+                values: values
+                    .iter()
+                    .map(|value| String::from_utf8_lossy(&unescape(value, false)).into_owned())
+                    .collect(),
+            });
+        }
+    }
+
+    Ok(misplaced)
+}
+
+// Parse the data, returning the span of each gametree, or the located error.
+fn parse_spanned(
+    data: &[u8],
+    input: Input,
+    options: &ParseOptions,
+) -> Result<Vec<(GameTree, Range<usize>)>, SgfParseError> {
+    let mut tokens = vec![];
+    let mut lexer = tokenize_with_options(data, options);
+    while let Some(result) = lexer.next() {
+        let start = tokens.last().map_or(0, |(_, span): &SpannedToken| span.end);
+        if tokens.len() % CANCELLATION_CHECK_INTERVAL == 0 {
+            check_cancelled(options)
+                .map_err(|e| SgfParseError::from(e).located(data, start..start))?;
+        }
+        match result {
+            Ok(token) => tokens.push(token),
+            Err(e) => {
+                let span = start..lexer.position();
+                return Err(SgfParseError::from(e).located(data, span));
+            }
+        }
+    }
+    let gametree_ranges = split_by_gametree(&tokens).map_err(|(e, i)| {
+        let span = match tokens.get(i) {
+            Some((_, span)) => span.clone(),
+            None => data.len()..data.len(),
+        };
+        SgfParseError::from(e).located(data, span)
+    })?;
+    let gametree_tokens = split_tokens(tokens, &gametree_ranges);
+    gametree_tokens
+        .into_iter()
+        .map(|gametree_tokens| {
+            let span = gametree_span(&gametree_tokens);
+            match parse_gametree_tokens(gametree_tokens, input, options, None) {
+                Ok(gametree) => Ok((gametree, span)),
+                Err(e) => {
+                    let span = match &e {
+                        SgfParseErrorKind::UnknownProperty(property)
+                        | SgfParseErrorKind::InvalidProperty(property) => property.span.clone(),
+                        _ => span,
+                    };
+                    Err(SgfParseError::from(e).located(data, span))
+                }
+            }
+        })
+        .collect::<Result<_, _>>()
+}
+
+/// A recovery action taken by [`parse_with_report`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Recovery {
+    /// What was done.
+    pub action: RecoveryAction,
+    /// Byte range of the input the action applies to.
+    pub span: Range<usize>,
+}
+
+/// Kinds of [`Recovery`] actions.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RecoveryAction {
+    /// Content outside any game tree, or which couldn't be tokenized, was skipped.
+    SkippedContent,
+    /// A game tree left open at the end of the input was closed.
+    ClosedGameTree,
+    /// A FF\[3\] style mixed case identifier was converted. Holds the original identifier.
+    ConvertedIdentifier(String),
+    /// A property whose identifier had no upper case letters was dropped. Holds the identifier.
+    DroppedProperty(String),
+    /// A property was dropped by [`ParseOptions::property_filter`]. Holds the identifier.
+    FilteredProperty(String),
+    /// A node was inserted before properties at the start of a game tree.
+    InsertedNode,
+    /// A game tree which failed to parse was skipped.
+    SkippedGameTree(SgfParseError),
+}
+
+/// Report of the recovery actions taken by [`parse_with_report`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ParseReport {
+    /// Recovery actions in the order of their position in the input.
+    pub recoveries: Vec<Recovery>,
+    /// Byte range of the input each returned game tree was parsed from.
+    pub gametree_spans: Vec<Range<usize>>,
+}
+
+impl ParseReport {
+    /// Returns `true` if no recovery actions were needed.
+    pub fn is_clean(&self) -> bool {
+        self.recoveries.is_empty()
+    }
+
+    /// Returns the recovery actions which changed the game tree with the provided index.
+    ///
+    /// These are the recoveries within the game tree's span: converted identifiers, dropped and
+    /// filtered properties, inserted nodes, closed game trees, and content skipped after a
+    /// tokenizing error. Content skipped between game trees doesn't change any of them. Other
+    /// changes made deliberately through [`ParseOptions`], like root overrides, aren't recoveries
+    /// and aren't reported.
+    ///
+    /// Returns an empty iterator if there's no game tree with the provided index.
+    ///
+    /// # Examples
+    /// ```
+    /// use sgf_parse::{parse_with_report, ParseOptions, RecoveryAction};
+    ///
+    /// let sgf = "(;B[de]) C[junk] (;GaMe[1]W[ee])";
+    /// let (gametrees, report) = parse_with_report(sgf, &ParseOptions::default()).unwrap();
+    /// assert_eq!(report.modifications(0).count(), 0);
+    /// let actions: Vec<_> = report.modifications(1).map(|r| r.action.clone()).collect();
+    /// assert_eq!(actions, vec![RecoveryAction::ConvertedIdentifier("GaMe".to_string())]);
+    /// ```
+    pub fn modifications(&self, gametree: usize) -> impl Iterator<Item = &Recovery> {
+        let span = self.gametree_spans.get(gametree).cloned().unwrap_or(0..0);
+        self.recoveries.iter().filter(move |recovery| {
+            !span.is_empty() && span.start <= recovery.span.start && recovery.span.end <= span.end
+        })
+    }
+
+    /// Returns `true` if serializing the game tree with the provided index would reproduce the
+    /// content of its source text.
+    ///
+    /// Formatting like whitespace may still differ. See [`ParseReport::modifications`] for what
+    /// counts as a change. Returns `false` if there's no game tree with the provided index.
+    ///
+    /// # Examples
+    /// ```
+    /// use sgf_parse::{parse_with_report, ParseOptions};
+    ///
+    /// let sgf = "(;B[de])(;W[ee]";
+    /// let (gametrees, report) = parse_with_report(sgf, &ParseOptions::default()).unwrap();
+    /// assert!(report.is_faithful(0));
+    /// assert!(!report.is_faithful(1));
+    /// ```
+    pub fn is_faithful(&self, gametree: usize) -> bool {
+        gametree < self.gametree_spans.len() && self.modifications(gametree).next().is_none()
+    }
+}
+
+/// Returns the [`GameTree`] values parsed from the provided text, recovering from problems where
+/// possible, along with a report of every recovery action taken.
+///
+/// Unlike [`parse_with_options`], problems don't stop parsing. Content outside game trees is
+/// skipped, anything after content which can't be tokenized is skipped, game trees left open at
+/// the end of the input are closed, and game trees which fail to parse are skipped. Identifier
+/// conversion and node insertion still depend on [`ParseOptions::convert_mixed_case_identifiers`]
+/// and [`ParseOptions::recover_missing_nodes`], but are reported, as are properties dropped by
+/// [`ParseOptions::property_filter`].
+///
+/// # Errors
+/// Only returns an error if parsing is cancelled.
+///
+/// # Examples
+/// ```
+/// use sgf_parse::{parse_with_report, ParseOptions, RecoveryAction};
+///
+/// let sgf = "(;B[de]) C[junk] (;GaMe[1]W[ee](;B[ff])";
+/// let (gametrees, report) = parse_with_report(sgf, &ParseOptions::default()).unwrap();
+/// assert_eq!(gametrees.len(), 2);
+/// let actions: Vec<_> = report.recoveries.iter().map(|r| r.action.clone()).collect();
+/// assert_eq!(
+///     actions,
+///     vec![
+///         RecoveryAction::SkippedContent,
+///         RecoveryAction::ConvertedIdentifier("GaMe".to_string()),
+///         RecoveryAction::ClosedGameTree,
+///     ]
+/// );
+/// assert_eq!(&sgf[report.recoveries[0].span.clone()], "C[junk]");
+/// ```
+pub fn parse_with_report(
+    text: &str,
+    options: &ParseOptions,
+) -> Result<(Vec<GameTree>, ParseReport), SgfParseError> {
+    let data = text.as_bytes();
+    let mut recoveries = vec![];
+    let mut tokens: Vec<SpannedToken> = vec![];
+    let mut gametree_depth: usize = 0;
+    let mut lexer = tokenize_with_options(data, options);
+    // End of the last token or skipped property.
+    let mut last_end = 0;
+    let mut i = 0;
+    while let Some(result) = lexer.next() {
+        if i % CANCELLATION_CHECK_INTERVAL == 0 {
+            check_cancelled(options)?;
+        }
+        i += 1;
+        let (token, span) = match result {
+            Ok(result) => result,
+            Err(e) => {
+                let start = last_end;
+                let start = start + (text.len() - start - text[start..].trim_start().len());
+                // Properties with identifiers rejected by the options are skipped individually.
+                let end = match e {
+                    LexerError::UnexpectedPropertyIdentifier
+                    | LexerError::PropertyIdentifierTooLong => {
+                        lexer.skip_property();
+                        start + text[start..lexer.position()].trim_end().len()
+                    }
+                    _ => data.len(),
+                };
+                recoveries.push(Recovery {
+                    action: RecoveryAction::SkippedContent,
+                    span: start..end,
+                });
+                if end == data.len() {
+                    break;
+                }
+                last_end = end;
+                continue;
+            }
+        };
+        // Property spans include any whitespace following their values.
+        let span = span.start..span.start + text[span].trim_end().len();
+        last_end = span.end;
+        match &token {
+            Token::StartGameTree => gametree_depth += 1,
+            Token::EndGameTree if gametree_depth > 0 => gametree_depth -= 1,
+            _ if gametree_depth == 0 => {
+                match recoveries.last_mut() {
+                    Some(Recovery {
+                        action: RecoveryAction::SkippedContent,
+                        span: skipped,
+                    }) if tokens.last().map_or(0, |(_, span)| span.end) <= skipped.end => {
+                        skipped.end = span.end;
+                    }
+                    _ => recoveries.push(Recovery {
+                        action: RecoveryAction::SkippedContent,
+                        span,
+                    }),
+                }
+                continue;
+            }
+            _ => {}
+        }
+        tokens.push((token, span));
+    }
+    for _ in 0..gametree_depth {
+        recoveries.push(Recovery {
+            action: RecoveryAction::ClosedGameTree,
+            span: data.len()..data.len(),
+        });
+        tokens.push((Token::EndGameTree, data.len()..data.len()));
+    }
+
+    let mut gametrees = vec![];
+    let mut gametree_spans = vec![];
+    let gametree_ranges = split_by_gametree(&tokens).map_err(|(e, _)| e)?;
+    for gametree_tokens in split_tokens(tokens, &gametree_ranges) {
+        let span = gametree_span(&gametree_tokens);
+        let mut gametree_recoveries = vec![];
+        let result = parse_gametree_tokens(
+            gametree_tokens,
+            Input::Text,
+            options,
+            Some(&mut gametree_recoveries),
+        );
+        match result {
+            Ok(gametree) => {
+                gametrees.push(gametree);
+                gametree_spans.push(span);
+                recoveries.extend(gametree_recoveries);
+            }
+            Err(SgfParseErrorKind::Cancelled) => return Err(SgfParseErrorKind::Cancelled.into()),
+            Err(e) => recoveries.push(Recovery {
+                action: RecoveryAction::SkippedGameTree(e.into()),
+                span,
+            }),
+        }
+    }
+    recoveries.sort_by_key(|recovery| recovery.span.start);
+
+    Ok((
+        gametrees,
+        ParseReport {
+            recoveries,
+            gametree_spans,
+        },
+    ))
+}
+
+/// Returns the [`GameTree`] values parsed from the provided text, repairing it if needed, along
+/// with a report of what was fixed.
+///
+/// This is the recommended way to read SGF files from the wild. The text is first parsed
+/// strictly, and if that fails it's parsed again with [`parse_with_report`], converting FF\[3\]
+/// identifiers, inserting missing nodes, and decoding lossily. The report is clean if the strict
+/// parse succeeded. Use [`load_flexible`] to read a file.
+///
+/// # Examples
+/// ```
+/// use sgf_parse::{parse_flexible, RecoveryAction};
+///
+/// let (gametrees, report) = parse_flexible("(;B[de];W[fe])");
+/// assert_eq!(gametrees.len(), 1);
+/// assert!(report.is_clean());
+///
+/// let (gametrees, report) = parse_flexible("(;PlayerBlack[Lee];B[de]");
+/// assert_eq!(gametrees[0].to_string(), "(;PB[Lee];B[de])");
+/// let actions: Vec<_> = report.recoveries.iter().map(|r| r.action.clone()).collect();
+/// assert_eq!(
+///     actions,
+///     vec![
+///         RecoveryAction::ConvertedIdentifier("PlayerBlack".to_string()),
+///         RecoveryAction::ClosedGameTree,
+///     ]
+/// );
+/// ```
+pub fn parse_flexible(text: &str) -> (Vec<GameTree>, ParseReport) {
+    if let Ok(gametrees) = parse_spanned(text.as_bytes(), Input::Text, &strict_options()) {
+        return clean_report(gametrees);
+    }
+    // Without a cancel flag, parsing with a report can't fail.
+    parse_with_report(text, &lenient_options()).unwrap()
+}
+
+/// Returns the [`GameTree`] values read from the file at `path`, repairing them if needed, along
+/// with a report of what was fixed.
+///
+/// The file is first parsed strictly as by [`parse_bytes`], so any charset declared by `CA` is
+/// respected. If that fails it's repaired as by [`parse_flexible`]. For repairs, the whole file is
+/// decoded with the charset declared by the first game tree's `CA` property. Without a supported
+/// `CA` property, bytes which aren't valid UTF-8 are decoded as ISO-8859-1 (the SGF default).
+/// Spans in the report refer to the text as converted to UTF-8.
+///
+/// # Errors
+/// Returns an error if the file can't be read.
+///
+/// # Examples
+/// ```
+/// use sgf_parse::load_flexible;
+///
+/// let path = std::env::temp_dir().join("sgf_parse_load_flexible_example.sgf");
+/// std::fs::write(&path, b"(;C[Caf\xe9];B[de]").unwrap();
+/// let (gametrees, report) = load_flexible(&path).unwrap();
+/// assert_eq!(gametrees[0].to_string(), "(;C[Café];B[de])");
+/// assert_eq!(report.recoveries.len(), 1);
+/// # std::fs::remove_file(&path).unwrap();
+/// ```
+pub fn load_flexible(
+    path: impl AsRef<std::path::Path>,
+) -> Result<(Vec<GameTree>, ParseReport), SgfParseError> {
+    let data = std::fs::read(path).map_err(|e| SgfParseErrorKind::IoError(e.kind()))?;
+    let decoded = decode_utf16(&data, &lenient_options())?;
+    let (data, input) = match &decoded {
+        Some(text) => (text.as_bytes(), Input::Text),
+        None => (&data[..], Input::Bytes),
+    };
+    if let Ok(gametrees) = parse_spanned(data, input, &strict_options()) {
+        return Ok(clean_report(gametrees));
+    }
+    let text = match input {
+        Input::Text => decode_mixed(data),
+        Input::Bytes => decode_declared_charset(data),
+    };
+    Ok(parse_flexible(&text))
+}
+
+fn strict_options() -> ParseOptions {
+    ParseOptions {
+        convert_mixed_case_identifiers: false,
+        ..ParseOptions::default()
+    }
+}
+
+fn lenient_options() -> ParseOptions {
+    ParseOptions {
+        recover_missing_nodes: true,
+        lossy_decoding: true,
+        ..ParseOptions::default()
+    }
+}
+
+fn clean_report(gametrees: Vec<(GameTree, Range<usize>)>) -> (Vec<GameTree>, ParseReport) {
+    let (gametrees, gametree_spans) = gametrees.into_iter().unzip();
+    let report = ParseReport {
+        recoveries: vec![],
+        gametree_spans,
+    };
+    (gametrees, report)
+}
+
+// Decode data with the charset declared by the first gametree's `CA` property.
+//
+// Used for data which can't be parsed, so anything before the first gametree or after a lexer
+// error is ignored.
+fn decode_declared_charset(data: &[u8]) -> String {
+    let start = data.iter().position(|&b| b == b'(').unwrap_or(data.len());
+    let tokens: Vec<_> = tokenize_with_options(&data[start..], &lenient_options())
+        .map_while(Result::ok)
+        .collect();
+    match find_charset(&tokens, Input::Bytes) {
+        Ok(Charset::Latin1) => data.iter().map(|&b| decode_cp1252(b)).collect(),
+        #[cfg(feature = "encoding")]
+        Ok(Charset::Encoding(encoding)) => {
+            encoding.decode_without_bom_handling(data).0.into_owned()
+        }
+        _ => decode_mixed(data),
+    }
+}
+
+// Decode valid UTF-8 sequences as UTF-8, and any other bytes as Windows-1252.
+fn decode_mixed(mut data: &[u8]) -> String {
+    let mut text = String::with_capacity(data.len());
+    loop {
+        match std::str::from_utf8(data) {
+            Ok(valid) => {
+                text.push_str(valid);
+                return text;
+            }
+            Err(e) => {
+                let (valid, rest) = data.split_at(e.valid_up_to());
+                // The prefix was just checked.
+                text.push_str(std::str::from_utf8(valid).unwrap());
+                text.push(decode_cp1252(rest[0]));
+                data = &rest[1..];
+            }
+        }
+    }
+}
+
+/// Returns an iterator over the [`GameTree`] values parsed on demand from the provided text.
+///
+/// Text is only tokenized and parsed as far as needed to produce each game tree, so callers can
+/// stop after the first few games of a large collection cheaply. Default parsing options are
+/// used. The iterator ends after the first error.
+///
+/// # Examples
+/// ```
+/// use sgf_parse::{parse_lazy, GameType};
+///
+/// let sgf = "(;SZ[9]C[Some comment];B[de];W[fe])(;B[de];W[ff])(;B[de]";
+/// let mut gametrees = parse_lazy(sgf);
+/// assert_eq!(gametrees.next().unwrap().unwrap().gametype(), GameType::Go);
+/// assert!(gametrees.next().unwrap().is_ok());
+/// assert!(gametrees.next().unwrap().is_err());
+/// assert!(gametrees.next().is_none());
+/// ```
+pub fn parse_lazy(text: &str) -> impl Iterator<Item = Result<GameTree, SgfParseError>> + '_ {
+    let options = ParseOptions::default();
+    LazyParser {
+        tokens: tokenize_with_options(text.as_bytes(), &options),
+        options,
+        done: false,
+    }
+}
+
+struct LazyParser<I> {
+    tokens: I,
+    options: ParseOptions,
+    done: bool,
+}
+
+impl<'a, I> LazyParser<I>
+where
+    I: Iterator<Item = Result<(Token<'a>, std::ops::Range<usize>), LexerError>>,
+{
+    // Collect the tokens for the next gametree (if any).
+    fn next_gametree_tokens(&mut self) -> Result<Vec<SpannedToken<'a>>, SgfParseErrorKind> {
+        let mut tokens = vec![];
+        let mut gametree_depth: u64 = 0;
+        for result in &mut self.tokens {
+            let (token, span) = result?;
+            match token {
+                Token::StartGameTree => gametree_depth += 1,
+                Token::EndGameTree => {
+                    if gametree_depth == 0 {
+                        return Err(SgfParseErrorKind::UnexpectedGameTreeEnd);
+                    }
+                    gametree_depth -= 1;
+                }
+                _ => {}
+            }
+            let is_end = token == Token::EndGameTree;
+            tokens.push((token, span));
+            if gametree_depth == 0 && is_end {
+                return Ok(tokens);
+            }
+        }
+        if gametree_depth != 0 {
+            return Err(SgfParseErrorKind::UnexpectedEndOfData);
+        }
+
+        Ok(tokens)
+    }
+}
+
+impl<'a, I> Iterator for LazyParser<I>
+where
+    I: Iterator<Item = Result<(Token<'a>, std::ops::Range<usize>), LexerError>>,
+{
+    type Item = Result<GameTree, SgfParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let result = self.next_gametree_tokens().and_then(|tokens| {
+            if tokens.is_empty() {
+                Ok(None)
+            } else {
+                parse_gametree_tokens(tokens, Input::Text, &self.options, None).map(Some)
+            }
+        });
+        match result {
+            Ok(Some(gametree)) => Some(Ok(gametree)),
+            Ok(None) => {
+                self.done = true;
+                None
+            }
+            Err(e) => {
+                self.done = true;
+                Some(Err(e.into()))
+            }
+        }
+    }
+}
+
+/// A low level event produced by [`parse_events`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SgfEvent {
+    /// Start of a game tree or variation: `(`.
+    GameTreeStart,
+    /// Start of a node: `;`.
+    NodeStart,
+    /// A property of the current node with its identifier and unparsed values.
+    Property(String, Vec<String>),
+    /// End of a game tree or variation: `)`.
+    GameTreeEnd,
+}
+
+/// Returns an iterator over the structural events in the provided text.
+///
+/// This is a low level alternative to [`parse`] for building custom data structures or stream
+/// processing large collections without building [`SgfNode`] trees. Property identifiers and
+/// values are passed through as they appear in the text with only escapes and soft line breaks
+/// removed, and property values aren't parsed or validated. The event order is checked, so every
+/// variation starts with a node and game trees are balanced. The iterator ends after the first
+/// error.
+///
+/// # Examples
+/// ```
+/// use sgf_parse::{parse_events, SgfEvent};
+///
+/// let events: Vec<_> = parse_events("(;SZ[9];B[de](;W[ee])(;W[ff]))")
+///     .collect::<Result<_, _>>()
+///     .unwrap();
+/// let moves = events
+///     .iter()
+///     .filter(|event| matches!(event, SgfEvent::Property(id, _) if id == "B" || id == "W"))
+///     .count();
+/// assert_eq!(moves, 3);
+/// assert_eq!(events[0], SgfEvent::GameTreeStart);
+/// assert_eq!(
+///     events[2],
+///     SgfEvent::Property("SZ".to_string(), vec!["9".to_string()])
+/// );
+///
+/// let result: Result<Vec<_>, _> = parse_events("(;B[de]").collect();
+/// assert!(result.is_err());
+/// ```
+pub fn parse_events(text: &str) -> impl Iterator<Item = Result<SgfEvent, SgfParseError>> + '_ {
+    EventParser {
+        tokens: tokenize(text.as_bytes()),
+        gametree_depth: 0,
+        previous: None,
+        done: false,
+    }
+}
+
+struct EventParser<I> {
+    tokens: I,
+    gametree_depth: usize,
+    previous: Option<SgfEvent>,
+    done: bool,
+}
+
+impl<'a, I> EventParser<I>
+where
+    I: Iterator<Item = Result<(Token<'a>, std::ops::Range<usize>), LexerError>>,
+{
+    fn next_event(&mut self) -> Result<Option<SgfEvent>, SgfParseErrorKind> {
+        let token = match self.tokens.next() {
+            Some(result) => result?.0,
+            None if self.gametree_depth > 0 => return Err(SgfParseErrorKind::UnexpectedEndOfData),
+            None => return Ok(None),
+        };
+        let after_start = self.previous == Some(SgfEvent::GameTreeStart);
+        let event = match token {
+            Token::StartGameTree if after_start => {
+                return Err(SgfParseErrorKind::UnexpectedGameTreeStart)
+            }
+            Token::StartGameTree => {
+                self.gametree_depth += 1;
+                SgfEvent::GameTreeStart
+            }
+            Token::EndGameTree if self.gametree_depth == 0 || after_start => {
+                return Err(SgfParseErrorKind::UnexpectedGameTreeEnd)
+            }
+            Token::EndGameTree => {
+                self.gametree_depth -= 1;
+                SgfEvent::GameTreeEnd
+            }
+            Token::StartNode if self.gametree_depth == 0 => {
+                return Err(SgfParseErrorKind::UnexpectedProperty)
+            }
+            Token::StartNode => SgfEvent::NodeStart,
+            Token::Property(_) if self.gametree_depth == 0 || after_start => {
+                return Err(SgfParseErrorKind::UnexpectedProperty)
+            }
+            Token::Property((identifier, values)) => {
+                let values = values
+                    .iter()
+                    .map(|value| {
+                        String::from_utf8(unescape(value, false).into_owned())
+                            .map_err(|_| SgfParseErrorKind::InvalidTextEncoding)
+                    })
+                    .collect::<Result<_, _>>()?;
+                SgfEvent::Property(identifier, values)
+            }
+        };
+        self.previous = Some(match &event {
+            // Only the kind of the previous event matters, so skip cloning values.
+            SgfEvent::Property(_, _) => SgfEvent::NodeStart,
+            event => event.clone(),
+        });
+
+        Ok(Some(event))
+    }
+}
+
+impl<'a, I> Iterator for EventParser<I>
+where
+    I: Iterator<Item = Result<(Token<'a>, std::ops::Range<usize>), LexerError>>,
+{
+    type Item = Result<SgfEvent, SgfParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let result = self.next_event().map_err(SgfParseError::from).transpose();
+        if !matches!(result, Some(Ok(_))) {
+            self.done = true;
+        }
+        result
+    }
+}
+
+/// Iterator over the [`GameTree`] values in an SGF collection read from a [`BufRead`].
+///
+/// Only the bytes of one game tree are held in memory at a time, so arbitrarily large collection
+/// files can be processed game by game. The iterator ends after the first error.
+///
+/// # Examples
+/// ```
+/// use sgf_parse::{GameType, ParseOptions, SgfReader};
+///
+/// let data = "(;GM[1];B[de]C[Comment (with parens)])\n(;GM[37])".as_bytes();
+/// let mut reader = SgfReader::new(data, ParseOptions::default());
+/// assert_eq!(reader.next().unwrap().unwrap().gametype(), GameType::Go);
+/// assert_eq!(reader.next().unwrap().unwrap().gametype(), GameType::Unknown);
+/// assert!(reader.next().is_none());
+/// ```
+pub struct SgfReader<R> {
+    reader: R,
+    options: ParseOptions,
+    position: StreamPosition,
+    done: bool,
+}
+
+impl<R: BufRead> SgfReader<R> {
+    /// Returns a new `SgfReader` reading from `reader` and parsing with `options`.
+    pub fn new(reader: R, options: ParseOptions) -> Self {
+        Self {
+            reader,
+            options,
+            position: StreamPosition::default(),
+            done: false,
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for SgfReader<R> {
+    type Item = Result<GameTree, SgfParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let result = read_gametree(&mut self.reader, &self.options, &mut self.position)
+            .map_err(SgfParseError::from)
+            .and_then(|data| match data {
+                Some((data, start)) => parse_single_gametree(&data, start, &self.options).map(Some),
+                None => Ok(None),
+            });
+        match result {
+            Ok(Some(gametree)) => Some(Ok(gametree)),
+            Ok(None) => {
+                self.done = true;
+                None
+            }
+            Err(e) => {
+                self.done = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+// Parse the bytes of a single gametree as read by `read_gametree`.
+//
+// Error locations are shifted by `start` so they're relative to the whole stream.
+fn parse_single_gametree(
+    data: &[u8],
+    start: StreamPosition,
+    options: &ParseOptions,
+) -> Result<GameTree, SgfParseError> {
+    let mut gametrees = parse_bytes_with_options(data, options).map_err(|e| e.shifted(start))?;
+    match gametrees.len() {
+        1 => Ok(gametrees.pop().unwrap()),
+        _ => Err(SgfParseErrorKind::UnexpectedEndOfData.into()),
+    }
+}
+
+// Position of the next byte read from a stream.
+#[derive(Clone, Copy, Debug)]
+struct StreamPosition {
+    offset: usize,
+    line: usize,
+    column: usize,
+}
+
+impl Default for StreamPosition {
+    fn default() -> Self {
+        Self {
+            offset: 0,
+            line: 1,
+            column: 1,
+        }
+    }
+}
+
+impl StreamPosition {
+    fn advance(&mut self, bytes: &[u8]) {
+        self.offset += bytes.len();
+        for &byte in bytes {
+            if byte == b'\n' {
+                self.line += 1;
+                self.column = 1;
+            } else if byte & 0xC0 != 0x80 {
+                self.column += 1;
+            }
+        }
+    }
+}
+
+// Read the bytes of the next gametree (if any) from a reader, along with their position.
+//
+// `position` is advanced past everything consumed from the reader.
+//
+// Only tracks parentheses, property values and the identifier limits from the options, leaving
+// any other errors to the parser. Checking the limits here keeps garbage identifiers from being
+// buffered before the parser gets to reject them.
+fn read_gametree(
+    reader: &mut impl BufRead,
+    options: &ParseOptions,
+    position: &mut StreamPosition,
+) -> Result<Option<(Vec<u8>, StreamPosition)>, SgfParseErrorKind> {
+    let max_identifier_length = options.max_identifier_length.unwrap_or(usize::MAX);
+    let mut data = vec![];
+    let mut gametree_depth: u64 = 0;
+    let mut in_value = false;
+    let mut escaped = false;
+    // Length of the current property identifier, and whether whitespace has been seen after it.
+    let mut identifier_length = 0;
+    let mut identifier_ended = false;
+    loop {
+        let available = match reader.fill_buf() {
+            Ok(available) => available,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(SgfParseErrorKind::IoError(e.kind())),
+        };
+        if available.is_empty() {
+            break;
+        }
+        // Skip whitespace between gametrees.
+        if data.is_empty() {
+            let whitespace = available
+                .iter()
+                .take_while(|byte| byte.is_ascii_whitespace())
+                .count();
+            if whitespace > 0 {
+                position.advance(&available[..whitespace]);
+                reader.consume(whitespace);
+                continue;
+            }
+        }
+        let mut end = None;
+        for (i, &byte) in available.iter().enumerate() {
+            match byte {
+                _ if escaped => escaped = false,
+                b'\\' if in_value => escaped = true,
+                b']' if in_value => in_value = false,
+                _ if in_value => {}
+                b'[' | b'(' | b')' | b';' => {
+                    identifier_length = 0;
+                    identifier_ended = false;
+                    match byte {
+                        b'[' => in_value = true,
+                        b'(' => gametree_depth += 1,
+                        b')' if gametree_depth == 0 => {
+                            return Err(SgfParseErrorKind::UnexpectedGameTreeEnd);
+                        }
+                        b')' => {
+                            gametree_depth -= 1;
+                            if gametree_depth == 0 {
+                                end = Some(i + 1);
+                                break;
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+                // Whitespace before an identifier is skipped, as it is by the lexer.
+                _ if byte.is_ascii_whitespace() && identifier_length == 0 => {}
+                _ if byte.is_ascii_whitespace() && options.letters_only_identifiers => {
+                    identifier_ended = true;
+                }
+                _ if options.letters_only_identifiers
+                    && (identifier_ended || !byte.is_ascii_alphabetic()) =>
+                {
+                    return Err(LexerError::UnexpectedPropertyIdentifier.into());
+                }
+                _ if identifier_length >= max_identifier_length => {
+                    return Err(LexerError::PropertyIdentifierTooLong.into());
+                }
+                _ => identifier_length += 1,
+            }
+        }
+        let length = end.unwrap_or(available.len());
+        data.extend_from_slice(&available[..length]);
+        reader.consume(length);
+        if end.is_some() {
+            let start = *position;
+            position.advance(&data);
+            return Ok(Some((data, start)));
+        }
+    }
+    if data.is_empty() {
+        Ok(None)
+    } else {
+        Err(SgfParseErrorKind::UnexpectedEndOfData)
+    }
+}
+
+/// Options for parsing SGF files.
+pub struct ParseOptions {
+    /// Whether to allow conversion of FF\[3\] mixed case identifiers to FF\[4\].
+    ///
+    /// All lower case letters are dropped.
+    /// This should allow parsing any older files which are valid, but not valid FF\[4\].
+    pub convert_mixed_case_identifiers: bool,
+    /// Maximum number of points compressed point lists in a single game tree may expand to.
+    ///
+    /// A value like `AB[aA:ZZ]` expands to thousands of points, so when parsing untrusted input
+    /// it's worth setting a limit. Parsing fails with
+    /// [`SgfParseErrorKind::CompressedPointLimitExceeded`] if the limit is exceeded. Defaults to
+    /// `None` (no limit).
+    pub max_compressed_points: Option<usize>,
+    /// Maximum number of nodes to keep on the main line of each game tree.
+    ///
+    /// The main line follows the first child of each node. Main line nodes past this limit, along
+    /// with everything after them, are skipped without parsing their properties, which is useful
+    /// for quickly building previews of long games. Variations branching off earlier are kept.
+    /// Parsing fails with [`SgfParseErrorKind::InvalidMainLineLimit`] for `Some(0)`, since the
+    /// root node is always kept. Defaults to `None` (no limit).
+    pub max_main_line_nodes: Option<usize>,
+    /// Flag checked periodically while parsing to allow cancelling from another thread.
+    ///
+    /// Once the flag is set, parsing fails with [`SgfParseErrorKind::Cancelled`]. Defaults to
+    /// `None`.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::sync::atomic::{AtomicBool, Ordering};
+    /// use std::sync::Arc;
+    /// use sgf_parse::{parse_with_options, ParseOptions, SgfParseErrorKind};
+    ///
+    /// let cancel_flag = Arc::new(AtomicBool::new(false));
+    /// let options = ParseOptions {
+    ///     cancel_flag: Some(cancel_flag.clone()),
+    ///     ..ParseOptions::default()
+    /// };
+    /// cancel_flag.store(true, Ordering::Relaxed);
+    /// let error = parse_with_options("(;B[de])", &options).unwrap_err();
+    /// assert_eq!(error.kind, SgfParseErrorKind::Cancelled);
+    /// ```
+    pub cancel_flag: Option<Arc<AtomicBool>>,
+    /// Whether to replace undecodable bytes in property values with `U+FFFD` instead of failing.
+    ///
+    /// Only relevant when parsing bytes with [`parse_bytes_with_options`]. Defaults to `false`.
+    pub lossy_decoding: bool,
+    /// Root properties to replace (or add) before detecting the game type and parsing properties.
+    ///
+    /// Maps property identifiers to the values they should have in every game tree's root node.
+    /// Useful for fixing files known to be mislabeled. Defaults to an empty map.
+    ///
+    /// # Examples
+    /// ```
+    /// use sgf_parse::{parse_with_options, ParseOptions, GameType};
+    ///
+    /// let mut options = ParseOptions::default();
+    /// options.root_overrides.insert("GM".to_string(), vec!["1".to_string()]);
+    /// options.root_overrides.insert("SZ".to_string(), vec!["19".to_string()]);
+    /// let gametrees = parse_with_options("(;GM[37]SZ[9];B[de])", &options).unwrap();
+    /// assert_eq!(gametrees[0].gametype(), GameType::Go);
+    /// assert_eq!(gametrees[0].to_string(), "(;GM[1]SZ[19:19];B[de])");
+    /// ```
+    pub root_overrides: BTreeMap<String, Vec<String>>,
+    /// Filter for which properties to keep while parsing.
+    ///
+    /// Filtered properties are skipped before their values are decoded or parsed, saving memory
+    /// for workloads that only need some properties. Defaults to [`PropertyFilter::All`].
+    ///
+    /// # Examples
+    /// ```
+    /// use sgf_parse::{parse_with_options, ParseOptions, PropertyFilter};
+    ///
+    /// let options = ParseOptions {
+    ///     property_filter: PropertyFilter::Drop(vec!["C".to_string()].into_iter().collect()),
+    ///     ..ParseOptions::default()
+    /// };
+    /// let gametrees = parse_with_options("(;C[Comment]B[de];W[ee]C[Another])", &options).unwrap();
+    /// assert_eq!(gametrees[0].to_string(), "(;B[de];W[ee])");
+    /// ```
+    pub property_filter: PropertyFilter,
+    /// Whether to recover properties appearing at the start of a game tree without a node.
+    ///
+    /// When set, input like `(B[cc])` is parsed as if it were `(;B[cc])`. Otherwise such input
+    /// fails with [`SgfParseErrorKind::UnexpectedProperty`]. Defaults to `false`.
+    ///
+    /// # Examples
+    /// ```
+    /// use sgf_parse::{parse_with_options, ParseOptions};
+    ///
+    /// let options = ParseOptions {
+    ///     recover_missing_nodes: true,
+    ///     ..ParseOptions::default()
+    /// };
+    /// let gametrees = parse_with_options("(SZ[9](B[cc])(;B[dd]))", &options).unwrap();
+    /// assert_eq!(gametrees[0].to_string(), "(;SZ[9:9](;B[cc])(;B[dd]))");
+    /// ```
+    pub recover_missing_nodes: bool,
+    /// Whether to move `GM` and `FF` properties found in non-root nodes into the root node.
+    ///
+    /// Misplaced properties are hoisted before the game type is detected, so files with a late
+    /// `GM` property are classified correctly. The first misplaced value of each identifier is
+    /// used if the root doesn't already have one, and any others are dropped. Nodes left without
+    /// properties are removed, with their children taking their place. Use
+    /// [`find_misplaced_root_properties`] to report these properties instead. Defaults to
+    /// `false`.
+    ///
+    /// # Examples
+    /// ```
+    /// use sgf_parse::{parse_with_options, GameType, ParseOptions};
+    ///
+    /// let options = ParseOptions {
+    ///     hoist_misplaced_root_properties: true,
+    ///     ..ParseOptions::default()
+    /// };
+    /// let gametrees = parse_with_options("(;C[Intro];GM[2]FF[4]B[aa])", &options).unwrap();
+    /// assert_eq!(gametrees[0].gametype(), GameType::Unknown);
+    /// assert_eq!(gametrees[0].to_string(), "(;C[Intro]GM[2]FF[4];B[aa])");
+    /// ```
+    pub hoist_misplaced_root_properties: bool,
+    /// Parsers for games this crate doesn't support, keyed by `GM` value.
+    ///
+    /// Game trees with a matching root `GM` property are first parsed as an unknown game, then
+    /// converted by the registered parser into a [`GameTree::Custom`]. Defaults to an empty map.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::any::Any;
+    /// use std::sync::Arc;
+    /// use sgf_parse::{parse_with_options, unknown_game, DynGameNode, GameType, ParseOptions, SgfNode};
+    ///
+    /// #[derive(Clone, Debug)]
+    /// struct Backgammon(SgfNode<unknown_game::Prop>);
+    ///
+    /// impl DynGameNode for Backgammon {
+    ///     fn serialize(&self) -> String {
+    ///         self.0.serialize()
+    ///     }
+    ///
+    ///     fn as_any(&self) -> &dyn Any {
+    ///         self
+    ///     }
+    ///
+    ///     fn clone_box(&self) -> Box<dyn DynGameNode> {
+    ///         Box::new(self.clone())
+    ///     }
+    ///
+    ///     fn node_count(&self) -> usize {
+    ///         self.0.node_count()
+    ///     }
+    ///
+    ///     fn max_depth(&self) -> usize {
+    ///         self.0.max_depth()
+    ///     }
+    ///
+    ///     fn variation_count(&self) -> usize {
+    ///         self.0.variation_count()
+    ///     }
+    /// }
+    ///
+    /// let mut options = ParseOptions::default();
+    /// options.custom_games.insert(
+    ///     6,
+    ///     Arc::new(|node| Ok(Box::new(Backgammon(node)) as Box<dyn DynGameNode>)),
+    /// );
+    /// let gametrees = parse_with_options("(;GM[6];B[31ab])", &options).unwrap();
+    /// assert_eq!(gametrees[0].gametype(), GameType::Custom);
+    /// assert_eq!(gametrees[0].to_string(), "(;GM[6];B[31ab])");
+    /// ```
+    pub custom_games: BTreeMap<i64, CustomGameParser>,
+    /// Whether to fail on properties not defined by the SGF specification for the game.
+    ///
+    /// Parsing fails with [`SgfParseErrorKind::UnknownProperty`] instead of returning an `Unknown`
+    /// property. The error holds the offending property's identifier and span. Defaults to
+    /// `false`.
+    ///
+    /// # Examples
+    /// ```
+    /// use sgf_parse::{parse_with_options, ParseOptions, SgfParseErrorKind};
+    ///
+    /// let options = ParseOptions {
+    ///     reject_unknown_properties: true,
+    ///     ..ParseOptions::default()
+    /// };
+    /// let sgf = "(;GM[1]SZ[9];B[cc]XX[1])";
+    /// match parse_with_options(sgf, &options).map_err(|e| e.kind) {
+    ///     Err(SgfParseErrorKind::UnknownProperty(property)) => {
+    ///         assert_eq!(property.identifier, "XX");
+    ///         assert_eq!(&sgf[property.span], "XX[1]");
+    ///     }
+    ///     result => panic!("Unexpected result {:?}", result),
+    /// }
+    /// ```
+    pub reject_unknown_properties: bool,
+    /// Whether to fail on properties with values which are invalid for their identifier.
+    ///
+    /// Parsing fails with [`SgfParseErrorKind::InvalidProperty`] instead of returning an `Invalid`
+    /// property. The error holds the offending property's identifier and span. Defaults to
+    /// `false`.
+    ///
+    /// # Examples
+    /// ```
+    /// use sgf_parse::{parse_with_options, ParseOptions, SgfParseErrorKind};
+    ///
+    /// let options = ParseOptions {
+    ///     reject_invalid_properties: true,
+    ///     ..ParseOptions::default()
+    /// };
+    /// let sgf = "(;GM[1]SZ[9]KM[lots];B[cc])";
+    /// match parse_with_options(sgf, &options).map_err(|e| e.kind) {
+    ///     Err(SgfParseErrorKind::InvalidProperty(property)) => {
+    ///         assert_eq!(property.identifier, "KM");
+    ///         assert_eq!(&sgf[property.span], "KM[lots]");
+    ///     }
+    ///     result => panic!("Unexpected result {:?}", result),
+    /// }
+    /// ```
+    pub reject_invalid_properties: bool,
+    /// Whether to keep property values of unknown games exactly as written.
+    ///
+    /// Serializing a parsed property normally escapes its values in a canonical way, so unknown
+    /// games may not round trip exactly. For instance, escapes before `:` in unknown properties
+    /// are dropped, and soft line breaks are removed. With this option, the node keeps the
+    /// original text of any property whose serialized form would differ, and serializes the
+    /// property as it was written as long as it's unchanged. Properties are parsed as usual. Go
+    /// games and custom games aren't affected. Defaults to `false`.
+    ///
+    /// # Examples
+    /// ```
+    /// use sgf_parse::{parse_with_options, ParseOptions};
+    ///
+    /// let sgf = "(;GM[5]XX[a\\:b]C[Soft\\\nbreak];B[e4])";
+    /// let gametrees = parse_with_options(sgf, &ParseOptions::default()).unwrap();
+    /// assert_eq!(gametrees[0].to_string(), "(;GM[5]XX[a:b]C[Softbreak];B[e4])");
+    ///
+    /// let options = ParseOptions {
+    ///     keep_raw_unknown_game_values: true,
+    ///     ..ParseOptions::default()
+    /// };
+    /// let gametrees = parse_with_options(sgf, &options).unwrap();
+    /// assert_eq!(gametrees[0].to_string(), sgf);
+    /// ```
+    pub keep_raw_unknown_game_values: bool,
+    /// The maximum length of property identifiers, or `None` for no limit.
+    ///
+    /// Corrupted data before a `[` is otherwise read as one long identifier. Longer identifiers
+    /// fail with [`LexerError::PropertyIdentifierTooLong`]. [`parse_with_report`] skips the
+    /// property instead and reports it as [`RecoveryAction::SkippedContent`]. The length is
+    /// checked before any FF\[3\] identifier conversion. [`SgfReader`] and [`parse_from_reader`]
+    /// check it while reading, so long identifiers aren't buffered. Defaults to `None`.
+    ///
+    /// # Examples
+    /// ```
+    /// use sgf_parse::{parse_with_options, LexerError, ParseOptions, SgfParseErrorKind};
+    ///
+    /// let options = ParseOptions {
+    ///     max_identifier_length: Some(16),
+    ///     ..ParseOptions::default()
+    /// };
+    /// let sgf = "(;B[de]CorruptedBinaryDataWithoutEnd[])";
+    /// assert_eq!(
+    ///     parse_with_options(sgf, &options).unwrap_err().kind,
+    ///     SgfParseErrorKind::LexerError(LexerError::PropertyIdentifierTooLong)
+    /// );
+    /// ```
+    pub max_identifier_length: Option<usize>,
+    /// Whether to only allow ASCII letters in property identifiers.
+    ///
+    /// By default any ASCII characters are read as part of an identifier, and anything other
+    /// than upper case letters is dropped by FF\[3\] identifier conversion. With this option,
+    /// identifiers with other characters fail with [`LexerError::UnexpectedPropertyIdentifier`].
+    /// [`parse_with_report`] skips the property instead and reports it as
+    /// [`RecoveryAction::SkippedContent`]. Whitespace is still allowed between an identifier and
+    /// its values. Defaults to `false`.
+    ///
+    /// # Examples
+    /// ```
+    /// use sgf_parse::{parse_with_report, ParseOptions, RecoveryAction};
+    ///
+    /// let options = ParseOptions {
+    ///     letters_only_identifiers: true,
+    ///     ..ParseOptions::default()
+    /// };
+    /// let sgf = "(;B[de]\u{1}#%!x[junk]C[ok])";
+    /// let (gametrees, report) = parse_with_report(sgf, &options).unwrap();
+    /// assert_eq!(gametrees[0].to_string(), "(;B[de]C[ok])");
+    /// assert_eq!(report.recoveries[0].action, RecoveryAction::SkippedContent);
+    /// assert_eq!(&sgf[report.recoveries[0].span.clone()], "\u{1}#%!x[junk]");
+    /// ```
+    pub letters_only_identifiers: bool,
+}
+
+/// Filter for which properties to keep while parsing, used in [`ParseOptions::property_filter`].
 ///
-/// let sgf = "(;SZ[9]C[Some comment];B[de];W[fe])(;B[de];W[ff])";
-/// let gametrees = parse_with_options(sgf, &ParseOptions::default()).unwrap();
-/// assert!(gametrees.len() == 2);
-/// assert!(gametrees.iter().all(|gametree| gametree.gametype() == GameType::Go));
-/// ```
-pub fn parse_with_options(
-    text: &str,
-    options: &ParseOptions,
-) -> Result<Vec<GameTree>, SgfParseError> {
-    let tokens = tokenize(text)
-        .map(|result| match result {
-            Err(e) => Err(SgfParseError::LexerError(e)),
-            Ok((token, _span)) => Ok(token),
-        })
-        .collect::<Result<Vec<_>, _>>()?;
-    split_by_gametree(&tokens)?
-        .into_iter()
-        .map(|tokens| match find_gametype(tokens)? {
-            GameType::Go => parse_gametree::<go::Prop>(tokens, options),
-            GameType::Unknown => parse_gametree::<unknown_game::Prop>(tokens, options),
-        })
-        .collect::<Result<_, _>>()
+/// Identifiers are matched after any FF\[3\] identifier conversion.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum PropertyFilter {
+    /// Keep all properties.
+    #[default]
+    All,
+    /// Keep only properties with the given identifiers.
+    Keep(HashSet<String>),
+    /// Drop properties with the given identifiers.
+    Drop(HashSet<String>),
 }
 
-/// Options for parsing SGF files.
-pub struct ParseOptions {
-    /// Whether to allow conversion of FF\[3\] mixed case identifiers to FF\[4\].
-    ///
-    /// All lower case letters are dropped.
-    /// This should allow parsing any older files which are valid, but not valid FF\[4\].
-    pub convert_mixed_case_identifiers: bool,
+impl PropertyFilter {
+    /// Returns whether properties with the given identifier should be kept.
+    pub fn keeps(&self, identifier: &str) -> bool {
+        match self {
+            Self::All => true,
+            Self::Keep(identifiers) => identifiers.contains(identifier),
+            Self::Drop(identifiers) => !identifiers.contains(identifier),
+        }
+    }
 }
 
 impl Default for ParseOptions {
     fn default() -> Self {
         ParseOptions {
             convert_mixed_case_identifiers: true,
+            max_compressed_points: None,
+            max_main_line_nodes: None,
+            cancel_flag: None,
+            lossy_decoding: false,
+            root_overrides: BTreeMap::new(),
+            property_filter: PropertyFilter::All,
+            recover_missing_nodes: false,
+            hoist_misplaced_root_properties: false,
+            custom_games: BTreeMap::new(),
+            reject_unknown_properties: false,
+            reject_invalid_properties: false,
+            keep_raw_unknown_game_values: false,
+            max_identifier_length: None,
+            letters_only_identifiers: false,
         }
     }
 }
 
 /// Error type for failures parsing sgf from text.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum SgfParseError {
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SgfParseError {
+    /// What went wrong.
+    pub kind: SgfParseErrorKind,
+    /// Where the error was found in the input, if known.
+    pub location: Option<ErrorLocation>,
+}
+
+/// Where an [`SgfParseError`] was found in the input.
+///
+/// For UTF-16 input the location refers to the decoded text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ErrorLocation {
+    /// Byte range of the input where the error was found.
+    pub span: Range<usize>,
+    /// Line number (starting at 1) of the start of the span.
+    pub line: usize,
+    /// Column number in characters (starting at 1) of the start of the span.
+    pub column: usize,
+}
+
+impl ErrorLocation {
+    fn new(data: &[u8], span: Range<usize>) -> Self {
+        let before = &data[..span.start.min(data.len())];
+        let line_start = before
+            .iter()
+            .rposition(|&b| b == b'\n')
+            .map_or(0, |i| i + 1);
+        // Count characters by skipping UTF-8 continuation bytes.
+        let column = before[line_start..]
+            .iter()
+            .filter(|&&b| b & 0xC0 != 0x80)
+            .count();
+        Self {
+            span,
+            line: before.iter().filter(|&&b| b == b'\n').count() + 1,
+            column: column + 1,
+        }
+    }
+}
+
+impl ErrorLocation {
+    // Returns the location relative to a stream in which the data started at `start`.
+    fn shifted(self, start: StreamPosition) -> Self {
+        Self {
+            span: self.span.start + start.offset..self.span.end + start.offset,
+            line: self.line + start.line - 1,
+            column: match self.line {
+                1 => self.column + start.column - 1,
+                _ => self.column,
+            },
+        }
+    }
+}
+
+impl SgfParseError {
+    // Returns the error with its location in the data set to the span.
+    fn located(self, data: &[u8], span: Range<usize>) -> Self {
+        Self {
+            kind: self.kind,
+            location: Some(ErrorLocation::new(data, span)),
+        }
+    }
+
+    // Returns the error with its spans relative to a stream in which the data started at `start`.
+    fn shifted(self, start: StreamPosition) -> Self {
+        let kind = match self.kind {
+            SgfParseErrorKind::UnknownProperty(property) => {
+                SgfParseErrorKind::UnknownProperty(property.shifted(start.offset))
+            }
+            SgfParseErrorKind::InvalidProperty(property) => {
+                SgfParseErrorKind::InvalidProperty(property.shifted(start.offset))
+            }
+            kind => kind,
+        };
+        Self {
+            kind,
+            location: self.location.map(|location| location.shifted(start)),
+        }
+    }
+}
+
+/// The kind of an [`SgfParseError`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SgfParseErrorKind {
     LexerError(LexerError),
     UnexpectedGameTreeStart,
     UnexpectedGameTreeEnd,
@@ -86,182 +1621,896 @@ pub enum SgfParseError {
     UnexpectedEndOfData,
     UnexpectedGameType,
     InvalidFF4Property,
+    CompressedPointLimitExceeded,
+    Cancelled,
+    InvalidTextEncoding,
+    IoError(io::ErrorKind),
+    UnknownProperty(RejectedProperty),
+    InvalidProperty(RejectedProperty),
+    InvalidMainLineLimit,
 }
 
-impl From<LexerError> for SgfParseError {
+/// A property rejected by [`ParseOptions::reject_unknown_properties`] or
+/// [`ParseOptions::reject_invalid_properties`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RejectedProperty {
+    /// The property's identifier.
+    pub identifier: String,
+    /// Byte range of the property in the input.
+    pub span: Range<usize>,
+}
+
+impl RejectedProperty {
+    fn shifted(self, offset: usize) -> Self {
+        Self {
+            identifier: self.identifier,
+            span: self.span.start + offset..self.span.end + offset,
+        }
+    }
+}
+
+impl From<SgfParseErrorKind> for SgfParseError {
+    fn from(kind: SgfParseErrorKind) -> Self {
+        Self {
+            kind,
+            location: None,
+        }
+    }
+}
+
+impl From<LexerError> for SgfParseErrorKind {
     fn from(error: LexerError) -> Self {
         Self::LexerError(error)
     }
 }
 
+impl From<LexerError> for SgfParseError {
+    fn from(error: LexerError) -> Self {
+        SgfParseErrorKind::LexerError(error).into()
+    }
+}
+
 impl std::fmt::Display for SgfParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match &self.location {
+            Some(location) => write!(
+                f,
+                "{} at line {}, column {}",
+                self.kind, location.line, location.column
+            ),
+            None => write!(f, "{}", self.kind),
+        }
+    }
+}
+
+impl std::fmt::Display for SgfParseErrorKind {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match self {
-            SgfParseError::LexerError(e) => write!(f, "Error tokenizing: {}", e),
-            SgfParseError::UnexpectedGameTreeStart => write!(f, "Unexpected start of game tree"),
-            SgfParseError::UnexpectedGameTreeEnd => write!(f, "Unexpected end of game tree"),
-            SgfParseError::UnexpectedProperty => write!(f, "Unexpected property"),
-            SgfParseError::UnexpectedEndOfData => write!(f, "Unexpected end of data"),
-            SgfParseError::UnexpectedGameType => write!(f, "Unexpected game type"),
-            SgfParseError::InvalidFF4Property => {
+            SgfParseErrorKind::LexerError(e) => write!(f, "Error tokenizing: {}", e),
+            SgfParseErrorKind::UnexpectedGameTreeStart => {
+                write!(f, "Unexpected start of game tree")
+            }
+            SgfParseErrorKind::UnexpectedGameTreeEnd => write!(f, "Unexpected end of game tree"),
+            SgfParseErrorKind::UnexpectedProperty => write!(f, "Unexpected property"),
+            SgfParseErrorKind::UnexpectedEndOfData => write!(f, "Unexpected end of data"),
+            SgfParseErrorKind::UnexpectedGameType => write!(f, "Unexpected game type"),
+            SgfParseErrorKind::InvalidFF4Property => {
                 write!(
                     f,
                     "Invalid FF[4] property without `convert_mixed_case_identifiers`"
                 )
             }
+            SgfParseErrorKind::CompressedPointLimitExceeded => {
+                write!(f, "Compressed point lists exceed the configured limit")
+            }
+            SgfParseErrorKind::Cancelled => write!(f, "Parsing cancelled"),
+            SgfParseErrorKind::InvalidTextEncoding => write!(f, "Invalid text encoding"),
+            SgfParseErrorKind::IoError(kind) => write!(f, "Error reading input: {}", kind),
+            SgfParseErrorKind::UnknownProperty(property) => write!(
+                f,
+                "Property {} not in the SGF specification for the game",
+                property.identifier
+            ),
+            SgfParseErrorKind::InvalidProperty(property) => {
+                write!(f, "Invalid {} property value", property.identifier)
+            }
+            SgfParseErrorKind::InvalidMainLineLimit => {
+                write!(f, "Main line node limit must allow the root node")
+            }
+        }
+    }
+}
+
+impl std::error::Error for SgfParseError {}
+
+// Number of tokens or nodes to process between checks of `ParseOptions::cancel_flag`.
+const CANCELLATION_CHECK_INTERVAL: usize = 1024;
+
+fn check_cancelled(options: &ParseOptions) -> Result<(), SgfParseErrorKind> {
+    match &options.cancel_flag {
+        Some(flag) if flag.load(Ordering::Relaxed) => Err(SgfParseErrorKind::Cancelled),
+        _ => Ok(()),
+    }
+}
+
+// Returns a lexer for the data which applies the identifier limits from the options.
+fn tokenize_with_options<'a>(data: &'a [u8], options: &ParseOptions) -> Lexer<'a> {
+    tokenize(data).with_identifier_limits(
+        options.max_identifier_length,
+        options.letters_only_identifiers,
+    )
+}
+
+// A token with its byte range in the input.
+type SpannedToken<'a> = (Token<'a>, Range<usize>);
+
+// Split the tokens up into individual gametrees.
+//
+// This will let us easily scan each gametree for GM properties.
+// Only considers StartGameTree/EndGameTree tokens.
+// Returns the range of token indices for each gametree.
+fn split_by_gametree(
+    tokens: &[SpannedToken],
+) -> Result<Vec<Range<usize>>, (SgfParseErrorKind, usize)> {
+    let mut gametrees = vec![];
+    let mut gametree_depth: u64 = 0;
+    let mut slice_start = 0;
+    for (i, (token, _)) in tokens.iter().enumerate() {
+        match token {
+            Token::StartGameTree => gametree_depth += 1,
+            Token::EndGameTree => {
+                if gametree_depth == 0 {
+                    return Err((SgfParseErrorKind::UnexpectedGameTreeEnd, i));
+                }
+                gametree_depth -= 1;
+                if gametree_depth == 0 {
+                    gametrees.push(slice_start..i + 1);
+                    slice_start = i + 1;
+                }
+            }
+            _ => {}
+        }
+    }
+    if gametree_depth != 0 {
+        return Err((SgfParseErrorKind::UnexpectedEndOfData, tokens.len()));
+    }
+
+    Ok(gametrees)
+}
+
+// Split tokens into the tokens of each gametree as found by `split_by_gametree`.
+fn split_tokens<'a>(
+    tokens: Vec<SpannedToken<'a>>,
+    ranges: &[Range<usize>],
+) -> Vec<Vec<SpannedToken<'a>>> {
+    // Most collections have a single gametree, which doesn't need copying.
+    if let [range] = ranges {
+        if range.len() == tokens.len() {
+            return vec![tokens];
+        }
+    }
+    let mut tokens = tokens.into_iter();
+    ranges
+        .iter()
+        .map(|range| tokens.by_ref().take(range.len()).collect())
+        .collect()
+}
+
+// Returns the span of a gametree from its tokens.
+fn gametree_span(tokens: &[SpannedToken]) -> Range<usize> {
+    match (tokens.first(), tokens.last()) {
+        (Some((_, first)), Some((_, last))) => first.start..last.end,
+        _ => 0..0,
+    }
+}
+
+// Parse a single gametree from its tokens.
+//
+// Tokens are consumed so their identifiers and values can be moved into the parsed properties.
+//
+// Recovery actions taken are added to `recoveries` if provided.
+fn parse_gametree_tokens(
+    mut tokens: Vec<SpannedToken>,
+    input: Input,
+    options: &ParseOptions,
+    mut recoveries: Option<&mut Vec<Recovery>>,
+) -> Result<GameTree, SgfParseErrorKind> {
+    if options.recover_missing_nodes {
+        tokens = insert_missing_nodes(tokens, recoveries.as_deref_mut());
+    }
+    if options.hoist_misplaced_root_properties {
+        tokens = hoist_misplaced_root_properties(tokens);
+    }
+    let tokens = apply_root_overrides(tokens, &options.root_overrides);
+    let charset = find_charset(&tokens, input)?;
+    if is_go_gametree(&tokens)? {
+        if let Some(max_points) = options.max_compressed_points {
+            check_compressed_points(&tokens, max_points)?;
+        }
+        return parse_gametree::<go::Prop>(tokens, charset, options, false, recoveries);
+    }
+    let custom_game_parser = find_custom_game_parser(&tokens, options)?;
+    // Custom game parsers expect properties as they're normally parsed.
+    let keep_raw_values = options.keep_raw_unknown_game_values && custom_game_parser.is_none();
+    let gametree = parse_gametree::<unknown_game::Prop>(
+        tokens,
+        charset,
+        options,
+        keep_raw_values,
+        recoveries,
+    )?;
+    match (custom_game_parser, gametree) {
+        (Some(parser), GameTree::Unknown(node)) => {
+            parser(node).map(GameTree::Custom).map_err(|e| e.kind)
+        }
+        (_, gametree) => Ok(gametree),
+    }
+}
+
+// Parse a single gametree of a known type.
+fn parse_gametree<Prop: SgfProp>(
+    tokens: Vec<SpannedToken>,
+    charset: Charset,
+    options: &ParseOptions,
+    keep_raw_values: bool,
+    mut recoveries: Option<&mut Vec<Recovery>>,
+) -> Result<GameTree, SgfParseErrorKind>
+where
+    SgfNode<Prop>: std::convert::Into<GameTree>,
+{
+    // Complete top level nodes. A valid game tree has exactly one.
+    let mut collection: Vec<SgfNode<Prop>> = vec![];
+    // Incomplete nodes from the top level down to the node whose children we're building.
+    let mut stack: Vec<SgfNode<Prop>> = vec![];
+    // Length of `stack` at the start of each unfinished game tree.
+    let mut gametree_starts: Vec<usize> = vec![];
+    let max_main_line_nodes = match options.max_main_line_nodes {
+        Some(0) => return Err(SgfParseErrorKind::InvalidMainLineLimit),
+        limit => limit.unwrap_or(usize::MAX),
+    };
+    // Number of nodes at the bottom of `stack` on the main line, or `None` once it's been cut.
+    let mut main_line_len = Some(0);
+    // Number of open game trees when the main line was cut. Their remaining nodes are skipped.
+    let mut skipped_gametrees: Option<usize> = None;
+
+    let mut tokens = tokens.into_iter().enumerate().peekable();
+    while let Some((i, (token, _))) = tokens.next() {
+        if i % CANCELLATION_CHECK_INTERVAL == 0 {
+            check_cancelled(options)?;
+        }
+        match token {
+            Token::StartGameTree => {
+                // SGF game trees must have a root node.
+                // Lists in skipped game trees are always empty.
+                if let Some(&start) = gametree_starts.last() {
+                    let node_list_is_empty = stack.len() == start
+                        && match start.checked_sub(1) {
+                            Some(parent) => stack[parent].children.is_empty(),
+                            None => collection.is_empty(),
+                        };
+                    if node_list_is_empty && skipped_gametrees.is_none() {
+                        return Err(SgfParseErrorKind::UnexpectedGameTreeStart);
+                    }
+                }
+                gametree_starts.push(stack.len());
+            }
+            Token::EndGameTree => match gametree_starts.pop() {
+                Some(start) => {
+                    close_nodes(&mut stack, &mut collection, start);
+                    main_line_len = main_line_len.map(|len| len.min(stack.len()));
+                    if skipped_gametrees > Some(gametree_starts.len()) {
+                        skipped_gametrees = None;
+                    }
+                }
+                None => return Err(SgfParseErrorKind::UnexpectedGameTreeEnd),
+            },
+            Token::StartNode
+                if skipped_gametrees.is_some()
+                    || (stack.len() >= max_main_line_nodes
+                        && main_line_len == Some(stack.len())
+                        && is_first_child(&stack, &collection)) =>
+            {
+                if skipped_gametrees.is_none() {
+                    main_line_len = None;
+                    skipped_gametrees = Some(gametree_starts.len());
+                }
+                while let Some((_, (Token::Property(_), _))) = tokens.peek() {
+                    tokens.next();
+                }
+            }
+            Token::StartNode => {
+                let mut new_node = SgfNode::default();
+                let mut prop_tokens = vec![];
+                while let Some((_, (Token::Property(_), _))) = tokens.peek() {
+                    prop_tokens.push(tokens.next().unwrap().1);
+                }
+                for (token, span) in prop_tokens {
+                    match token {
+                        Token::Property((identifier, values)) => {
+                            let identifier = {
+                                if identifier.chars().all(|c| c.is_ascii_uppercase()) {
+                                    identifier
+                                } else if options.convert_mixed_case_identifiers {
+                                    let converted: String = identifier
+                                        .chars()
+                                        .filter(|c| c.is_ascii_uppercase())
+                                        .collect();
+                                    // Without upper case letters there's no identifier left.
+                                    let action = if converted.is_empty() {
+                                        RecoveryAction::DroppedProperty(identifier)
+                                    } else {
+                                        RecoveryAction::ConvertedIdentifier(identifier)
+                                    };
+                                    if let Some(recoveries) = recoveries.as_deref_mut() {
+                                        recoveries.push(Recovery {
+                                            action,
+                                            span: span.clone(),
+                                        });
+                                    }
+                                    if converted.is_empty() {
+                                        continue;
+                                    }
+                                    converted
+                                } else {
+                                    return Err(SgfParseErrorKind::InvalidFF4Property);
+                                }
+                            };
+                            if !options.property_filter.keeps(&identifier) {
+                                if let Some(recoveries) = recoveries.as_deref_mut() {
+                                    recoveries.push(Recovery {
+                                        action: RecoveryAction::FilteredProperty(identifier),
+                                        span,
+                                    });
+                                }
+                                continue;
+                            }
+                            let raw = if keep_raw_values {
+                                let mut raw = identifier.clone();
+                                for value in &values {
+                                    raw.push('[');
+                                    raw += &decode_value(Cow::Borrowed(value), charset, options)?;
+                                    raw.push(']');
+                                }
+                                Some(raw)
+                            } else {
+                                None
+                            };
+                            let decoded = decode_values(&identifier, &values, charset, options)?;
+                            let prop = Prop::from_values(identifier, decoded);
+                            check_rejected_property(&prop, span, options)?;
+                            if let Some(raw) = raw {
+                                let serialized = prop.to_string();
+                                if serialized != raw {
+                                    new_node.keep_raw_property(serialized, raw);
+                                }
+                            }
+                            new_node.properties.push(prop)
+                        }
+                        _ => unreachable!(),
+                    }
+                }
+                if main_line_len == Some(stack.len()) && is_first_child(&stack, &collection) {
+                    main_line_len = Some(stack.len() + 1);
+                }
+                stack.push(new_node);
+            }
+            Token::Property(_) => return Err(SgfParseErrorKind::UnexpectedProperty),
+        }
+    }
+
+    // Nodes before the first game tree start are never closed by a game tree end.
+    close_nodes(&mut stack, &mut collection, 0);
+    if !gametree_starts.is_empty() || collection.len() != 1 {
+        return Err(SgfParseErrorKind::UnexpectedEndOfData);
+    }
+    let mut root_node = collection.into_iter().next().unwrap();
+    root_node.set_root(true);
+    Ok(root_node.into())
+}
+
+// Fail on properties rejected by `ParseOptions::reject_unknown_properties` or
+// `ParseOptions::reject_invalid_properties`.
+fn check_rejected_property<Prop: SgfProp>(
+    prop: &Prop,
+    span: Range<usize>,
+    options: &ParseOptions,
+) -> Result<(), SgfParseErrorKind> {
+    let rejected = || RejectedProperty {
+        identifier: prop.identifier(),
+        span,
+    };
+    if options.reject_unknown_properties && prop.is_unknown() {
+        Err(SgfParseErrorKind::UnknownProperty(rejected()))
+    } else if options.reject_invalid_properties && prop.is_invalid() {
+        Err(SgfParseErrorKind::InvalidProperty(rejected()))
+    } else {
+        Ok(())
+    }
+}
+
+// Pop incomplete nodes down to `depth`, adding each to its parent's children.
+fn close_nodes<Prop: SgfProp>(
+    stack: &mut Vec<SgfNode<Prop>>,
+    collection: &mut Vec<SgfNode<Prop>>,
+    depth: usize,
+) {
+    while stack.len() > depth {
+        let node = stack.pop().unwrap();
+        match stack.last_mut() {
+            Some(parent) => parent.children.push(node),
+            None => collection.push(node),
+        }
+    }
+}
+
+// Returns whether a node pushed onto `stack` would be the first child of its parent.
+fn is_first_child<Prop: SgfProp>(stack: &[SgfNode<Prop>], collection: &[SgfNode<Prop>]) -> bool {
+    match stack.last() {
+        Some(parent) => parent.children.is_empty(),
+        None => collection.is_empty(),
+    }
+}
+
+// Insert a `StartNode` token wherever a game tree starts with a property.
+//
+// Inserted tokens have an empty span at the start of the property.
+fn insert_missing_nodes<'a>(
+    tokens: Vec<SpannedToken<'a>>,
+    mut recoveries: Option<&mut Vec<Recovery>>,
+) -> Vec<SpannedToken<'a>> {
+    if !tokens
+        .windows(2)
+        .any(|pair| matches!(pair, [(Token::StartGameTree, _), (Token::Property(_), _)]))
+    {
+        return tokens;
+    }
+    let mut new_tokens: Vec<SpannedToken> = Vec::with_capacity(tokens.len() + 1);
+    for (token, span) in tokens {
+        if matches!(token, Token::Property(_))
+            && matches!(new_tokens.last(), Some((Token::StartGameTree, _)))
+        {
+            new_tokens.push((Token::StartNode, span.start..span.start));
+            if let Some(recoveries) = recoveries.as_deref_mut() {
+                recoveries.push(Recovery {
+                    action: RecoveryAction::InsertedNode,
+                    span: span.start..span.start,
+                });
+            }
+        }
+        new_tokens.push((token, span));
+    }
+
+    new_tokens
+}
+
+// Replace or add root property tokens from `overrides`.
+//
+// Added tokens have an empty span at the end of the root node.
+fn apply_root_overrides<'a>(
+    mut tokens: Vec<SpannedToken<'a>>,
+    overrides: &BTreeMap<String, Vec<String>>,
+) -> Vec<SpannedToken<'a>> {
+    if overrides.is_empty() || !starts_with_root_node(&tokens) {
+        return tokens;
+    }
+    let root_props_end = root_props_end(&tokens);
+    let mut missing: Vec<_> = overrides.keys().collect();
+    for (token, _) in &mut tokens[2..root_props_end] {
+        if let Token::Property((identifier, values)) = token {
+            if let Some(new_values) = overrides.get(identifier) {
+                *values = new_values
+                    .iter()
+                    .map(|v| {
+                        Cow::Owned(
+                            escape_raw_values(identifier, std::slice::from_ref(v)).into_bytes(),
+                        )
+                    })
+                    .collect();
+                missing.retain(|&ident| ident != identifier);
+            }
+        }
+    }
+    let end = tokens[root_props_end - 1].1.end;
+    let new_tokens = missing.into_iter().map(|identifier| {
+        let values = overrides[identifier].iter().map(|v| {
+            Cow::Owned(escape_raw_values(identifier, std::slice::from_ref(v)).into_bytes())
+        });
+        (
+            Token::Property((identifier.clone(), values.collect())),
+            end..end,
+        )
+    });
+    tokens.splice(root_props_end..root_props_end, new_tokens);
+
+    tokens
+}
+
+// Root properties which determine how a gametree is parsed.
+const GAMETREE_DETECTION_IDENTIFIERS: [&str; 2] = ["GM", "FF"];
+
+// Find detection properties outside the root node of a gametree's tokens.
+//
+// Returns the path to each node along with the property token.
+fn misplaced_root_properties<'a, 'b>(
+    tokens: &'a [SpannedToken<'b>],
+) -> Vec<(NodePath, &'a Token<'b>)> {
+    // Parent index and child index of each node so far, in order.
+    let mut nodes: Vec<(Option<usize>, usize)> = vec![];
+    let mut child_counts: Vec<usize> = vec![];
+    let mut current: Option<usize> = None;
+    let mut gametree_starts: Vec<Option<usize>> = vec![];
+    let mut misplaced = vec![];
+    for (token, _) in tokens {
+        match token {
+            Token::StartGameTree => gametree_starts.push(current),
+            Token::EndGameTree => current = gametree_starts.pop().flatten(),
+            Token::StartNode => {
+                let index = match current {
+                    Some(parent) => {
+                        child_counts[parent] += 1;
+                        child_counts[parent] - 1
+                    }
+                    None => 0,
+                };
+                nodes.push((current, index));
+                child_counts.push(0);
+                current = Some(nodes.len() - 1);
+            }
+            Token::Property((identifier, _))
+                if current.is_some_and(|node| node > 0)
+                    && GAMETREE_DETECTION_IDENTIFIERS.contains(&identifier.as_str()) =>
+            {
+                let mut path = vec![];
+                let mut ancestor = current.unwrap();
+                while let (Some(parent), index) = nodes[ancestor] {
+                    path.push(index);
+                    ancestor = parent;
+                }
+                path.reverse();
+                misplaced.push((NodePath::from(path), token));
+            }
+            Token::Property(_) => {}
+        }
+    }
+
+    misplaced
+}
+
+// Move detection properties from non-root nodes to the root node.
+fn hoist_misplaced_root_properties(tokens: Vec<SpannedToken>) -> Vec<SpannedToken> {
+    if !starts_with_root_node(&tokens) || misplaced_root_properties(&tokens).is_empty() {
+        return tokens;
+    }
+    let root_props_end = root_props_end(&tokens);
+    let mut present: HashSet<String> = tokens[2..root_props_end]
+        .iter()
+        .filter_map(|(token, _)| match token {
+            Token::Property((identifier, _)) => Some(identifier.clone()),
+            _ => None,
+        })
+        .collect();
+    let mut tokens = tokens.into_iter();
+    let mut new_tokens: Vec<_> = tokens.by_ref().take(root_props_end).collect();
+    let mut rest = vec![];
+    // Indices in `rest` of the nodes properties were moved from.
+    let mut hoisted_from = vec![];
+    let mut node_start = None;
+    for token in tokens {
+        match &token.0 {
+            Token::Property((identifier, _))
+                if GAMETREE_DETECTION_IDENTIFIERS.contains(&identifier.as_str()) =>
+            {
+                if present.insert(identifier.clone()) {
+                    new_tokens.push(token);
+                }
+                hoisted_from.extend(node_start.take());
+            }
+            Token::Property(_) => rest.push(token),
+            Token::StartNode => {
+                node_start = Some(rest.len());
+                rest.push(token);
+            }
+            _ => {
+                node_start = None;
+                rest.push(token);
+            }
         }
     }
-}
+    let emptied: HashSet<usize> = hoisted_from
+        .into_iter()
+        .filter(|&i| !matches!(rest.get(i + 1), Some((Token::Property(_), _))))
+        .collect();
+    new_tokens.extend(remove_empty_nodes(rest, &emptied));
 
-impl std::error::Error for SgfParseError {}
+    new_tokens
+}
 
-// Split the tokens up into individual gametrees.
+// Remove the nodes starting at the `emptied` indices from the tokens.
 //
-// This will let us easily scan each gametree for GM properties.
-// Only considers StartGameTree/EndGameTree tokens.
-fn split_by_gametree(tokens: &[Token]) -> Result<Vec<&[Token]>, SgfParseError> {
-    let mut gametrees = vec![];
-    let mut gametree_depth: u64 = 0;
-    let mut slice_start = 0;
-    for (i, token) in tokens.iter().enumerate() {
+// A removed node's children take its place. If it was the first node of a game tree and has no
+// node after it, the game tree itself is removed so its variations are moved up to the parent.
+fn remove_empty_nodes<'a>(
+    tokens: Vec<SpannedToken<'a>>,
+    emptied: &HashSet<usize>,
+) -> Vec<SpannedToken<'a>> {
+    if emptied.is_empty() {
+        return tokens;
+    }
+    let mut removed = vec![false; tokens.len()];
+    // Indices of the tokens kept so far, and of the start of each open game tree.
+    let mut kept: Vec<usize> = vec![];
+    let mut gametree_starts: Vec<usize> = vec![];
+    for (i, (token, _)) in tokens.iter().enumerate() {
         match token {
-            Token::StartGameTree => gametree_depth += 1,
+            Token::StartGameTree => gametree_starts.push(i),
             Token::EndGameTree => {
-                if gametree_depth == 0 {
-                    return Err(SgfParseError::UnexpectedGameTreeEnd);
+                if let Some(start) = gametree_starts.pop() {
+                    removed[i] = removed[start];
                 }
-                gametree_depth -= 1;
-                if gametree_depth == 0 {
-                    gametrees.push(&tokens[slice_start..=i]);
-                    slice_start = i + 1;
+            }
+            Token::StartNode if emptied.contains(&i) => {
+                removed[i] = true;
+                let first_in_gametree = matches!(
+                    kept.last().map(|&j| &tokens[j].0),
+                    Some(Token::StartGameTree)
+                );
+                let followed_by_node = matches!(tokens.get(i + 1), Some((Token::StartNode, _)));
+                if first_in_gametree && !followed_by_node {
+                    removed[kept.pop().unwrap()] = true;
                 }
             }
             _ => {}
         }
-    }
-    if gametree_depth != 0 {
-        return Err(SgfParseError::UnexpectedEndOfData);
+        if !removed[i] {
+            kept.push(i);
+        }
     }
 
-    Ok(gametrees)
+    tokens
+        .into_iter()
+        .zip(removed)
+        .filter(|(_, removed)| !removed)
+        .map(|(token, _)| token)
+        .collect()
 }
 
-// Parse a single gametree of a known type.
-fn parse_gametree<Prop: SgfProp>(
-    tokens: &[Token],
-    options: &ParseOptions,
-) -> Result<GameTree, SgfParseError>
-where
-    SgfNode<Prop>: std::convert::Into<GameTree>,
-{
-    // TODO: Rewrite this without `unsafe`
-    let mut collection: Vec<SgfNode<Prop>> = vec![];
-    // //// Pointer to the `Vec` of children we're currently building.
-    let mut current_node_list_ptr = NonNull::new(&mut collection).unwrap();
-    // Stack of pointers to incomplete `Vec`s of children.
-    let mut incomplete_child_lists: Vec<NonNull<Vec<SgfNode<Prop>>>> = vec![];
-    //// Using pointers involves some unsafe calls, but should be ok here.
-    //// Since pointers are always initialized from real structs, and those structs
-    //// live for the whole function body, our only safety concern is dangling pointers.
-    ////
-    //// Since we build the tree traversing depth-first those structs shouldn't be
-    //// modified while the pointer is live. Heap-allocated contents of their
-    //// `children` may be modified, but that shouldn't change anything.
-
-    let mut tokens = tokens.iter().peekable();
-    while let Some(token) = tokens.next() {
-        match token {
-            Token::StartGameTree => {
-                // SGF game trees must have a root node.
-                if let Some(node_list_ptr) = incomplete_child_lists.last() {
-                    let node_list = unsafe { node_list_ptr.as_ref() };
-                    if node_list.is_empty() {
-                        return Err(SgfParseError::UnexpectedGameTreeStart);
-                    }
-                }
-                incomplete_child_lists.push(current_node_list_ptr);
+// Whether the tokens start with a game tree's root node.
+fn starts_with_root_node(tokens: &[SpannedToken]) -> bool {
+    matches!(
+        tokens,
+        [(Token::StartGameTree, _), (Token::StartNode, _), ..]
+    )
+}
+
+// Returns the index of the first token after the root node's properties.
+fn root_props_end(tokens: &[SpannedToken]) -> usize {
+    2 + tokens[2..]
+        .iter()
+        .take_while(|(token, _)| matches!(token, Token::Property(_)))
+        .count()
+}
+
+// Check that the compressed point lists in a Go gametree don't expand past `max_points`.
+//
+// This runs on the raw tokens so we can bail out before any points are allocated.
+fn check_compressed_points(
+    tokens: &[SpannedToken],
+    max_points: usize,
+) -> Result<(), SgfParseErrorKind> {
+    const POINT_LIST_IDENTIFIERS: [&str; 12] = [
+        "AB", "AE", "AW", "CR", "DD", "MA", "SL", "SQ", "TR", "VW", "TB", "TW",
+    ];
+    let mut total: usize = 0;
+    for (token, _) in tokens {
+        if let Token::Property((identifier, values)) = token {
+            if !POINT_LIST_IDENTIFIERS.contains(&identifier.as_str()) {
+                continue;
             }
-            Token::EndGameTree => match incomplete_child_lists.pop() {
-                Some(node_list) => current_node_list_ptr = node_list,
-                None => return Err(SgfParseError::UnexpectedGameTreeEnd),
-            },
-            Token::StartNode => {
-                let mut new_node = SgfNode::default();
-                let mut prop_tokens = vec![];
-                while let Some(Token::Property(_)) = tokens.peek() {
-                    prop_tokens.push(tokens.next().unwrap());
-                }
-                for token in prop_tokens {
-                    match token {
-                        // TODO: Consider refactoring to consume tokens and clone of values.
-                        Token::Property((identifier, values)) => {
-                            let identifier = {
-                                if identifier.chars().all(|c| c.is_ascii_uppercase()) {
-                                    identifier.clone()
-                                } else if options.convert_mixed_case_identifiers {
-                                    identifier
-                                        .chars()
-                                        .filter(|c| c.is_ascii_uppercase())
-                                        .collect()
-                                } else {
-                                    return Err(SgfParseError::InvalidFF4Property);
-                                }
-                            };
-                            new_node
-                                .properties
-                                .push(Prop::new(identifier, values.clone()))
-                        }
-                        _ => unreachable!(),
+            for value in values
+                .iter()
+                .filter_map(|value| std::str::from_utf8(value).ok())
+            {
+                if let Ok((ul, lr)) = parse_tuple::<go::Point, go::Point>(value) {
+                    if ul.x <= lr.x && ul.y <= lr.y {
+                        let width = usize::from(lr.x - ul.x) + 1;
+                        let height = usize::from(lr.y - ul.y) + 1;
+                        total = total.saturating_add(width * height);
                     }
                 }
-                let node_list = unsafe { current_node_list_ptr.as_mut() };
-                node_list.push(new_node);
-                current_node_list_ptr =
-                    NonNull::new(&mut node_list.last_mut().unwrap().children).unwrap();
             }
-            Token::Property(_) => return Err(SgfParseError::UnexpectedProperty),
+            if total > max_points {
+                return Err(SgfParseErrorKind::CompressedPointLimitExceeded);
+            }
         }
     }
-
-    if !incomplete_child_lists.is_empty() || collection.len() != 1 {
-        return Err(SgfParseError::UnexpectedEndOfData);
-    }
-    let mut root_node = collection.into_iter().next().unwrap();
-    root_node.is_root = true;
-    Ok(root_node.into())
+    Ok(())
 }
 
 // Figure out which game to parse from a slice of tokens.
 //
 // This function is necessary because we need to know the game before we can do the parsing.
-fn find_gametype(tokens: &[Token]) -> Result<GameType, SgfParseError> {
+fn is_go_gametree(tokens: &[SpannedToken]) -> Result<bool, SgfParseErrorKind> {
     match find_gametree_root_prop_values("GM", tokens)? {
-        None => Ok(GameType::Go),
+        None => Ok(true),
+        Some(values) => Ok(values.len() == 1 && values[0][..] == b"1"[..]),
+    }
+}
+
+// Find the registered parser for a gametree's `GM` value, if any.
+fn find_custom_game_parser<'a>(
+    tokens: &[SpannedToken],
+    options: &'a ParseOptions,
+) -> Result<Option<&'a CustomGameParser>, SgfParseErrorKind> {
+    if options.custom_games.is_empty() {
+        return Ok(None);
+    }
+    let values = match find_gametree_root_prop_values("GM", tokens)? {
+        Some(values) if values.len() == 1 => values,
+        _ => return Ok(None),
+    };
+    let gametype = std::str::from_utf8(&values[0])
+        .ok()
+        .and_then(|s| s.trim().parse::<i64>().ok());
+    Ok(gametype.and_then(|gametype| options.custom_games.get(&gametype)))
+}
+
+// Whether the data being parsed is already decoded text, or raw bytes to decode.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Input {
+    Text,
+    Bytes,
+}
+
+// Character set of a gametree as declared by the root `CA` property.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Charset {
+    // No `CA` property, so values are decoded as UTF-8 if possible and ISO-8859-1 otherwise.
+    Unspecified,
+    Utf8,
+    Latin1,
+    #[cfg(feature = "encoding")]
+    Encoding(&'static encoding_rs::Encoding),
+    Other,
+}
+
+fn find_charset(tokens: &[SpannedToken], input: Input) -> Result<Charset, SgfParseErrorKind> {
+    if input == Input::Text {
+        return Ok(Charset::Utf8);
+    }
+    // See https://www.red-bean.com/sgf/properties.html#CA
+    match find_gametree_root_prop_values("CA", tokens)? {
+        None => Ok(Charset::Unspecified),
         Some(values) => {
-            if values.len() != 1 {
-                return Ok(GameType::Unknown);
-            }
-            match values[0].as_str() {
-                "1" => Ok(GameType::Go),
-                _ => Ok(GameType::Unknown),
+            let name = String::from_utf8_lossy(values.first().map_or(&[][..], |v| v));
+            Ok(charset_from_label(&name))
+        }
+    }
+}
+
+// Look up the character set for a `CA` property value.
+pub(crate) fn charset_from_label(label: &str) -> Charset {
+    let name = label.trim().to_ascii_uppercase();
+    match name.as_str() {
+        "UTF-8" | "UTF8" | "US-ASCII" | "ASCII" => Charset::Utf8,
+        "ISO-8859-1" | "ISO8859-1" | "LATIN1" | "LATIN-1" | "WINDOWS-1252" | "CP1252" => {
+            Charset::Latin1
+        }
+        #[cfg(feature = "encoding")]
+        _ => encoding_rs::Encoding::for_label(name.as_bytes())
+            .map_or(Charset::Other, Charset::Encoding),
+        #[cfg(not(feature = "encoding"))]
+        _ => Charset::Other,
+    }
+}
+
+// Unescape and decode the raw values of the property with this identifier.
+fn decode_values(
+    identifier: &str,
+    values: &[Cow<[u8]>],
+    charset: Charset,
+    options: &ParseOptions,
+) -> Result<Vec<String>, SgfParseErrorKind> {
+    let keep_escapes = has_compose_values(identifier);
+    values
+        .iter()
+        .map(|value| decode_value(unescape(value, keep_escapes), charset, options))
+        .collect()
+}
+
+fn decode_value(
+    value: Cow<[u8]>,
+    charset: Charset,
+    options: &ParseOptions,
+) -> Result<String, SgfParseErrorKind> {
+    match charset {
+        Charset::Latin1 => return Ok(value.iter().map(|&b| decode_cp1252(b)).collect()),
+        #[cfg(feature = "encoding")]
+        Charset::Encoding(encoding) => {
+            let (text, had_errors) = encoding.decode_without_bom_handling(&value);
+            if had_errors && !options.lossy_decoding {
+                return Err(SgfParseErrorKind::InvalidTextEncoding);
             }
+            return Ok(text.into_owned());
+        }
+        _ => {}
+    }
+    // Values unescaped into a new buffer are reused rather than copied.
+    let value = match value {
+        Cow::Owned(bytes) => match String::from_utf8(bytes) {
+            Ok(s) => return Ok(s),
+            Err(e) => Cow::Owned(e.into_bytes()),
+        },
+        Cow::Borrowed(bytes) => Cow::Borrowed(bytes),
+    };
+    let value = &value[..];
+    match std::str::from_utf8(value) {
+        Ok(s) => Ok(s.to_string()),
+        Err(_) if charset == Charset::Unspecified => {
+            Ok(value.iter().map(|&b| decode_cp1252(b)).collect())
+        }
+        Err(_) if options.lossy_decoding => Ok(String::from_utf8_lossy(value).into_owned()),
+        Err(_) => Err(SgfParseErrorKind::InvalidTextEncoding),
+    }
+}
+
+// Decode data starting with a UTF-16 byte order mark (if any).
+fn decode_utf16(data: &[u8], options: &ParseOptions) -> Result<Option<String>, SgfParseErrorKind> {
+    let from_bytes: fn([u8; 2]) -> u16 = match data.get(..2) {
+        Some([0xFF, 0xFE]) => u16::from_le_bytes,
+        Some([0xFE, 0xFF]) => u16::from_be_bytes,
+        _ => return Ok(None),
+    };
+    let chunks = data[2..].chunks_exact(2);
+    let trailing_byte = !chunks.remainder().is_empty();
+    let units = chunks.map(|chunk| from_bytes([chunk[0], chunk[1]]));
+    let mut text = String::with_capacity(data.len() / 2);
+    for c in std::char::decode_utf16(units) {
+        match c {
+            Ok(c) => text.push(c),
+            Err(_) if options.lossy_decoding => text.push(std::char::REPLACEMENT_CHARACTER),
+            Err(_) => return Err(SgfParseErrorKind::InvalidTextEncoding),
         }
     }
+    if trailing_byte && !options.lossy_decoding {
+        return Err(SgfParseErrorKind::InvalidTextEncoding);
+    }
+
+    Ok(Some(text))
+}
+
+// Decode a byte as Windows-1252.
+//
+// ISO-8859-1 maps 0x80-0x9F to control characters which are almost never intended, while
+// Windows-1252 (which files labeled ISO-8859-1 are often really using) maps them to printable
+// characters. Like web browsers, we treat ISO-8859-1 as Windows-1252.
+pub(crate) fn decode_cp1252(byte: u8) -> char {
+    const HIGH_CONTROL_CHARS: [char; 32] = [
+        '\u{20AC}', '\u{81}', '\u{201A}', '\u{192}', '\u{201E}', '\u{2026}', '\u{2020}',
+        '\u{2021}', '\u{2C6}', '\u{2030}', '\u{160}', '\u{2039}', '\u{152}', '\u{8D}', '\u{17D}',
+        '\u{8F}', '\u{90}', '\u{2018}', '\u{2019}', '\u{201C}', '\u{201D}', '\u{2022}', '\u{2013}',
+        '\u{2014}', '\u{2DC}', '\u{2122}', '\u{161}', '\u{203A}', '\u{153}', '\u{9D}', '\u{17E}',
+        '\u{178}',
+    ];
+    match byte {
+        0x80..=0x9F => HIGH_CONTROL_CHARS[usize::from(byte - 0x80)],
+        _ => byte as char,
+    }
 }
 
 // Find the property values for a given identifier in the root node from the gametree's tokens.
 //
 // We use this to determine key root properties (like GM and FF) before parsing.
 // Returns an error if there's more than one match.
-fn find_gametree_root_prop_values<'a>(
+fn find_gametree_root_prop_values<'a, 'b>(
     prop_ident: &'a str,
-    tokens: &'a [Token],
-) -> Result<Option<&'a Vec<String>>, SgfParseError> {
+    tokens: &'a [SpannedToken<'b>],
+) -> Result<Option<&'a Vec<Cow<'b, [u8]>>>, SgfParseErrorKind> {
     // Find the matching property values in the first node.
     // Skip the initial StartGameTree, StartNode tokens; we'll handle any errors later.
-    let matching_tokens: Vec<&Vec<String>> = tokens
+    let matching_tokens: Vec<&Vec<Cow<[u8]>>> = tokens
         .iter()
         .skip(2)
+        .map(|(token, _)| token)
         .take_while(|&token| matches!(token, Token::Property(_)))
         .filter_map(move |token| match token {
             Token::Property((ident, values)) if ident == prop_ident => Some(values),
@@ -272,14 +2521,14 @@ fn find_gametree_root_prop_values<'a>(
     match matching_tokens.len() {
         0 => Ok(None),
         1 => Ok(Some(matching_tokens[0])),
-        _ => Err(SgfParseError::UnexpectedProperty),
+        _ => Err(SgfParseErrorKind::UnexpectedProperty),
     }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::{go, serialize};
+    use crate::{go, serialize, GameType};
 
     fn load_test_sgf() -> Result<String, Box<dyn std::error::Error>> {
         // See https://www.red-bean.com/sgf/examples/
@@ -359,7 +2608,7 @@ mod test {
         let sgf_nodes = go::parse(input).unwrap();
         let expected = vec![
             go::Prop::GM(1),
-            go::Prop::Invalid("W".to_string(), vec!["rp.pmonpoqprpsornqmpm".to_string()]),
+            go::Prop::Invalid("W".into(), vec!["rp.pmonpoqprpsornqmpm".to_string()]),
         ];
 
         assert_eq!(sgf_nodes.len(), 1);
@@ -367,6 +2616,539 @@ mod test {
         assert_eq!(sgf_node.properties().cloned().collect::<Vec<_>>(), expected);
     }
 
+    #[test]
+    fn misplaced_root_properties() {
+        let sgf = "(;FF[4];B[aa](;W[bb];FF[3])(;W[cc](;GM[1])(;GM[2]FF[4])))";
+        let misplaced: Vec<(Vec<usize>, String)> = find_misplaced_root_properties(sgf)
+            .unwrap()
+            .into_iter()
+            .map(|prop| (prop.path.into(), prop.identifier))
+            .collect();
+        let expected = vec![
+            (vec![0, 0, 0], "FF".to_string()),
+            (vec![0, 1, 0], "GM".to_string()),
+            (vec![0, 1, 1], "GM".to_string()),
+            (vec![0, 1, 1], "FF".to_string()),
+        ];
+        assert_eq!(misplaced, expected);
+
+        let options = ParseOptions {
+            hoist_misplaced_root_properties: true,
+            ..ParseOptions::default()
+        };
+        let gametrees = parse_with_options(sgf, &options).unwrap();
+        assert_eq!(gametrees[0].gametype(), GameType::Go);
+        assert_eq!(
+            gametrees[0].to_string(),
+            "(;FF[4]GM[1];B[aa](;W[bb])(;W[cc]))"
+        );
+
+        let sgf = "(;C[a](;GM[1];FF[4](;B[aa])(;W[bb];GM[2]))(;GM[1];C[b]))";
+        let gametrees = parse_with_options(sgf, &options).unwrap();
+        assert_eq!(
+            gametrees[0].to_string(),
+            "(;C[a]GM[1]FF[4](;B[aa])(;W[bb])(;C[b]))"
+        );
+    }
+
+    #[test]
+    fn sgf_reader_small_buffer() {
+        let data = load_test_sgf().unwrap();
+        let sgf = format!("{}\n(;C[Escaped \\] and ) paren];B[aa])\n", data);
+        let reader = std::io::BufReader::with_capacity(3, sgf.as_bytes());
+        let gametrees: Vec<_> = SgfReader::new(reader, ParseOptions::default())
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(gametrees, parse(&sgf).unwrap());
+        assert_eq!(gametrees.len(), 3);
+    }
+
+    #[test]
+    fn parse_from_reader_matches_parse() {
+        let data = load_test_sgf().unwrap();
+        let reader = std::io::BufReader::with_capacity(16, data.as_bytes());
+        let gametrees = parse_from_reader(reader, &ParseOptions::default()).unwrap();
+        assert_eq!(gametrees, parse(&data).unwrap());
+        let result = parse_from_reader("(;B[aa]))".as_bytes(), &ParseOptions::default());
+        assert_eq!(
+            result.map_err(|e| e.kind),
+            Err(SgfParseErrorKind::UnexpectedGameTreeEnd)
+        );
+    }
+
+    #[test]
+    fn charset_detection() {
+        let comment = |data: &[u8]| {
+            let node = parse_bytes(data).unwrap().remove(0).into_go_node().unwrap();
+            match node.get_property("C") {
+                Some(go::Prop::C(text)) => text.text.clone(),
+                _ => unreachable!(),
+            }
+        };
+        assert_eq!(comment(b"(;CA[windows-1252]C[\x96\xe9])"), "–é");
+        assert_eq!(comment(b"(;CA[latin1]C[\x81\xff])"), "\u{81}ÿ");
+        assert_eq!(comment(b"(;CA[US-ASCII]C[\xc3\xa9])"), "é");
+        assert_eq!(comment(b"(;CA[ISO-8859-1]C[\xc3\xa9])"), "Ã©");
+        let text = parse("(;CA[ISO-8859-1]C[é])").unwrap();
+        assert_eq!(serialize(&text), "(;CA[ISO-8859-1]C[é])");
+        assert_eq!(
+            parse_bytes(b"(;CA[US-ASCII]C[\xe9])").map_err(|e| e.kind),
+            Err(SgfParseErrorKind::InvalidTextEncoding)
+        );
+    }
+
+    #[cfg(feature = "encoding")]
+    #[test]
+    fn transcode_declared_charset() {
+        let gametrees =
+            parse_bytes(b"(;CA[GB2312]C[\xc6\xe5])(;CA[Shift_JIS]C[\x82\xa0])").unwrap();
+        assert_eq!(
+            serialize(&gametrees),
+            "(;CA[GB2312]C[棋])(;CA[Shift_JIS]C[あ])"
+        );
+        assert_eq!(
+            parse_bytes(b"(;CA[Shift_JIS]C[\x82])").map_err(|e| e.kind),
+            Err(SgfParseErrorKind::InvalidTextEncoding)
+        );
+    }
+
+    #[cfg(feature = "encoding")]
+    #[test]
+    fn load_flexible_declared_charset() {
+        let path = std::env::temp_dir().join(format!(
+            "sgf_parse_load_flexible_declared_charset_{}.sgf",
+            std::process::id()
+        ));
+        std::fs::write(&path, b"(;CA[Shift_JIS]C[\x82\xa0]").unwrap();
+        let (gametrees, report) = load_flexible(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(serialize(&gametrees), "(;CA[Shift_JIS]C[あ])");
+        assert_eq!(report.recoveries.len(), 1);
+    }
+
+    #[test]
+    fn byte_order_marks() {
+        let expected = parse("(;C[Café])").unwrap();
+        assert_eq!(
+            parse_bytes(b"\xef\xbb\xbf(;C[Caf\xc3\xa9])").unwrap(),
+            expected
+        );
+        let utf16: Vec<u16> = "\u{feff}(;C[Café])".encode_utf16().collect();
+        let le: Vec<u8> = utf16.iter().flat_map(|unit| unit.to_le_bytes()).collect();
+        let be: Vec<u8> = utf16.iter().flat_map(|unit| unit.to_be_bytes()).collect();
+        assert_eq!(parse_bytes(&le).unwrap(), expected);
+        assert_eq!(parse_bytes(&be).unwrap(), expected);
+        assert_eq!(
+            parse_bytes(&le[..le.len() - 1]).map_err(|e| e.kind),
+            Err(SgfParseErrorKind::InvalidTextEncoding)
+        );
+        assert_eq!(parse("\u{feff}(;C[Café])").unwrap(), expected);
+    }
+
+    #[test]
+    fn error_locations() {
+        let locate = |sgf: &str| {
+            let error = parse(sgf).unwrap_err();
+            let location = error.location.unwrap();
+            (error.kind, location.span, location.line, location.column)
+        };
+        assert_eq!(
+            locate("(;B[aa])\n(;C[é]W[bb]\n;B[cc]"),
+            (SgfParseErrorKind::UnexpectedEndOfData, 28..28, 3, 7)
+        );
+        assert_eq!(
+            locate("(;B[aa])\n(;Cé[x])"),
+            (
+                SgfParseErrorKind::LexerError(LexerError::UnexpectedEndOfProperty),
+                11..12,
+                2,
+                3
+            )
+        );
+        assert_eq!(
+            locate("(;B[aa])\n(;C[é]((;B[cc])))"),
+            (SgfParseErrorKind::UnexpectedGameTreeStart, 9..27, 2, 1)
+        );
+        assert_eq!(
+            locate("(;B[aa]\r\n;C[x"),
+            (
+                SgfParseErrorKind::LexerError(LexerError::UnexpectedEndOfProperty),
+                10..13,
+                2,
+                2
+            )
+        );
+    }
+
+    #[test]
+    fn flexible_parsing() {
+        let (gametrees, report) = parse_flexible("(;GM[1]SZ[9];B[aa])\n(;C[Two])");
+        assert_eq!(gametrees.len(), 2);
+        assert!(report.is_clean());
+        assert!(report.is_faithful(1));
+
+        let (gametrees, report) = parse_flexible("(;B[aa]) (;C[Two]");
+        assert_eq!(serialize(&gametrees), "(;B[aa])(;C[Two])");
+        assert!(report.is_faithful(0));
+        assert!(!report.is_faithful(1));
+
+        assert_eq!(decode_mixed(b"\xe9t\xc3\xa9 \x80"), "été €");
+        assert_eq!(
+            decode_declared_charset(b"junk (;CA[ISO-8859-1]C[\xc3\xa9]"),
+            "junk (;CA[ISO-8859-1]C[Ã©]"
+        );
+        assert_eq!(decode_declared_charset(b"(;C[\xc3\xa9 \xe9]"), "(;C[é é]");
+        assert_eq!(
+            load_flexible("/nonexistent/sgf_parse/game.sgf").map_err(|e| e.kind),
+            Err(SgfParseErrorKind::IoError(io::ErrorKind::NotFound))
+        );
+    }
+
+    #[test]
+    fn parse_report() {
+        let options = ParseOptions {
+            recover_missing_nodes: true,
+            ..ParseOptions::default()
+        };
+        let sgf = ") x[y] (B[aa]aw[bb]) ((;W[cc])) (;C[ok]) (;C[unterminated";
+        let (gametrees, report) = parse_with_report(sgf, &options).unwrap();
+        assert_eq!(serialize(&gametrees), "(;B[aa])(;C[ok])(;)");
+        let recoveries: Vec<_> = report
+            .recoveries
+            .iter()
+            .map(|recovery| (recovery.action.clone(), &sgf[recovery.span.clone()]))
+            .collect();
+        let expected = vec![
+            (RecoveryAction::SkippedContent, ") x[y]"),
+            (RecoveryAction::InsertedNode, ""),
+            (RecoveryAction::DroppedProperty("aw".to_string()), "aw[bb]"),
+            (
+                RecoveryAction::SkippedGameTree(SgfParseErrorKind::UnexpectedGameTreeStart.into()),
+                "((;W[cc]))",
+            ),
+            (RecoveryAction::SkippedContent, "C[unterminated"),
+            (RecoveryAction::ClosedGameTree, ""),
+        ];
+        assert_eq!(recoveries, expected);
+        assert!(!report.is_clean());
+        let modifications: Vec<Vec<_>> = (0..3)
+            .map(|i| report.modifications(i).map(|r| r.action.clone()).collect())
+            .collect();
+        assert_eq!(
+            modifications,
+            vec![
+                vec![
+                    RecoveryAction::InsertedNode,
+                    RecoveryAction::DroppedProperty("aw".to_string())
+                ],
+                vec![],
+                vec![
+                    RecoveryAction::SkippedContent,
+                    RecoveryAction::ClosedGameTree
+                ],
+            ]
+        );
+        assert!(!report.is_faithful(0));
+        assert!(report.is_faithful(1));
+        assert!(!report.is_faithful(2));
+        assert!(!report.is_faithful(3));
+
+        let (_, report) = parse_with_report("(;B[aa])", &options).unwrap();
+        assert!(report.is_clean());
+
+        let filtered = ParseOptions {
+            property_filter: PropertyFilter::Drop(vec!["C".to_string()].into_iter().collect()),
+            ..ParseOptions::default()
+        };
+        let sgf = "(;B[aa]C[x]) (;Ww[bb]C[y])";
+        let (gametrees, report) = parse_with_report(sgf, &filtered).unwrap();
+        assert_eq!(serialize(&gametrees), "(;B[aa])(;W[bb])");
+        let recoveries: Vec<_> = report
+            .recoveries
+            .iter()
+            .map(|recovery| (recovery.action.clone(), &sgf[recovery.span.clone()]))
+            .collect();
+        let expected = vec![
+            (RecoveryAction::FilteredProperty("C".to_string()), "C[x]"),
+            (
+                RecoveryAction::ConvertedIdentifier("Ww".to_string()),
+                "Ww[bb]",
+            ),
+            (RecoveryAction::FilteredProperty("C".to_string()), "C[y]"),
+        ];
+        assert_eq!(recoveries, expected);
+        assert!(!report.is_faithful(0));
+    }
+
+    #[test]
+    fn events() {
+        use SgfEvent::*;
+        let prop = |identifier: &str, values: &[&str]| {
+            Property(
+                identifier.to_string(),
+                values.iter().map(|value| value.to_string()).collect(),
+            )
+        };
+        let events: Vec<_> = parse_events("(;AB[aa][bb]C[a\\]b];W[cc]) (;)")
+            .collect::<Result<_, _>>()
+            .unwrap();
+        let expected = vec![
+            GameTreeStart,
+            NodeStart,
+            prop("AB", &["aa", "bb"]),
+            prop("C", &["a]b"]),
+            NodeStart,
+            prop("W", &["cc"]),
+            GameTreeEnd,
+            GameTreeStart,
+            NodeStart,
+            GameTreeEnd,
+        ];
+        assert_eq!(events, expected);
+
+        let cases = [
+            ("(;B[aa]))", SgfParseErrorKind::UnexpectedGameTreeEnd),
+            ("(;B[aa]", SgfParseErrorKind::UnexpectedEndOfData),
+            ("(B[aa])", SgfParseErrorKind::UnexpectedProperty),
+            (";B[aa]", SgfParseErrorKind::UnexpectedProperty),
+            ("((;B[aa]))", SgfParseErrorKind::UnexpectedGameTreeStart),
+            ("()", SgfParseErrorKind::UnexpectedGameTreeEnd),
+        ];
+        for (sgf, error) in cases.iter() {
+            let results: Vec<_> = parse_events(sgf).collect();
+            assert_eq!(results.last(), Some(&Err(error.clone().into())), "{}", sgf);
+            assert_eq!(results.iter().filter(|result| result.is_err()).count(), 1);
+        }
+    }
+
+    #[test]
+    fn reject_unknown_and_invalid_properties() {
+        let sgf = "(;GM[1]FF[4]SZ[9]KM[x];B[cc]ZZ[1];W[dd]TB[aa]) (;GM[2]TB[aa])";
+        assert!(parse(sgf).is_ok());
+        let unknown = ParseOptions {
+            reject_unknown_properties: true,
+            ..ParseOptions::default()
+        };
+        let rejected = |identifier: &str, span| RejectedProperty {
+            identifier: identifier.to_string(),
+            span,
+        };
+        assert_eq!(
+            parse_with_options(sgf, &unknown).map_err(|e| e.kind),
+            Err(SgfParseErrorKind::UnknownProperty(rejected("ZZ", 28..33)))
+        );
+        // `TB` is only defined for Go.
+        assert_eq!(
+            parse_with_options(&sgf[47..], &unknown).map_err(|e| e.kind),
+            Err(SgfParseErrorKind::UnknownProperty(rejected("TB", 7..13)))
+        );
+        let error = parse_with_options(sgf, &unknown).unwrap_err();
+        assert_eq!(&sgf[error.location.unwrap().span], "ZZ[1]");
+
+        let invalid = ParseOptions {
+            reject_invalid_properties: true,
+            ..ParseOptions::default()
+        };
+        assert_eq!(
+            parse_with_options(sgf, &invalid).map_err(|e| e.kind),
+            Err(SgfParseErrorKind::InvalidProperty(rejected("KM", 17..22)))
+        );
+
+        // Filtered properties aren't checked.
+        let filtered = ParseOptions {
+            property_filter: PropertyFilter::Drop(
+                vec!["KM".to_string(), "ZZ".to_string()]
+                    .into_iter()
+                    .collect(),
+            ),
+            reject_invalid_properties: true,
+            ..unknown
+        };
+        assert_eq!(
+            parse_with_options(sgf, &filtered).map_err(|e| e.kind),
+            Err(SgfParseErrorKind::UnknownProperty(rejected("TB", 54..60)))
+        );
+        assert_eq!(
+            parse_with_options(&sgf[..46], &filtered).map(|_| ()),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn compose_escapes_round_trip() {
+        let sgf = "(;AP[My\\: App:1.0]LB[aa:with \\: colon][bb:back\\\\slash]FG[1:a\\:b])";
+        let text = |text: &str| crate::SimpleText {
+            text: text.to_string(),
+        };
+        let node = go::parse(sgf).unwrap().remove(0);
+        assert_eq!(node.get_ap(), Some(&(text("My: App"), text("1.0"))));
+        let labels = node.get_lb().unwrap();
+        assert!(labels.contains(&("aa".parse().unwrap(), text("with : colon"))));
+        assert!(labels.contains(&("bb".parse().unwrap(), text("back\\slash"))));
+        assert_eq!(node.get_fg(), Some(&Some((1, text("a:b")))));
+        assert_eq!(go::parse(&node.serialize()).unwrap(), vec![node]);
+
+        // Invalid values are written back out as they were read.
+        for sgf in [
+            "(;LB[aa:x:y])",
+            "(;AP[a\\]b])",
+            "(;KM[x\\]\\\\])",
+            "(;XX[a\\]b:c])",
+        ]
+        .iter()
+        {
+            let gametrees = parse(sgf).unwrap();
+            assert_eq!(serialize(&gametrees), *sgf);
+        }
+    }
+
+    #[test]
+    fn raw_unknown_game_values_round_trip() {
+        let options = ParseOptions {
+            keep_raw_unknown_game_values: true,
+            ..ParseOptions::default()
+        };
+        let sgf = "(;GM[5]FF[4]SZ[8]C[soft\\\r\nbreak]XX[a\\:b][c\\d]AB[b\\:c][aa]\
+                   AP[x\\:y:1];W[e4]N[\\]](;B[d5])(;B[e5]YY[]))";
+        let gametrees = parse_with_options(sgf, &options).unwrap();
+        assert_eq!(serialize(&gametrees), sgf);
+        let node = match &gametrees[0] {
+            GameTree::Unknown(node) => node,
+            _ => panic!("Unexpected game type"),
+        };
+        assert_eq!(node.get_property("GM"), Some(&unknown_game::Prop::GM(5)));
+        assert_eq!(
+            node.get_property("XX"),
+            Some(&unknown_game::Prop::Unknown(
+                "XX".into(),
+                vec!["a:b".to_string(), "cd".to_string()]
+            ))
+        );
+        assert_eq!(node.get_comment().unwrap().text, "softbreak");
+        assert!(node.validate().is_ok());
+        assert_eq!(gametrees, parse(sgf).unwrap());
+
+        // Changed properties are serialized as usual.
+        let mut node = node.clone();
+        node.set_property(unknown_game::Prop::new(
+            "XX".to_string(),
+            vec!["c:d".to_string()],
+        ));
+        assert_eq!(
+            node.serialize(),
+            "(;GM[5]FF[4]SZ[8]C[soft\\\r\nbreak]XX[c:d]AB[b\\:c][aa]AP[x\\:y:1];W[e4]N[\\]]\
+             (;B[d5])(;B[e5]YY[]))"
+        );
+
+        // Go games are parsed as usual.
+        let gametrees = parse_with_options("(;C[a\\b])", &options).unwrap();
+        assert_eq!(serialize(&gametrees), "(;C[ab])");
+    }
+
+    #[test]
+    fn trailing_content_text_is_not_decoded_again() {
+        let sgf = "(;CA[ISO-8859-1]C[Café]) garbage";
+        let (gametrees, trailing) =
+            parse_with_trailing_content(sgf, &ParseOptions::default()).unwrap();
+        assert_eq!(serialize(&gametrees), "(;CA[ISO-8859-1]C[Café])");
+        assert_eq!(trailing.unwrap().preview, "garbage");
+    }
+
+    #[test]
+    fn identifier_limits() {
+        let options = ParseOptions {
+            max_identifier_length: Some(4),
+            letters_only_identifiers: true,
+            ..ParseOptions::default()
+        };
+        let sgf = "(;B[aa]Garbage[x]  ;W[bb]\t\x01\x02[y][z]\n;B[cc]Lost[]";
+        let error = parse_with_options(sgf, &options).unwrap_err();
+        assert_eq!(
+            error.kind,
+            SgfParseErrorKind::LexerError(LexerError::PropertyIdentifierTooLong)
+        );
+        assert_eq!(error.location.unwrap().span, 7..11);
+
+        let (gametrees, report) = parse_with_report(sgf, &options).unwrap();
+        assert_eq!(serialize(&gametrees), "(;B[aa];W[bb];B[cc]L[])");
+        let skipped: Vec<_> = report
+            .recoveries
+            .iter()
+            .filter(|recovery| recovery.action == RecoveryAction::SkippedContent)
+            .map(|recovery| &sgf[recovery.span.clone()])
+            .collect();
+        assert_eq!(skipped, vec!["Garbage[x]", "\x01\x02[y][z]"]);
+
+        // Trailing content starts at the first game tree the limits reject.
+        let (gametrees, trailing) =
+            parse_with_trailing_content("(;B[aa])(;Garbage[x])", &options).unwrap();
+        assert_eq!(gametrees.len(), 1);
+        assert_eq!(trailing.unwrap().preview, "(;Garbage[x])");
+
+        // Readers stop buffering at the first identifier the limits reject.
+        let result = parse_from_reader("(;B[aa]\n(;W 1[bb]))".as_bytes(), &options);
+        assert_eq!(
+            result.map_err(|e| e.kind),
+            Err(SgfParseErrorKind::LexerError(
+                LexerError::UnexpectedPropertyIdentifier
+            ))
+        );
+        let garbage = io::Read::chain("(;B[aa]".as_bytes(), io::repeat(b'A'));
+        let mut reader = SgfReader::new(io::BufReader::new(garbage), options);
+        assert_eq!(
+            reader.next().unwrap().map_err(|e| e.kind),
+            Err(SgfParseErrorKind::LexerError(
+                LexerError::PropertyIdentifierTooLong
+            ))
+        );
+    }
+
+    #[test]
+    fn sgf_reader_errors() {
+        let mut reader = SgfReader::new("(;B[aa])(;B[bb]".as_bytes(), ParseOptions::default());
+        assert!(reader.next().unwrap().is_ok());
+        assert_eq!(
+            reader.next().unwrap().map_err(|e| e.kind),
+            Err(SgfParseErrorKind::UnexpectedEndOfData)
+        );
+        assert!(reader.next().is_none());
+
+        struct FailingReader;
+        impl std::io::Read for FailingReader {
+            fn read(&mut self, _buf: &mut [u8]) -> std::io::Result<usize> {
+                Err(std::io::ErrorKind::BrokenPipe.into())
+            }
+        }
+        let reader = std::io::BufReader::new(FailingReader);
+        let mut reader = SgfReader::new(reader, ParseOptions::default());
+        assert_eq!(
+            reader.next().unwrap().map_err(|e| e.kind),
+            Err(SgfParseErrorKind::IoError(std::io::ErrorKind::BrokenPipe))
+        );
+    }
+
+    #[test]
+    fn reader_error_locations_are_relative_to_the_stream() {
+        let options = || ParseOptions {
+            reject_invalid_properties: true,
+            ..ParseOptions::default()
+        };
+        for sgf in [
+            "(;B[aa])\n(;B[bb]\n;W[cc])\n\n  (;SZ[9]\nKM[x])",
+            "(;C[é])  (;KM[x])",
+        ] {
+            let expected = parse_with_options(sgf, &options()).unwrap_err();
+            assert!(expected.location.is_some());
+            let error = parse_from_reader(sgf.as_bytes(), &options()).unwrap_err();
+            assert_eq!(error, expected);
+            let reader = io::BufReader::with_capacity(3, sgf.as_bytes());
+            let error = SgfReader::new(reader, options())
+                .find_map(Result::err)
+                .unwrap();
+            assert_eq!(error, expected);
+        }
+    }
+
     #[test]
     fn unknown_game() {
         let input = "(;GM[37]W[rp.pmonpoqprpsornqmpm])";
@@ -398,7 +3180,7 @@ mod test {
     fn stack_overflow() {
         // This input generated a stack overflow with the old code
         let input = "(;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;)";
-        let result = parse(&input);
+        let result = parse(input);
         assert!(result.is_ok());
     }
 
@@ -425,14 +3207,101 @@ mod test {
             convert_mixed_case_identifiers: false,
             ..ParseOptions::default()
         };
+        let result = parse_with_options(input, &parse_options).map_err(|e| e.kind);
+        assert_eq!(result, Err(SgfParseErrorKind::InvalidFF4Property));
+    }
+
+    #[test]
+    fn compressed_point_limit() {
+        let input = "(;GM[1]SZ[52]AB[aa:ZZ];AW[aa:ee])";
+        let parse_options = ParseOptions {
+            max_compressed_points: Some(2704),
+            ..ParseOptions::default()
+        };
+        let result = parse_with_options(input, &parse_options).map_err(|e| e.kind);
+        assert_eq!(result, Err(SgfParseErrorKind::CompressedPointLimitExceeded));
+
+        let parse_options = ParseOptions {
+            max_compressed_points: Some(2729),
+            ..ParseOptions::default()
+        };
+        assert!(parse_with_options(input, &parse_options).is_ok());
+    }
+
+    #[test]
+    fn max_main_line_nodes() {
+        let input = "(;SZ[9];B[aa](;W[bb];B[cc](;W[dd]))(;W[ee](;B[ff])))";
+        let parse_options = ParseOptions {
+            max_main_line_nodes: Some(3),
+            ..ParseOptions::default()
+        };
+        let gametrees = parse_with_options(input, &parse_options).unwrap();
+        assert_eq!(
+            serialize(&gametrees),
+            "(;SZ[9:9];B[aa](;W[bb])(;W[ee];B[ff]))"
+        );
+
+        let input = "(;C[a];C[b](;C[c];C[d](;C[e])(;C[f]))(;C[g]))";
+        let parse_options = ParseOptions {
+            max_main_line_nodes: Some(2),
+            ..ParseOptions::default()
+        };
+        let gametrees = parse_with_options(input, &parse_options).unwrap();
+        assert_eq!(serialize(&gametrees), "(;C[a];C[b];C[g])");
+
+        let parse_options = ParseOptions {
+            max_main_line_nodes: Some(0),
+            ..ParseOptions::default()
+        };
         let result = parse_with_options(input, &parse_options);
-        assert_eq!(result, Err(SgfParseError::InvalidFF4Property));
+        assert_eq!(
+            result.map_err(|e| e.kind),
+            Err(SgfParseErrorKind::InvalidMainLineLimit)
+        );
+    }
+
+    #[test]
+    fn root_overrides_add_missing_properties() {
+        let mut parse_options = ParseOptions::default();
+        parse_options
+            .root_overrides
+            .insert("SZ".to_string(), vec!["13".to_string()]);
+        let gametrees = parse_with_options("(;C[Comment];B[de])(;B[dd])", &parse_options).unwrap();
+        assert_eq!(
+            serialize(&gametrees),
+            "(;C[Comment]SZ[13:13];B[de])(;B[dd]SZ[13:13])"
+        );
+    }
+
+    #[test]
+    fn property_filter_keep() {
+        let parse_options = ParseOptions {
+            property_filter: PropertyFilter::Keep(
+                vec!["B".to_string(), "W".to_string()].into_iter().collect(),
+            ),
+            ..ParseOptions::default()
+        };
+        let gametrees =
+            parse_with_options("(;SZ[9]C[Comment];B[de]LB[aa:A];W[fe])", &parse_options).unwrap();
+        assert_eq!(serialize(&gametrees), "(;;B[de];W[fe])");
+    }
+
+    #[test]
+    fn custom_game_parser_error() {
+        let mut parse_options = ParseOptions::default();
+        parse_options.custom_games.insert(
+            2,
+            Arc::new(|_| Err(SgfParseErrorKind::UnexpectedGameType.into())),
+        );
+        let result =
+            parse_with_options("(;GM[1]B[aa])(;GM[2]B[aa])", &parse_options).map_err(|e| e.kind);
+        assert_eq!(result, Err(SgfParseErrorKind::UnexpectedGameType));
     }
 
     #[test]
     fn compressed_list_for_unknown_game() {
         let input = "(;GM[]MA[a:b])";
-        let gametree = parse(&input).unwrap().pop().unwrap();
+        let gametree = parse(input).unwrap().pop().unwrap();
         let node = match gametree {
             GameTree::Unknown(node) => node,
             _ => panic!("Expected Unknown Game type"),
@@ -0,0 +1,178 @@
+use crate::{parse_with_options, serialize, GameTree, ParseOptions, SgfParseError};
+
+type Transform = Box<dyn Fn(&mut GameTree) -> Result<(), String> + Send + Sync>;
+
+/// A reusable sequence of transformations applied to SGF collections.
+///
+/// Each input is parsed, every [`GameTree`] is passed through the transforms in the order they
+/// were added, and the result is serialized again. Errors are reported per input so one bad file
+/// doesn't stop a batch.
+///
+/// # Examples
+/// ```
+/// use sgf_parse::{GameTree, Pipeline, SgfProp};
+///
+/// let pipeline = Pipeline::new().transform(|gametree| {
+///     if let GameTree::GoGame(node) = gametree {
+///         node.properties.retain(|prop| prop.identifier() != "C");
+///     }
+/// });
+/// let results = pipeline.run(["(;C[Comment]B[de])", "(;B[dd]"]);
+/// assert_eq!(results[0], Ok("(;B[de])".to_string()));
+/// assert!(results[1].is_err());
+/// ```
+pub struct Pipeline {
+    options: ParseOptions,
+    transforms: Vec<Transform>,
+}
+
+impl Pipeline {
+    /// Returns a new pipeline with no transforms using default parsing options.
+    pub fn new() -> Self {
+        Self::with_options(ParseOptions::default())
+    }
+
+    /// Returns a new pipeline with no transforms using the provided parsing options.
+    pub fn with_options(options: ParseOptions) -> Self {
+        Self {
+            options,
+            transforms: vec![],
+        }
+    }
+
+    /// Adds a transform to the end of the pipeline.
+    pub fn transform<F>(self, f: F) -> Self
+    where
+        F: Fn(&mut GameTree) + Send + Sync + 'static,
+    {
+        self.try_transform(move |gametree| {
+            f(gametree);
+            Ok(())
+        })
+    }
+
+    /// Adds a fallible transform to the end of the pipeline.
+    ///
+    /// If the transform returns an error, processing of the current input stops and the error is
+    /// reported as [`PipelineError::Transform`].
+    pub fn try_transform<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&mut GameTree) -> Result<(), String> + Send + Sync + 'static,
+    {
+        self.transforms.push(Box::new(f));
+        self
+    }
+
+    /// Returns the serialized result of running the pipeline on a single input.
+    ///
+    /// # Errors
+    /// Returns an error if the input can't be parsed or a transform fails.
+    pub fn run_one(&self, text: &str) -> Result<String, PipelineError> {
+        let mut gametrees = parse_with_options(text, &self.options)?;
+        for gametree in gametrees.iter_mut() {
+            for transform in &self.transforms {
+                transform(gametree).map_err(PipelineError::Transform)?;
+            }
+        }
+        Ok(serialize(&gametrees))
+    }
+
+    /// Returns the results of running the pipeline on each input in order.
+    pub fn run<I, T>(&self, inputs: I) -> Vec<Result<String, PipelineError>>
+    where
+        I: IntoIterator<Item = T>,
+        T: AsRef<str>,
+    {
+        inputs
+            .into_iter()
+            .map(|text| self.run_one(text.as_ref()))
+            .collect()
+    }
+
+    /// Returns the results of running the pipeline on each input using up to `threads` threads.
+    ///
+    /// Results are returned in the same order as the inputs.
+    ///
+    /// # Examples
+    /// ```
+    /// use sgf_parse::Pipeline;
+    ///
+    /// let inputs = vec!["(;B[de])"; 16];
+    /// let results = Pipeline::new().run_parallel(&inputs, 4);
+    /// assert!(results.iter().all(|result| result.as_deref() == Ok("(;B[de])")));
+    /// ```
+    pub fn run_parallel<T>(
+        &self,
+        inputs: &[T],
+        threads: usize,
+    ) -> Vec<Result<String, PipelineError>>
+    where
+        T: AsRef<str> + Sync,
+    {
+        if inputs.is_empty() {
+            return vec![];
+        }
+        let chunk_size = inputs.len().div_ceil(threads.max(1));
+        std::thread::scope(|scope| {
+            let handles: Vec<_> = inputs
+                .chunks(chunk_size)
+                .map(|chunk| scope.spawn(move || self.run(chunk)))
+                .collect();
+            handles
+                .into_iter()
+                .flat_map(|handle| handle.join().expect("pipeline worker panicked"))
+                .collect()
+        })
+    }
+}
+
+impl Default for Pipeline {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Error type for failures processing an input with a [`Pipeline`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PipelineError {
+    Parse(SgfParseError),
+    Transform(String),
+}
+
+impl From<SgfParseError> for PipelineError {
+    fn from(error: SgfParseError) -> Self {
+        Self::Parse(error)
+    }
+}
+
+impl std::fmt::Display for PipelineError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PipelineError::Parse(e) => write!(f, "Error parsing: {}", e),
+            PipelineError::Transform(e) => write!(f, "Error in transform: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for PipelineError {}
+
+#[cfg(test)]
+mod tests {
+    use super::{Pipeline, PipelineError};
+
+    #[test]
+    fn transform_errors_are_reported_per_input() {
+        let pipeline = Pipeline::new().try_transform(|gametree| match gametree.to_string().len() {
+            n if n > 10 => Err("too long".to_string()),
+            _ => Ok(()),
+        });
+        let results = pipeline.run_parallel(&["(;B[de])", "(;B[de];W[ee];B[ff])"], 2);
+        assert_eq!(
+            results,
+            vec![
+                Ok("(;B[de])".to_string()),
+                Err(PipelineError::Transform("too long".to_string()))
+            ]
+        );
+    }
+}
@@ -0,0 +1,43 @@
+use crate::Color;
+
+/// A game position which moves can be applied to.
+///
+/// This trait lets analysis code (replaying games, checking legality, fingerprinting positions)
+/// be written once for any game with a position implementation. See
+/// [`go::Position`](`crate::go::Position`) for the Go implementation.
+///
+/// # Examples
+/// ```
+/// use sgf_parse::{Color, GamePosition};
+/// use sgf_parse::go::{Move, Position};
+///
+/// fn replay<P: GamePosition>(position: &mut P, moves: &[(Color, P::Move)]) -> Result<(), P::Error> {
+///     for (color, mv) in moves {
+///         position.apply_move(*color, mv)?;
+///     }
+///     Ok(())
+/// }
+///
+/// let mut position = Position::new(9, 9);
+/// let moves = [(Color::Black, Move::Move("ee".parse().unwrap())), (Color::White, Move::Pass)];
+/// replay(&mut position, &moves).unwrap();
+/// assert_eq!(position.to_play(), Color::Black);
+/// ```
+pub trait GamePosition {
+    /// The type of moves which can be applied to the position.
+    type Move;
+    /// Error type for illegal moves.
+    type Error;
+
+    /// Applies a move by the given player.
+    ///
+    /// # Errors
+    /// Returns an error if the move is illegal. The position is left unchanged in that case.
+    fn apply_move(&mut self, color: Color, mv: &Self::Move) -> Result<(), Self::Error>;
+
+    /// Returns the player to move next.
+    fn to_play(&self) -> Color;
+
+    /// Returns a hash of the position, including the player to move.
+    fn position_hash(&self) -> u64;
+}
@@ -1,5 +1,287 @@
+// The table of general properties, passed to `$callback` after its arguments.
+//
+// This is the single list of properties defined by the SGF specification. Properties without
+// values are listed under `flags`. Each other property lists its typed getter, its property type,
+// the kind of value it has, and the function parsing its raw values (with an optional range for
+// numbers). The `Prop` enums, their getters, the `idents` constants, the `property_spec` table
+// and `has_compose_values` are all generated from it.
+//
+// See https://www.red-bean.com/sgf/properties.html
+macro_rules! general_properties {
+    ($callback:ident! { $($args:tt)* }) => {
+        $callback! {
+            $($args)*
+            flags: {
+                // Move properties
+                KO [Move];
+                // Move annotation properties
+                DO [Move];
+                IT [Move];
+            }
+            values: {
+                // Move properties
+                B(get_b) [Move]: Move = parse_single_value;
+                MN(get_mn) [Move]: Number = parse_single_value;
+                W(get_w) [Move]: Move = parse_single_value;
+                // Setup properties
+                AB(get_ab) [Setup]: StoneList = parse_list;
+                AE(get_ae) [Setup]: PointList = parse_list;
+                AW(get_aw) [Setup]: StoneList = parse_list;
+                PL(get_pl) [Setup]: Color = parse_single_value;
+                // Node annotation properties
+                C(get_c) []: Text = parse_single_value;
+                DM(get_dm) []: Double = parse_single_value;
+                GB(get_gb) []: Double = parse_single_value;
+                GW(get_gw) []: Double = parse_single_value;
+                HO(get_ho) []: Double = parse_single_value;
+                N(get_n) []: SimpleText = parse_single_value;
+                UC(get_uc) []: Double = parse_single_value;
+                V(get_v) []: Real = parse_single_value;
+                // Move annotation properties
+                BM(get_bm) [Move]: Double = parse_single_value;
+                TE(get_te) [Move]: Double = parse_single_value;
+                // Markup properties
+                AR(get_ar) []: PointPairList = parse_list_composed;
+                CR(get_cr) []: PointList = parse_list;
+                DD(get_dd) [Inherit]: PointEList = parse_elist;
+                LB(get_lb) []: Labels = parse_labels;
+                LN(get_ln) []: PointPairList = parse_list_lines;
+                MA(get_ma) []: PointList = parse_list;
+                SL(get_sl) []: PointList = parse_list;
+                SQ(get_sq) []: PointList = parse_list;
+                TR(get_tr) []: PointList = parse_list;
+                // Root properties
+                AP(get_ap) [Root]: Application = parse_application;
+                CA(get_ca) [Root]: SimpleText = parse_single_value;
+                FF(get_ff) [Root]: Number = parse_single_value, 0..=4;
+                GM(get_gm) [Root]: Number = parse_single_value;
+                ST(get_st) [Root]: Number = parse_single_value, 0..=3;
+                SZ(get_sz) [Root]: Size = parse_size;
+                // Game info properties
+                AN(get_an) [GameInfo]: SimpleText = parse_single_value;
+                BR(get_br) [GameInfo]: SimpleText = parse_single_value;
+                BT(get_bt) [GameInfo]: SimpleText = parse_single_value;
+                CP(get_cp) [GameInfo]: SimpleText = parse_single_value;
+                DT(get_dt) [GameInfo]: SimpleText = parse_single_value;
+                EV(get_ev) [GameInfo]: SimpleText = parse_single_value;
+                GN(get_gn) [GameInfo]: SimpleText = parse_single_value;
+                GC(get_gc) [GameInfo]: Text = parse_single_value;
+                ON(get_on) [GameInfo]: SimpleText = parse_single_value;
+                OT(get_ot) [GameInfo]: SimpleText = parse_single_value;
+                PB(get_pb) [GameInfo]: SimpleText = parse_single_value;
+                PC(get_pc) [GameInfo]: SimpleText = parse_single_value;
+                PW(get_pw) [GameInfo]: SimpleText = parse_single_value;
+                RE(get_re) [GameInfo]: SimpleText = parse_single_value;
+                RO(get_ro) [GameInfo]: SimpleText = parse_single_value;
+                RU(get_ru) [GameInfo]: SimpleText = parse_single_value;
+                SO(get_so) [GameInfo]: SimpleText = parse_single_value;
+                TM(get_tm) [GameInfo]: Real = parse_single_value;
+                US(get_us) [GameInfo]: SimpleText = parse_single_value;
+                WR(get_wr) [GameInfo]: SimpleText = parse_single_value;
+                WT(get_wt) [GameInfo]: SimpleText = parse_single_value;
+                // Timing properties
+                BL(get_bl) [Move]: Real = parse_single_value;
+                OB(get_ob) [Move]: Number = parse_single_value;
+                OW(get_ow) [Move]: Number = parse_single_value;
+                WL(get_wl) [Move]: Real = parse_single_value;
+                // Miscellaneous properties
+                FG(get_fg) []: Figure = parse_figure;
+                PM(get_pm) [Inherit]: Number = parse_single_value, 1..=2;
+                VW(get_vw) [Inherit]: PointEList = parse_elist;
+            }
+        }
+    };
+}
+
+// The table of Go specific properties, in the same form as `general_properties!`.
+//
+// See https://www.red-bean.com/sgf/go.html
+macro_rules! go_properties {
+    ($callback:ident! { $($args:tt)* }) => {
+        $callback! {
+            $($args)*
+            flags: {}
+            values: {
+                HA(get_ha) [GameInfo]: Number = parse_single_value, 2..;
+                KM(get_km) [GameInfo]: Real = parse_single_value;
+                TB(get_tb) []: PointEList = parse_elist;
+                TW(get_tw) []: PointEList = parse_elist;
+            }
+        }
+    };
+}
+
+// The Rust type of a value kind from a property table, given a game's move, point, stone and
+// board size types.
+macro_rules! prop_value_type {
+    (Number, $mv:ty, $pt:ty, $st:ty, $sz:ty) => { i64 };
+    (Real, $mv:ty, $pt:ty, $st:ty, $sz:ty) => { f64 };
+    (Double, $mv:ty, $pt:ty, $st:ty, $sz:ty) => { crate::props::Double };
+    (Color, $mv:ty, $pt:ty, $st:ty, $sz:ty) => { crate::props::Color };
+    (SimpleText, $mv:ty, $pt:ty, $st:ty, $sz:ty) => { crate::props::SimpleText };
+    (Text, $mv:ty, $pt:ty, $st:ty, $sz:ty) => { crate::props::Text };
+    (Move, $mv:ty, $pt:ty, $st:ty, $sz:ty) => { $mv };
+    (StoneList, $mv:ty, $pt:ty, $st:ty, $sz:ty) => { std::collections::HashSet<$st> };
+    (PointList, $mv:ty, $pt:ty, $st:ty, $sz:ty) => { std::collections::HashSet<$pt> };
+    (PointEList, $mv:ty, $pt:ty, $st:ty, $sz:ty) => { std::collections::HashSet<$pt> };
+    (PointPairList, $mv:ty, $pt:ty, $st:ty, $sz:ty) => { std::collections::HashSet<($pt, $pt)> };
+    (Labels, $mv:ty, $pt:ty, $st:ty, $sz:ty) => {
+        std::collections::HashSet<($pt, crate::props::SimpleText)>
+    };
+    (Application, $mv:ty, $pt:ty, $st:ty, $sz:ty) => {
+        (crate::props::SimpleText, crate::props::SimpleText)
+    };
+    (Size, $mv:ty, $pt:ty, $st:ty, $sz:ty) => { $sz };
+    (Figure, $mv:ty, $pt:ty, $st:ty, $sz:ty) => { Option<(i64, crate::props::SimpleText)> };
+}
+
+// The `ValueType` of a value kind from a property table.
+macro_rules! prop_spec_value_type {
+    (None) => {
+        crate::ValueType::None
+    };
+    (Number) => {
+        crate::ValueType::Number
+    };
+    (Real) => {
+        crate::ValueType::Real
+    };
+    (Double) => {
+        crate::ValueType::Double
+    };
+    (Color) => {
+        crate::ValueType::Color
+    };
+    (SimpleText) => {
+        crate::ValueType::SimpleText
+    };
+    (Text) => {
+        crate::ValueType::Text
+    };
+    (Move) => {
+        crate::ValueType::Move
+    };
+    (StoneList) => {
+        crate::ValueType::List(&crate::ValueType::Stone)
+    };
+    (PointList) => {
+        crate::ValueType::List(&crate::ValueType::Point)
+    };
+    (PointEList) => {
+        crate::ValueType::EList(&crate::ValueType::Point)
+    };
+    (PointPairList) => {
+        crate::ValueType::List(&crate::ValueType::Compose(
+            &crate::ValueType::Point,
+            &crate::ValueType::Point,
+        ))
+    };
+    (Labels) => {
+        crate::ValueType::List(&crate::ValueType::Compose(
+            &crate::ValueType::Point,
+            &crate::ValueType::SimpleText,
+        ))
+    };
+    (Application) => {
+        crate::ValueType::Compose(&crate::ValueType::SimpleText, &crate::ValueType::SimpleText)
+    };
+    (Size) => {
+        crate::ValueType::Either(
+            &crate::ValueType::Number,
+            &crate::ValueType::Compose(&crate::ValueType::Number, &crate::ValueType::Number),
+        )
+    };
+    (Figure) => {
+        crate::ValueType::Either(
+            &crate::ValueType::None,
+            &crate::ValueType::Compose(&crate::ValueType::Number, &crate::ValueType::SimpleText),
+        )
+    };
+}
+
+// Whether values of a value kind from a property table are compose values.
+macro_rules! prop_has_compose_values {
+    (PointPairList) => {
+        true
+    };
+    (Labels) => {
+        true
+    };
+    (Application) => {
+        true
+    };
+    (Size) => {
+        true
+    };
+    (Figure) => {
+        true
+    };
+    ($kind:ident) => {
+        false
+    };
+}
+
+// The values of a property with a value of the given kind, as stored in `Unknown` and `Invalid`
+// properties.
+macro_rules! prop_raw_values {
+    (Text, $value:expr) => { vec![$value.text.clone()] };
+    (SimpleText, $value:expr) => { vec![$value.text.clone()] };
+    (StoneList, $value:expr) => { prop_raw_values!(@list $value) };
+    (PointList, $value:expr) => { prop_raw_values!(@list $value) };
+    (PointEList, $value:expr) => { prop_raw_values!(@list $value) };
+    (PointPairList, $value:expr) => { prop_raw_values!(@list $value) };
+    (Labels, $value:expr) => { prop_raw_values!(@list $value) };
+    (@list $value:expr) => {
+        if $value.is_empty() {
+            vec![String::new()]
+        } else {
+            crate::props::list_values($value)
+        }
+    };
+    ($kind:ident, $value:expr) => { vec![$value.to_sgf()] };
+}
+
+// The `PropertyType` from a property table entry, if it has one.
+macro_rules! prop_property_type {
+    () => {
+        None
+    };
+    ($property_type:ident) => {
+        Some(crate::props::PropertyType::$property_type)
+    };
+}
+
 macro_rules! sgf_prop {
-    ($name:ident, $mv:ty, $pt:ty, $st:ty, { $($variants:tt)* }) => {
+    ($name:ident, $mv:ty, $pt:ty, $st:ty, $sz:ty, $game_properties:ident $(,)?) => {
+        $game_properties!(sgf_prop! { @with_game $name, $mv, $pt, $st, $sz, });
+    };
+    ($name:ident, $mv:ty, $pt:ty, $st:ty, $sz:ty $(,)?) => {
+        general_properties!(sgf_prop! {
+            @merge $name, $mv, $pt, $st, $sz, flags: {} values: {}
+        });
+    };
+    (@with_game $name:ident, $mv:ty, $pt:ty, $st:ty, $sz:ty, $($game:tt)*) => {
+        general_properties!(sgf_prop! { @merge $name, $mv, $pt, $st, $sz, $($game)* });
+    };
+    (
+        @merge $name:ident, $mv:ty, $pt:ty, $st:ty, $sz:ty,
+        flags: { $($game_flags:tt)* } values: { $($game_values:tt)* }
+        flags: { $($flags:tt)* } values: { $($values:tt)* }
+    ) => {
+        sgf_prop! {
+            @impl $name, $mv, $pt, $st, $sz,
+            flags: { $($flags)* $($game_flags)* }
+            values: { $($values)* $($game_values)* }
+        }
+    };
+    (
+        @impl $name:ident, $mv:ty, $pt:ty, $st:ty, $sz:ty,
+        flags: { $($flag:ident [$($flag_type:ident)?];)* }
+        values: {
+            $($ident:ident($getter:ident) [$($property_type:ident)?]:
+                $kind:ident = $parse:path $(, $range:expr)?;)*
+        }
+    ) => {
         /// An SGF Property with identifier and value.
         ///
         /// All [general properties](https://www.red-bean.com/sgf/properties.html) from the SGF
@@ -19,390 +301,88 @@ macro_rules! sgf_prop {
         /// * 'Point' => [`Point`](`Self::Point`)
         /// * 'Stone' => [`Stone`](`Self::Stone`)
         /// * 'Move' => [`Move`](`Self::Move`)
+        /// * `SZ` => `(u8, u8)` for Go, or the raw [`String`] value for unknown games
         /// * 'List' => [`HashSet`](`std::collections::HashSet`)
         /// * 'Compose' => [`tuple`] of the composed values
         #[derive(Clone, Debug, PartialEq)]
         pub enum $name {
-            // Move properties
-            B($mv),
-            KO,
-            MN(i64),
-            W($mv),
-            // Setup properties
-            AB(std::collections::HashSet<$st>),
-            AE(std::collections::HashSet<$pt>),
-            AW(std::collections::HashSet<$st>),
-            PL(crate::props::Color),
-            // Node annotation properties
-            C(crate::props::Text),
-            DM(crate::props::Double),
-            GB(crate::props::Double),
-            GW(crate::props::Double),
-            HO(crate::props::Double),
-            N(crate::props::SimpleText),
-            UC(crate::props::Double),
-            V(f64),
-            // Move annotation properties
-            BM(crate::props::Double),
-            DO,
-            IT,
-            TE(crate::props::Double),
-            // Markup properties
-            AR(std::collections::HashSet<($pt, $pt)>),
-            CR(std::collections::HashSet<$pt>),
-            DD(std::collections::HashSet<$pt>),
-            LB(std::collections::HashSet<($pt, crate::props::SimpleText)>),
-            LN(std::collections::HashSet<($pt, $pt)>),
-            MA(std::collections::HashSet<$pt>),
-            SL(std::collections::HashSet<$pt>),
-            SQ(std::collections::HashSet<$pt>),
-            TR(std::collections::HashSet<$pt>),
-            // Root properties
-            AP((crate::props::SimpleText, crate::props::SimpleText)),
-            CA(crate::props::SimpleText),
-            FF(i64),
-            GM(i64),
-            ST(i64),
-            SZ((u8, u8)),
-            // Game info properties
-            AN(crate::props::SimpleText),
-            BR(crate::props::SimpleText),
-            BT(crate::props::SimpleText),
-            CP(crate::props::SimpleText),
-            DT(crate::props::SimpleText),
-            EV(crate::props::SimpleText),
-            GN(crate::props::SimpleText),
-            GC(crate::props::Text),
-            ON(crate::props::SimpleText),
-            OT(crate::props::SimpleText),
-            PB(crate::props::SimpleText),
-            PC(crate::props::SimpleText),
-            PW(crate::props::SimpleText),
-            RE(crate::props::SimpleText),
-            RO(crate::props::SimpleText),
-            RU(crate::props::SimpleText),
-            SO(crate::props::SimpleText),
-            TM(f64),
-            US(crate::props::SimpleText),
-            WR(crate::props::SimpleText),
-            WT(crate::props::SimpleText),
-            // Timing properties
-            BL(f64),
-            OB(i64),
-            OW(i64),
-            WL(f64),
-            // Miscellaneous properties
-            FG(Option<(i64, crate::props::SimpleText)>),
-            PM(i64),
-            VW(std::collections::HashSet<$pt>),
+            $($flag,)*
+            $($ident(prop_value_type!($kind, $mv, $pt, $st, $sz)),)*
             Unknown(String, Vec<String>),
             Invalid(String, Vec<String>),
-            // Game specific properties
-            $($variants)*
         }
 
         impl $name {
-            fn parse_general_prop(identifier: String, values: Vec<String>) -> Self {
+            fn parse_prop(identifier: String, values: Vec<String>) -> Self {
                 use crate::props::parse::{
-                    parse_elist, parse_list, parse_list_composed, parse_single_value, verify_empty,
+                    check_range, parse_elist, parse_list, parse_list_composed, parse_list_lines,
+                    parse_single_value, verify_empty,
                 };
 
                 let result = match &identifier[..] {
-                    "B" => parse_single_value(&values).map(Self::B),
-                    "KO" => verify_empty(&values).map(|()| Self::KO),
-                    "MN" => parse_single_value(&values).map(Self::MN),
-                    "W" => parse_single_value(&values).map(Self::W),
-                    "AB" => parse_list(&values).map(Self::AB),
-                    "AE" => parse_list(&values).map(Self::AE),
-                    "AW" => parse_list(&values).map(Self::AW),
-                    "PL" => parse_single_value(&values).map(Self::PL),
-                    "C" => parse_single_value(&values).map(Self::C),
-                    "DM" => parse_single_value(&values).map(Self::DM),
-                    "GB" => parse_single_value(&values).map(Self::GB),
-                    "GW" => parse_single_value(&values).map(Self::GW),
-                    "HO" => parse_single_value(&values).map(Self::HO),
-                    "N" => parse_single_value(&values).map(Self::N),
-                    "UC" => parse_single_value(&values).map(Self::UC),
-                    "V" => parse_single_value(&values).map(Self::V),
-                    "DO" => verify_empty(&values).map(|()| Self::DO),
-                    "IT" => verify_empty(&values).map(|()| Self::IT),
-                    "BM" => parse_single_value(&values).map(Self::BM),
-                    "TE" => parse_single_value(&values).map(Self::TE),
-                    "AR" => parse_list_composed(&values).map(Self::AR),
-                    "CR" => parse_list(&values).map(Self::CR),
-                    "DD" => parse_elist(&values).map(Self::DD),
-                    "LB" => parse_labels(&values).map(Self::LB),
-                    "LN" => parse_list_composed(&values).map(Self::LN),
-                    "MA" => parse_list(&values).map(Self::MA),
-                    "SL" => parse_list(&values).map(Self::SL),
-                    "SQ" => parse_list(&values).map(Self::SQ),
-                    "TR" => parse_list(&values).map(Self::TR),
-                    "AP" => parse_application(&values).map(Self::AP),
-                    "CA" => parse_single_value(&values).map(Self::CA),
-                    "FF" => match parse_single_value(&values) {
-                        Ok(value) => {
-                            if !(0..=4).contains(&value) {
-                                Err(SgfPropError {})
-                            } else {
-                                Ok(Self::FF(value))
-                            }
-                        }
-                        _ => Err(SgfPropError {}),
-                    },
-                    "GM" => parse_single_value(&values).map(Self::GM),
-                    "ST" => match parse_single_value(&values) {
-                        Ok(value) => {
-                            if !(0..=3).contains(&value) {
-                                Err(SgfPropError {})
-                            } else {
-                                Ok(Self::ST(value))
-                            }
-                        }
-                        _ => Err(SgfPropError {}),
-                    },
-                    "SZ" => parse_size(&values).map(Self::SZ),
-                    "AN" => parse_single_value(&values).map(Self::AN),
-                    "BR" => parse_single_value(&values).map(Self::BR),
-                    "BT" => parse_single_value(&values).map(Self::BT),
-                    "CP" => parse_single_value(&values).map(Self::CP),
-                    "DT" => parse_single_value(&values).map(Self::DT),
-                    "EV" => parse_single_value(&values).map(Self::EV),
-                    "GN" => parse_single_value(&values).map(Self::GN),
-                    "GC" => parse_single_value(&values).map(Self::GC),
-                    "ON" => parse_single_value(&values).map(Self::ON),
-                    "OT" => parse_single_value(&values).map(Self::OT),
-                    "PB" => parse_single_value(&values).map(Self::PB),
-                    "PC" => parse_single_value(&values).map(Self::PC),
-                    "PW" => parse_single_value(&values).map(Self::PW),
-                    "RE" => parse_single_value(&values).map(Self::RE),
-                    "RO" => parse_single_value(&values).map(Self::RO),
-                    "RU" => parse_single_value(&values).map(Self::RU),
-                    "SO" => parse_single_value(&values).map(Self::SO),
-                    "TM" => parse_single_value(&values).map(Self::TM),
-                    "US" => parse_single_value(&values).map(Self::US),
-                    "WR" => parse_single_value(&values).map(Self::WR),
-                    "WT" => parse_single_value(&values).map(Self::WT),
-                    "BL" => parse_single_value(&values).map(Self::BL),
-                    "OB" => parse_single_value(&values).map(Self::OB),
-                    "OW" => parse_single_value(&values).map(Self::OW),
-                    "WL" => parse_single_value(&values).map(Self::WL),
-                    "FG" => parse_figure(&values).map(Self::FG),
-                    "PM" => match parse_single_value(&values) {
-                        Ok(value) => {
-                            if !(1..=2).contains(&value) {
-                                Err(SgfPropError {})
-                            } else {
-                                Ok(Self::PM(value))
-                            }
-                        }
-                        _ => Err(SgfPropError {}),
-                    },
-                    "VW" => parse_elist(&values).map(Self::VW),
+                    $(stringify!($flag) => verify_empty(&values).map(|()| Self::$flag),)*
+                    $(stringify!($ident) => $parse(&values)
+                        $(.and_then(|value| check_range(value, $range)))?
+                        .map(Self::$ident),)*
                     _ => return Self::Unknown(identifier, values),
                 };
                 result.unwrap_or(Self::Invalid(identifier, values))
             }
 
-            fn general_identifier(&self) -> Option<String> {
+            fn prop_identifier(&self) -> &str {
                 match self {
-                    Self::B(_) => Some("B".to_string()),
-                    Self::KO => Some("KO".to_string()),
-                    Self::MN(_) => Some("MN".to_string()),
-                    Self::W(_) => Some("W".to_string()),
-                    Self::AB(_) => Some("AB".to_string()),
-                    Self::AE(_) => Some("AE".to_string()),
-                    Self::AW(_) => Some("AW".to_string()),
-                    Self::PL(_) => Some("PL".to_string()),
-                    Self::C(_) => Some("C".to_string()),
-                    Self::DM(_) => Some("DM".to_string()),
-                    Self::GB(_) => Some("GB".to_string()),
-                    Self::GW(_) => Some("GW".to_string()),
-                    Self::HO(_) => Some("HO".to_string()),
-                    Self::N(_) => Some("N".to_string()),
-                    Self::UC(_) => Some("UC".to_string()),
-                    Self::V(_) => Some("V".to_string()),
-                    Self::DO => Some("DO".to_string()),
-                    Self::IT => Some("IT".to_string()),
-                    Self::BM(_) => Some("BM".to_string()),
-                    Self::TE(_) => Some("TE".to_string()),
-                    Self::AR(_) => Some("AR".to_string()),
-                    Self::CR(_) => Some("CR".to_string()),
-                    Self::DD(_) => Some("DD".to_string()),
-                    Self::LB(_) => Some("LB".to_string()),
-                    Self::LN(_) => Some("LN".to_string()),
-                    Self::MA(_) => Some("MA".to_string()),
-                    Self::SL(_) => Some("SL".to_string()),
-                    Self::SQ(_) => Some("SQ".to_string()),
-                    Self::TR(_) => Some("TR".to_string()),
-                    Self::AP(_) => Some("AP".to_string()),
-                    Self::CA(_) => Some("CA".to_string()),
-                    Self::FF(_) => Some("FF".to_string()),
-                    Self::GM(_) => Some("GM".to_string()),
-                    Self::ST(_) => Some("ST".to_string()),
-                    Self::SZ(_) => Some("SZ".to_string()),
-                    Self::AN(_) => Some("AN".to_string()),
-                    Self::BR(_) => Some("BR".to_string()),
-                    Self::BT(_) => Some("BT".to_string()),
-                    Self::CP(_) => Some("CP".to_string()),
-                    Self::DT(_) => Some("DT".to_string()),
-                    Self::EV(_) => Some("EV".to_string()),
-                    Self::GN(_) => Some("GN".to_string()),
-                    Self::GC(_) => Some("GC".to_string()),
-                    Self::ON(_) => Some("ON".to_string()),
-                    Self::OT(_) => Some("OT".to_string()),
-                    Self::PB(_) => Some("PB".to_string()),
-                    Self::PC(_) => Some("PC".to_string()),
-                    Self::PW(_) => Some("PW".to_string()),
-                    Self::RE(_) => Some("RE".to_string()),
-                    Self::RO(_) => Some("RO".to_string()),
-                    Self::RU(_) => Some("RU".to_string()),
-                    Self::SO(_) => Some("SO".to_string()),
-                    Self::TM(_) => Some("TM".to_string()),
-                    Self::US(_) => Some("US".to_string()),
-                    Self::WR(_) => Some("WR".to_string()),
-                    Self::WT(_) => Some("WT".to_string()),
-                    Self::BL(_) => Some("BL".to_string()),
-                    Self::OB(_) => Some("OB".to_string()),
-                    Self::OW(_) => Some("OW".to_string()),
-                    Self::WL(_) => Some("WL".to_string()),
-                    Self::FG(_) => Some("FG".to_string()),
-                    Self::PM(_) => Some("PM".to_string()),
-                    Self::VW(_) => Some("VW".to_string()),
-                    Self::Invalid(identifier, _) => Some(identifier.to_string()),
-                    Self::Unknown(identifier, _) => Some(identifier.to_string()),
-                    #[allow(unreachable_patterns)]
-                    _ => None,
+                    $(Self::$flag => stringify!($flag),)*
+                    $(Self::$ident(_) => stringify!($ident),)*
+                    Self::Unknown(identifier, _) | Self::Invalid(identifier, _) => identifier,
                 }
             }
 
-            fn general_property_type(&self) -> Option<PropertyType> {
-                match &self {
-                    Self::B(_) => Some(PropertyType::Move),
-                    Self::KO => Some(PropertyType::Move),
-                    Self::MN(_) => Some(PropertyType::Move),
-                    Self::W(_) => Some(PropertyType::Move),
-                    Self::AB(_) => Some(PropertyType::Setup),
-                    Self::AE(_) => Some(PropertyType::Setup),
-                    Self::AW(_) => Some(PropertyType::Setup),
-                    Self::PL(_) => Some(PropertyType::Setup),
-                    Self::DO => Some(PropertyType::Move),
-                    Self::IT => Some(PropertyType::Move),
-                    Self::BM(_) => Some(PropertyType::Move),
-                    Self::TE(_) => Some(PropertyType::Move),
-                    Self::DD(_) => Some(PropertyType::Inherit),
-                    Self::AP(_) => Some(PropertyType::Root),
-                    Self::CA(_) => Some(PropertyType::Root),
-                    Self::FF(_) => Some(PropertyType::Root),
-                    Self::GM(_) => Some(PropertyType::Root),
-                    Self::ST(_) => Some(PropertyType::Root),
-                    Self::SZ(_) => Some(PropertyType::Root),
-                    Self::AN(_) => Some(PropertyType::GameInfo),
-                    Self::BR(_) => Some(PropertyType::GameInfo),
-                    Self::BT(_) => Some(PropertyType::GameInfo),
-                    Self::CP(_) => Some(PropertyType::GameInfo),
-                    Self::DT(_) => Some(PropertyType::GameInfo),
-                    Self::EV(_) => Some(PropertyType::GameInfo),
-                    Self::GN(_) => Some(PropertyType::GameInfo),
-                    Self::GC(_) => Some(PropertyType::GameInfo),
-                    Self::ON(_) => Some(PropertyType::GameInfo),
-                    Self::OT(_) => Some(PropertyType::GameInfo),
-                    Self::PB(_) => Some(PropertyType::GameInfo),
-                    Self::PC(_) => Some(PropertyType::GameInfo),
-                    Self::PW(_) => Some(PropertyType::GameInfo),
-                    Self::RE(_) => Some(PropertyType::GameInfo),
-                    Self::RO(_) => Some(PropertyType::GameInfo),
-                    Self::RU(_) => Some(PropertyType::GameInfo),
-                    Self::SO(_) => Some(PropertyType::GameInfo),
-                    Self::TM(_) => Some(PropertyType::GameInfo),
-                    Self::US(_) => Some(PropertyType::GameInfo),
-                    Self::WR(_) => Some(PropertyType::GameInfo),
-                    Self::WT(_) => Some(PropertyType::GameInfo),
-                    Self::BL(_) => Some(PropertyType::Move),
-                    Self::OB(_) => Some(PropertyType::Move),
-                    Self::OW(_) => Some(PropertyType::Move),
-                    Self::WL(_) => Some(PropertyType::Move),
-                    Self::PM(_) => Some(PropertyType::Inherit),
-                    Self::VW(_) => Some(PropertyType::Inherit),
-                    _ => None,
+            fn prop_type(&self) -> Option<PropertyType> {
+                match self {
+                    $(Self::$flag => prop_property_type!($($flag_type)?),)*
+                    $(Self::$ident(_) => prop_property_type!($($property_type)?),)*
+                    Self::Unknown(_, _) | Self::Invalid(_, _) => None,
                 }
             }
 
-            fn serialize_prop_value(&self) -> Option<String> {
+            // Returns the approximate number of bytes the property's value owns on the heap.
+            pub(crate) fn heap_size(&self) -> usize {
+                use crate::props::HeapSize;
+
                 match self {
-                    Self::B(x) => Some(x.to_sgf()),
-                    Self::KO => Some("".to_string()),
-                    Self::MN(x) => Some(x.to_sgf()),
-                    Self::W(x) => Some(x.to_sgf()),
-                    Self::AB(x) => Some(x.to_sgf()),
-                    Self::AE(x) => Some(x.to_sgf()),
-                    Self::AW(x) => Some(x.to_sgf()),
-                    Self::PL(x) => Some(x.to_sgf()),
-                    Self::C(x) => Some(x.to_sgf()),
-                    Self::DM(x) => Some(x.to_sgf()),
-                    Self::GB(x) => Some(x.to_sgf()),
-                    Self::GW(x) => Some(x.to_sgf()),
-                    Self::HO(x) => Some(x.to_sgf()),
-                    Self::N(x) => Some(x.to_sgf()),
-                    Self::UC(x) => Some(x.to_sgf()),
-                    Self::V(x) => Some(x.to_sgf()),
-                    Self::AR(x) => Some(x.to_sgf()),
-                    Self::CR(x) => Some(x.to_sgf()),
-                    Self::DO => Some("".to_string()),
-                    Self::IT => Some("".to_string()),
-                    Self::BM(x) => Some(x.to_sgf()),
-                    Self::TE(x) => Some(x.to_sgf()),
-                    Self::DD(x) => Some(x.to_sgf()),
-                    Self::LB(x) => Some(x.to_sgf()),
-                    Self::LN(x) => Some(x.to_sgf()),
-                    Self::MA(x) => Some(x.to_sgf()),
-                    Self::SL(x) => Some(x.to_sgf()),
-                    Self::SQ(x) => Some(x.to_sgf()),
-                    Self::TR(x) => Some(x.to_sgf()),
-                    Self::AP(x) => Some(x.to_sgf()),
-                    Self::CA(x) => Some(x.to_sgf()),
-                    Self::FF(x) => Some(x.to_sgf()),
-                    Self::GM(x) => Some(x.to_sgf()),
-                    Self::ST(x) => Some(x.to_sgf()),
-                    Self::SZ(x) => Some(x.to_sgf()),
-                    Self::AN(x) => Some(x.to_sgf()),
-                    Self::BR(x) => Some(x.to_sgf()),
-                    Self::BT(x) => Some(x.to_sgf()),
-                    Self::CP(x) => Some(x.to_sgf()),
-                    Self::DT(x) => Some(x.to_sgf()),
-                    Self::EV(x) => Some(x.to_sgf()),
-                    Self::GN(x) => Some(x.to_sgf()),
-                    Self::GC(x) => Some(x.to_sgf()),
-                    Self::ON(x) => Some(x.to_sgf()),
-                    Self::OT(x) => Some(x.to_sgf()),
-                    Self::PB(x) => Some(x.to_sgf()),
-                    Self::PC(x) => Some(x.to_sgf()),
-                    Self::PW(x) => Some(x.to_sgf()),
-                    Self::RE(x) => Some(x.to_sgf()),
-                    Self::RO(x) => Some(x.to_sgf()),
-                    Self::RU(x) => Some(x.to_sgf()),
-                    Self::SO(x) => Some(x.to_sgf()),
-                    Self::TM(x) => Some(x.to_sgf()),
-                    Self::US(x) => Some(x.to_sgf()),
-                    Self::WR(x) => Some(x.to_sgf()),
-                    Self::WT(x) => Some(x.to_sgf()),
-                    Self::BL(x) => Some(x.to_sgf()),
-                    Self::OB(x) => Some(x.to_sgf()),
-                    Self::OW(x) => Some(x.to_sgf()),
-                    Self::WL(x) => Some(x.to_sgf()),
-                    Self::FG(x) => Some(x.to_sgf()),
-                    Self::PM(x) => Some(x.to_sgf()),
-                    Self::VW(x) => Some(x.to_sgf()),
-                    Self::Unknown(_, x) => Some(x.to_sgf()),
-                    Self::Invalid(_, x) => Some(x.to_sgf()),
-                    #[allow(unreachable_patterns)]
-                    _ => None,
+                    $(Self::$flag => 0,)*
+                    $(Self::$ident(x) => x.heap_size(),)*
+                    Self::Unknown(identifier, values) | Self::Invalid(identifier, values) => {
+                        identifier.heap_size() + values.heap_size()
+                    }
+                }
+            }
+
+            // Returns the property's values as stored in `Unknown` and `Invalid` properties.
+            fn prop_values(&self) -> Vec<String> {
+                match self {
+                    $(Self::$flag => vec![String::new()],)*
+                    $(Self::$ident(x) => prop_raw_values!($kind, x),)*
+                    Self::Unknown(_, values) | Self::Invalid(_, values) => values.clone(),
+                }
+            }
+
+            fn serialize_prop_value(&self) -> String {
+                match self {
+                    $(Self::$flag => "".to_string(),)*
+                    $(Self::$ident(x) => x.to_sgf(),)*
+                    Self::Unknown(identifier, x) | Self::Invalid(identifier, x) => {
+                        crate::props::escape_raw_values(identifier, x)
+                    }
                 }
             }
 
             fn general_validate_properties(properties: &[Self], is_root: bool) -> Result<(), crate::InvalidNodeError> {
+                use crate::sgf_node::error_context;
                 use crate::InvalidNodeError;
                 let mut identifiers = HashSet::new();
                 let mut markup_points = HashSet::new();
+                let mut setup_points: HashSet<$pt> = HashSet::new();
                 let mut setup_node = false;
                 let mut move_node = false;
                 let mut move_seen = false;
@@ -413,115 +393,429 @@ macro_rules! sgf_prop {
                         Prop::B(_) => {
                             move_seen = true;
                             if identifiers.contains("W") {
-                                return Err(InvalidNodeError::MultipleMoves(format!(
-                                    "{:?}",
-                                    properties.to_vec()
-                                )));
+                                return Err(InvalidNodeError::MultipleMoves(error_context(properties)));
                             }
                         }
                         Prop::W(_) => {
                             move_seen = true;
                             if identifiers.contains("B") {
-                                return Err(InvalidNodeError::MultipleMoves(format!(
-                                    "{:?}",
-                                    properties.to_vec()
-                                )));
+                                return Err(InvalidNodeError::MultipleMoves(error_context(properties)));
                             }
                         }
                         Prop::CR(ps) | Prop::MA(ps) | Prop::SL(ps) | Prop::SQ(ps) | Prop::TR(ps) => {
                             for p in ps.iter() {
                                 if markup_points.contains(&p) {
-                                    return Err(InvalidNodeError::RepeatedMarkup(format!(
-                                        "{:?}",
-                                        properties.to_vec()
-                                    )));
+                                    return Err(InvalidNodeError::RepeatedMarkup(error_context(properties)));
                                 }
                                 markup_points.insert(p);
                             }
                         }
+                        Prop::AB(stones) | Prop::AW(stones) => {
+                            for stone in stones.iter() {
+                                if !setup_points.insert(<$pt>::from(stone.clone())) {
+                                    return Err(InvalidNodeError::ConflictingSetup(error_context(properties)));
+                                }
+                            }
+                        }
+                        Prop::AE(ps) => {
+                            for p in ps.iter() {
+                                if !setup_points.insert(p.clone()) {
+                                    return Err(InvalidNodeError::ConflictingSetup(error_context(properties)));
+                                }
+                            }
+                        }
+                        Prop::LB(labels) => {
+                            let mut label_points = HashSet::new();
+                            for (p, _) in labels.iter() {
+                                if !label_points.insert(p) {
+                                    return Err(InvalidNodeError::RepeatedLabel(error_context(std::slice::from_ref(prop))));
+                                }
+                            }
+                        }
+                        Prop::AR(pairs) | Prop::LN(pairs) => {
+                            let is_line = matches!(prop, Prop::LN(_));
+                            let mut seen = HashSet::new();
+                            for (start, end) in pairs.iter() {
+                                let pair = if is_line && start.to_sgf() > end.to_sgf() {
+                                    (end, start)
+                                } else {
+                                    (start, end)
+                                };
+                                if start == end || !seen.insert(pair) {
+                                    return Err(InvalidNodeError::InvalidProperty(error_context(std::slice::from_ref(prop))));
+                                }
+                            }
+                        }
                         Prop::DM(_) | Prop::UC(_) | Prop::GW(_) | Prop::GB(_) => {
                             exclusive_node_annotations += 1
                         }
                         Prop::BM(_) | Prop::DO | Prop::IT | Prop::TE(_) => move_annotation_count += 1,
-                        Prop::Invalid(identifier, values) => {
-                            return Err(InvalidNodeError::InvalidProperty(format!(
-                                "{}, {:?}",
-                                identifier, values
-                            )))
+                        Prop::Invalid(_, _) => {
+                            return Err(InvalidNodeError::InvalidProperty(error_context(std::slice::from_ref(prop))))
                         }
                         _ => {}
                     }
                     match prop.property_type() {
                         Some(PropertyType::Move) => move_node = true,
                         Some(PropertyType::Setup) => setup_node = true,
-                        Some(PropertyType::Root) => {
-                            if !is_root {
-                                return Err(InvalidNodeError::UnexpectedRootProperties(format!(
-                                            "{:?}",
-                                            properties
-                                )));
-                            }
+                        Some(PropertyType::Root) if !is_root => {
+                            return Err(InvalidNodeError::UnexpectedRootProperties(error_context(properties)));
                         }
                         _ => {}
                     }
-                    let ident = prop.identifier();
+                    let ident = prop.identifier_str();
                     if identifiers.contains(&ident) {
-                        return Err(InvalidNodeError::RepeatedIdentifier(format!(
-                                    "{:?}",
-                                    properties.to_vec()
-                        )));
+                        return Err(InvalidNodeError::RepeatedIdentifier(error_context(properties)));
                     }
-                    identifiers.insert(prop.identifier());
+                    identifiers.insert(prop.identifier_str());
                 }
                 if setup_node && move_node {
-                    return Err(InvalidNodeError::SetupAndMove(format!(
-                                "{:?}",
-                                properties.to_vec()
-                    )));
+                    return Err(InvalidNodeError::SetupAndMove(error_context(properties)));
                 }
                 if identifiers.contains("KO") && !(identifiers.contains("B") || identifiers.contains("W")) {
-                    return Err(InvalidNodeError::KoWithoutMove(format!(
-                                "{:?}",
-                                properties.to_vec()
-                    )));
+                    return Err(InvalidNodeError::KoWithoutMove(error_context(properties)));
                 }
                 if move_annotation_count > 1 {
-                    return Err(InvalidNodeError::MultipleMoveAnnotations(format!(
-                                "{:?}",
-                                properties.to_vec()
-                    )));
+                    return Err(InvalidNodeError::MultipleMoveAnnotations(error_context(properties)));
                 }
                 if move_annotation_count == 1 && !move_seen {
-                    return Err(InvalidNodeError::UnexpectedMoveAnnotation(format!(
-                                "{:?}",
-                                properties.to_vec()
-                    )));
+                    return Err(InvalidNodeError::UnexpectedMoveAnnotation(error_context(properties)));
                 }
                 if exclusive_node_annotations > 1 {
-                    return Err(InvalidNodeError::MultipleExclusiveAnnotations(format!(
-                                "{:?}",
-                                properties.to_vec()
-                    )));
+                    return Err(InvalidNodeError::MultipleExclusiveAnnotations(error_context(properties)));
                 }
                 Ok(())
             }
         }
 
-        impl Eq for $name {}
+        impl $name {
+            // Sanitizes the raw text of any Text or SimpleText values in place.
+            fn sanitize_text(&mut self, escape_html: bool) {
+                let sanitize = |text: &mut String| *text = crate::props::sanitize(text, escape_html);
+                match self {
+                    Self::C(text) | Self::GC(text) => sanitize(&mut text.text),
+                    Self::N(text)
+                    | Self::AN(text)
+                    | Self::BR(text)
+                    | Self::BT(text)
+                    | Self::CP(text)
+                    | Self::DT(text)
+                    | Self::EV(text)
+                    | Self::GN(text)
+                    | Self::ON(text)
+                    | Self::OT(text)
+                    | Self::PB(text)
+                    | Self::PC(text)
+                    | Self::PW(text)
+                    | Self::RE(text)
+                    | Self::RO(text)
+                    | Self::RU(text)
+                    | Self::SO(text)
+                    | Self::US(text)
+                    | Self::WR(text)
+                    | Self::WT(text) => sanitize(&mut text.text),
+                    Self::AP((name, version)) => {
+                        sanitize(&mut name.text);
+                        sanitize(&mut version.text);
+                    }
+                    Self::FG(Some((_, text))) => sanitize(&mut text.text),
+                    Self::LB(labels) => {
+                        *labels = labels
+                            .drain()
+                            .map(|(point, mut text)| {
+                                sanitize(&mut text.text);
+                                (point, text)
+                            })
+                            .collect();
+                    }
+                    _ => {}
+                }
+            }
+        }
 
-        fn parse_size(values: &[String]) -> Result<(u8, u8), SgfPropError> {
-            if values.len() != 1 {
-                return Err(SgfPropError {});
+        impl crate::SgfNode<$name> {
+            /// Returns the approximate number of bytes used by the node and its descendants.
+            ///
+            /// This counts the nodes themselves, their property and child lists, and memory owned
+            /// by property values like text and point lists. Allocator overhead isn't included, so
+            /// treat the result as an estimate for enforcing memory budgets rather than an exact
+            /// measurement.
+            ///
+            /// # Examples
+            /// ```
+            /// use sgf_parse::go::parse;
+            ///
+            /// let small = parse("(;B[dd])").unwrap().remove(0);
+            /// let large = parse("(;B[dd]C[A much longer comment for this move];W[pp])")
+            ///     .unwrap()
+            ///     .remove(0);
+            /// assert!(large.memory_estimate() > small.memory_estimate());
+            /// ```
+            pub fn memory_estimate(&self) -> usize {
+                let mut size = std::mem::size_of::<Self>();
+                let mut stack = vec![self];
+                while let Some(node) = stack.pop() {
+                    size += node.properties.capacity() * std::mem::size_of::<$name>();
+                    size += node.children.capacity() * std::mem::size_of::<Self>();
+                    size += node.properties.iter().map(|prop| prop.heap_size()).sum::<usize>();
+                    size += node.raw_properties_heap_size();
+                    stack.extend(node.children.iter());
+                }
+                size
+            }
+        }
+
+        impl crate::SgfNode<$name> {
+            /// Returns the node's comment from the `C` property (if present and valid).
+            ///
+            /// # Examples
+            /// ```
+            /// use sgf_parse::go::parse;
+            ///
+            /// let node = parse("(;B[dd]C[Good move])").unwrap().remove(0);
+            /// assert_eq!(node.get_comment().unwrap().text, "Good move");
+            /// ```
+            pub fn get_comment(&self) -> Option<&crate::Text> {
+                self.get_c()
+            }
+
+            /// Returns the node's node annotation properties (`N`, `DM`, `GB`, `GW`, `UC`, `HO`
+            /// and `V`).
+            ///
+            /// If the node has more than one of `DM`, `GB`, `GW` and `UC` (which is invalid), the
+            /// evaluation comes from the first of them.
+            ///
+            /// # Examples
+            /// ```
+            /// use sgf_parse::go::parse;
+            /// use sgf_parse::{Double, PositionEvaluation};
+            ///
+            /// let node = parse("(;N[Joseki]GB[2]HO[1])").unwrap().remove(0);
+            /// let annotations = node.get_node_annotations();
+            /// assert_eq!(annotations.name.unwrap().text, "Joseki");
+            /// assert_eq!(
+            ///     annotations.evaluation,
+            ///     Some(PositionEvaluation::GoodForBlack(Double::Two))
+            /// );
+            /// assert_eq!(annotations.hotspot, Some(Double::One));
+            /// assert_eq!(annotations.value, None);
+            /// ```
+            pub fn get_node_annotations(&self) -> crate::NodeAnnotations<'_> {
+                let evaluation = self.properties().find_map(|prop| match prop {
+                    Prop::DM(double) => Some(crate::PositionEvaluation::Even(*double)),
+                    Prop::GB(double) => Some(crate::PositionEvaluation::GoodForBlack(*double)),
+                    Prop::GW(double) => Some(crate::PositionEvaluation::GoodForWhite(*double)),
+                    Prop::UC(double) => Some(crate::PositionEvaluation::Unclear(*double)),
+                    _ => None,
+                });
+                crate::NodeAnnotations {
+                    name: self.get_n(),
+                    evaluation,
+                    hotspot: self.get_ho().copied(),
+                    value: self.get_v().copied(),
+                }
+            }
+
+            /// Returns the node's move annotation from `BM`, `DO`, `IT` or `TE` (if present).
+            ///
+            /// If the node has more than one of them (which is invalid), the first is returned.
+            ///
+            /// # Examples
+            /// ```
+            /// use sgf_parse::go::parse;
+            /// use sgf_parse::{Double, MoveAnnotation};
+            ///
+            /// let node = parse("(;B[dd]TE[1];W[pp]IT[])").unwrap().remove(0);
+            /// assert_eq!(node.get_move_annotation(), Some(MoveAnnotation::Tesuji(Double::One)));
+            /// let child = &node.children[0];
+            /// assert_eq!(child.get_move_annotation(), Some(MoveAnnotation::Interesting));
+            /// ```
+            pub fn get_move_annotation(&self) -> Option<crate::MoveAnnotation> {
+                self.properties().find_map(|prop| match prop {
+                    Prop::BM(double) => Some(crate::MoveAnnotation::Bad(*double)),
+                    Prop::DO => Some(crate::MoveAnnotation::Doubtful),
+                    Prop::IT => Some(crate::MoveAnnotation::Interesting),
+                    Prop::TE(double) => Some(crate::MoveAnnotation::Tesuji(*double)),
+                    _ => None,
+                })
+            }
+        }
+
+        impl crate::SgfNode<$name> {
+            /// Sanitizes all text in the tree for display from untrusted sources.
+            ///
+            /// Every Text and SimpleText value is sanitized as by
+            /// [`Text::sanitized`](`crate::Text::sanitized`), with control characters and
+            /// bidirectional formatting characters removed, and HTML special characters escaped if
+            /// `escape_html` is set. Other values are left unchanged.
+            ///
+            /// # Examples
+            /// ```
+            /// use sgf_parse::go::parse;
+            ///
+            /// let mut node = parse("(;PB[<i>Lee</i>];B[dd]C[Nice\u{7}!])").unwrap().remove(0);
+            /// node.sanitize_for_display(true);
+            /// assert_eq!(node.serialize(), "(;PB[&lt;i&gt;Lee&lt;/i&gt;];B[dd]C[Nice!])");
+            /// ```
+            pub fn sanitize_for_display(&mut self, escape_html: bool) {
+                let mut stack = vec![self];
+                while let Some(node) = stack.pop() {
+                    for prop in node.properties.iter_mut() {
+                        prop.sanitize_text(escape_html);
+                    }
+                    stack.extend(node.children.iter_mut());
+                }
+            }
+        }
+
+        impl crate::SgfNode<$name> {
+            /// Resolves points added or erased by more than one setup property in each node.
+            ///
+            /// For each node of the tree, a point in more than one of `AB`, `AW` and `AE` is only
+            /// kept in the last of those properties, and setup properties left with no points are
+            /// removed. Returns the number of points removed.
+            ///
+            /// # Examples
+            /// ```
+            /// use sgf_parse::go::parse;
+            ///
+            /// let mut node = parse("(;AB[aa][bb]AW[bb]AE[aa])").unwrap().remove(0);
+            /// assert!(node.validate().is_err());
+            /// assert_eq!(node.resolve_setup_conflicts(), 2);
+            /// assert_eq!(node.serialize(), "(;AW[bb]AE[aa])");
+            /// assert!(node.validate().is_ok());
+            /// ```
+            pub fn resolve_setup_conflicts(&mut self) -> usize {
+                let mut removed = 0;
+                let mut stack = vec![self];
+                while let Some(node) = stack.pop() {
+                    let mut seen: HashSet<$pt> = HashSet::new();
+                    for prop in node.properties.iter_mut().rev() {
+                        match prop {
+                            Prop::AB(stones) | Prop::AW(stones) => {
+                                let count = stones.len();
+                                stones.retain(|stone| seen.insert(<$pt>::from(stone.clone())));
+                                removed += count - stones.len();
+                            }
+                            Prop::AE(ps) => {
+                                let count = ps.len();
+                                ps.retain(|p| seen.insert(p.clone()));
+                                removed += count - ps.len();
+                            }
+                            _ => {}
+                        }
+                    }
+                    node.properties.retain(|prop| match prop {
+                        Prop::AB(stones) | Prop::AW(stones) => !stones.is_empty(),
+                        Prop::AE(ps) => !ps.is_empty(),
+                        _ => true,
+                    });
+                    stack.extend(node.children.iter_mut());
+                }
+
+                removed
             }
-            let value = &values[0];
-            if value.contains(':') {
-                crate::props::parse::parse_tuple(value)
-            } else {
-                let size = value.parse().map_err(|_| SgfPropError {})?;
-                Ok((size, size))
+        }
+
+        impl crate::SgfNode<$name> {
+            /// Returns any `LB` properties with labels longer than `max_length` characters.
+            ///
+            /// Renderers typically truncate long labels. Each entry has the path to the node (as
+            /// child indices from this node) and an [`InvalidNodeError::LabelTooLong`] error.
+            ///
+            /// # Examples
+            /// ```
+            /// use sgf_parse::go::parse;
+            ///
+            /// let node = parse("(;LB[aa:A];B[dd]LB[bb:Long label])").unwrap().remove(0);
+            /// let long_labels = node.long_labels(3);
+            /// assert_eq!(long_labels.len(), 1);
+            /// assert_eq!(long_labels[0].0.to_string(), "0");
+            /// ```
+            pub fn long_labels(&self, max_length: usize) -> Vec<(crate::NodePath, crate::InvalidNodeError)> {
+                let mut long_labels = vec![];
+                let mut stack = vec![(crate::NodePath::root(), self)];
+                while let Some((path, node)) = stack.pop() {
+                    for prop in node.properties.iter() {
+                        if let Prop::LB(labels) = prop {
+                            if labels.iter().any(|(_, text)| text.text.chars().count() > max_length) {
+                                long_labels.push((
+                                    path.clone(),
+                                    crate::InvalidNodeError::LabelTooLong(crate::sgf_node::error_context(
+                                        std::slice::from_ref(prop),
+                                    )),
+                                ));
+                            }
+                        }
+                    }
+                    for (i, child) in node.children.iter().enumerate().rev() {
+                        stack.push((path.child(i), child));
+                    }
+                }
+
+                long_labels
+            }
+
+            /// Removes repeated labels for the same point from all `LB` properties in the tree.
+            ///
+            /// The shortest label for each point is kept, with ties broken alphabetically.
+            /// Returns the number of labels removed.
+            ///
+            /// # Examples
+            /// ```
+            /// use sgf_parse::go::parse;
+            ///
+            /// let mut node = parse("(;LB[aa:A][aa:Bee][bb:C])").unwrap().remove(0);
+            /// assert!(node.validate().is_err());
+            /// assert_eq!(node.dedupe_labels(), 1);
+            /// assert!(node.validate().is_ok());
+            /// ```
+            pub fn dedupe_labels(&mut self) -> usize {
+                let mut removed = 0;
+                let mut stack = vec![self];
+                while let Some(node) = stack.pop() {
+                    for prop in node.properties.iter_mut() {
+                        if let Prop::LB(labels) = prop {
+                            let mut best: std::collections::HashMap<$pt, crate::SimpleText> =
+                                std::collections::HashMap::new();
+                            for (point, text) in labels.drain() {
+                                let key = |text: &crate::SimpleText| (text.text.chars().count(), text.text.clone());
+                                match best.get(&point) {
+                                    Some(current) if key(current) <= key(&text) => {}
+                                    _ => {
+                                        best.insert(point, text);
+                                    }
+                                }
+                                removed += 1;
+                            }
+                            removed -= best.len();
+                            labels.extend(best);
+                        }
+                    }
+                    stack.extend(node.children.iter_mut());
+                }
+
+                removed
             }
         }
 
+        impl crate::SgfNode<$name> {
+            $(
+                #[doc = concat!(
+                    "Returns the value of the `", stringify!($ident),
+                    "` property for the node (if present and valid)."
+                )]
+                pub fn $getter(&self) -> Option<&prop_value_type!($kind, $mv, $pt, $st, $sz)> {
+                    match self.get_property(stringify!($ident))? {
+                        $name::$ident(value) => Some(value),
+                        _ => None,
+                    }
+                }
+            )*
+        }
+
+        impl Eq for $name {}
+
         fn parse_labels(
             values: &[String],
         ) -> Result<HashSet<($pt, crate::SimpleText)>, SgfPropError> {
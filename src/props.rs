@@ -1,10 +1,17 @@
+mod annotations;
 mod error;
+mod heap_size;
 pub mod parse;
 mod sgf_prop;
 mod to_sgf;
 mod values;
 
+pub use annotations::{MoveAnnotation, NodeAnnotations, PositionEvaluation};
 pub use error::SgfPropError;
+pub(crate) use heap_size::HeapSize;
+pub(crate) use sgf_prop::Sealed;
 pub use sgf_prop::SgfProp;
 pub use to_sgf::ToSgf;
+pub(crate) use to_sgf::{escape_raw_values, list_values};
+pub(crate) use values::sanitize;
 pub use values::{Color, Double, PropertyType, SimpleText, Text};
@@ -0,0 +1,46 @@
+use super::{Double, SimpleText};
+
+/// The node annotation properties of a node, as returned by `get_node_annotations`.
+///
+/// Each field is `None` if the corresponding property is missing or invalid.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct NodeAnnotations<'a> {
+    /// The node's name from `N`.
+    pub name: Option<&'a SimpleText>,
+    /// The evaluation of the position from `DM`, `GB`, `GW`, or `UC`.
+    pub evaluation: Option<PositionEvaluation>,
+    /// Whether the node is a hotspot from `HO`.
+    pub hotspot: Option<Double>,
+    /// The estimated score from `V`.
+    pub value: Option<f64>,
+}
+
+/// An evaluation of the position at a node.
+///
+/// The SGF specification doesn't allow more than one of these properties on a node.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum PositionEvaluation {
+    /// The position is even, from `DM`.
+    Even(Double),
+    /// The position is good for black, from `GB`.
+    GoodForBlack(Double),
+    /// The position is good for white, from `GW`.
+    GoodForWhite(Double),
+    /// The position is unclear, from `UC`.
+    Unclear(Double),
+}
+
+/// An annotation of the move played at a node.
+///
+/// The SGF specification doesn't allow more than one of these properties on a node.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum MoveAnnotation {
+    /// A bad move, from `BM`.
+    Bad(Double),
+    /// A doubtful move, from `DO`.
+    Doubtful,
+    /// An interesting move, from `IT`.
+    Interesting,
+    /// A tesuji (good move), from `TE`.
+    Tesuji(Double),
+}
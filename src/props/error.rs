@@ -1,5 +1,5 @@
-// Error type for invalid SGF properties.
-#[derive(Debug)]
+/// Error type for invalid SGF properties.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct SgfPropError {}
 
 impl std::fmt::Display for SgfPropError {
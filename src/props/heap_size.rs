@@ -0,0 +1,88 @@
+use std::collections::HashSet;
+use std::mem::size_of;
+
+use super::{Color, Double, SimpleText, Text};
+
+// Approximate number of bytes a value owns on the heap.
+//
+// Used for memory estimates, so hash set overhead is only roughly accounted for.
+pub(crate) trait HeapSize {
+    fn heap_size(&self) -> usize;
+}
+
+macro_rules! no_heap {
+    ($($ty:ty),*) => {
+        $(
+            impl HeapSize for $ty {
+                fn heap_size(&self) -> usize {
+                    0
+                }
+            }
+        )*
+    };
+}
+
+no_heap!(
+    i64,
+    u8,
+    f64,
+    Color,
+    Double,
+    crate::go::Point,
+    crate::go::Stone,
+    crate::go::Move
+);
+
+impl HeapSize for String {
+    fn heap_size(&self) -> usize {
+        self.capacity()
+    }
+}
+
+impl HeapSize for SimpleText {
+    fn heap_size(&self) -> usize {
+        self.text.heap_size()
+    }
+}
+
+impl HeapSize for Text {
+    fn heap_size(&self) -> usize {
+        self.text.heap_size()
+    }
+}
+
+impl HeapSize for crate::Identifier {
+    fn heap_size(&self) -> usize {
+        if self.is_static() {
+            0
+        } else {
+            // Shared strings also store strong and weak reference counts.
+            self.len() + 2 * size_of::<usize>()
+        }
+    }
+}
+
+impl<A: HeapSize, B: HeapSize> HeapSize for (A, B) {
+    fn heap_size(&self) -> usize {
+        self.0.heap_size() + self.1.heap_size()
+    }
+}
+
+impl<T: HeapSize> HeapSize for Option<T> {
+    fn heap_size(&self) -> usize {
+        self.as_ref().map_or(0, HeapSize::heap_size)
+    }
+}
+
+impl<T: HeapSize> HeapSize for Vec<T> {
+    fn heap_size(&self) -> usize {
+        self.capacity() * size_of::<T>() + self.iter().map(HeapSize::heap_size).sum::<usize>()
+    }
+}
+
+impl<T: HeapSize> HeapSize for HashSet<T> {
+    fn heap_size(&self) -> usize {
+        // Each slot also has a control byte.
+        self.capacity() * (size_of::<T>() + 1) + self.iter().map(HeapSize::heap_size).sum::<usize>()
+    }
+}
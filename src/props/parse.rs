@@ -1,8 +1,10 @@
+use std::borrow::Cow;
 use std::collections::HashSet;
 use std::hash::Hash;
+use std::ops::RangeBounds;
 use std::str::FromStr;
 
-use super::SgfPropError;
+use super::{SgfPropError, ToSgf};
 
 pub trait FromCompressedList: Sized {
     fn from_compressed_list(
@@ -30,6 +32,12 @@ pub fn parse_elist<T: FromStr + FromCompressedList + Eq + Hash>(
     values: &[String],
 ) -> Result<HashSet<T>, SgfPropError> {
     let mut elements = HashSet::new();
+    // A single empty value is the SGF `none` value, an empty list.
+    if let [value] = values {
+        if value.is_empty() {
+            return Ok(elements);
+        }
+    }
     for value in values {
         if value.contains(':') {
             let (upper_left, lower_right): (T, T) = parse_tuple(value)?;
@@ -68,13 +76,93 @@ pub fn parse_list_composed<T: FromStr + Eq + Hash>(
     Ok(pairs)
 }
 
-pub fn split_compose(value: &str) -> Result<(&str, &str), SgfPropError> {
-    let parts: Vec<&str> = value.split(':').collect();
+// Lines are undirected, so endpoints are ordered by their serialized values to keep round trips
+// stable, and reversed duplicates are rejected.
+pub fn parse_list_lines<T: FromStr + Eq + Hash + ToSgf>(
+    values: &[String],
+) -> Result<HashSet<(T, T)>, SgfPropError> {
+    let mut lines = HashSet::new();
+    for value in values.iter() {
+        let line = normalize_line(parse_tuple(value)?);
+        if line.0 == line.1 || lines.contains(&line) {
+            return Err(SgfPropError {});
+        }
+        lines.insert(line);
+    }
+
+    Ok(lines)
+}
+
+fn normalize_line<T: ToSgf>((start, end): (T, T)) -> (T, T) {
+    if start.to_sgf() <= end.to_sgf() {
+        (start, end)
+    } else {
+        (end, start)
+    }
+}
+
+// Checks that a number parsed from a property value is in the provided range.
+pub fn check_range(value: i64, range: impl RangeBounds<i64>) -> Result<i64, SgfPropError> {
+    if range.contains(&value) {
+        Ok(value)
+    } else {
+        Err(SgfPropError {})
+    }
+}
+
+macro_rules! compose_identifiers {
+    (
+        $identifier:ident
+        flags: { $($flag:ident [$($flag_type:ident)?];)* }
+        values: {
+            $($ident:ident($getter:ident) [$($property_type:ident)?]:
+                $kind:ident = $parse:path $(, $range:expr)?;)*
+        }
+    ) => {
+        match $identifier {
+            $(stringify!($ident) => prop_has_compose_values!($kind),)*
+            _ => false,
+        }
+    };
+}
+
+// Returns whether values of the property with this identifier are compose values.
+//
+// Compose values keep their escapes until they're split so that escaped colons in either half can
+// be told apart from the separator.
+pub fn has_compose_values(identifier: &str) -> bool {
+    general_properties!(compose_identifiers! { identifier })
+        || go_properties!(compose_identifiers! { identifier })
+}
+
+// Splits a compose value on its unescaped `:` and removes the escapes from each half.
+pub fn split_compose(value: &str) -> Result<(Cow<'_, str>, Cow<'_, str>), SgfPropError> {
+    if !value.contains('\\') {
+        let parts: Vec<&str> = value.split(':').collect();
+        if parts.len() != 2 {
+            return Err(SgfPropError {});
+        }
+        return Ok((Cow::Borrowed(parts[0]), Cow::Borrowed(parts[1])));
+    }
+    let mut parts = vec![String::new()];
+    let mut escaped = false;
+    for c in value.chars() {
+        match c {
+            '\\' if !escaped => escaped = true,
+            ':' if !escaped => parts.push(String::new()),
+            c => {
+                escaped = false;
+                parts.last_mut().unwrap().push(c);
+            }
+        }
+    }
     if parts.len() != 2 {
         return Err(SgfPropError {});
     }
+    let second = parts.pop().unwrap();
+    let first = parts.pop().unwrap();
 
-    Ok((parts[0], parts[1]))
+    Ok((Cow::Owned(first), Cow::Owned(second)))
 }
 
 pub fn verify_empty(values: &[String]) -> Result<(), SgfPropError> {
@@ -86,7 +174,7 @@ pub fn verify_empty(values: &[String]) -> Result<(), SgfPropError> {
 
 #[cfg(test)]
 mod test {
-    use super::parse_list;
+    use super::{parse_elist, parse_list, parse_list_lines, split_compose};
     use crate::go::Point;
     use std::collections::HashSet;
 
@@ -122,4 +210,50 @@ mod test {
 
         assert_eq!(result, expected);
     }
+
+    #[test]
+    pub fn parse_empty_elist() {
+        let values = vec!["".to_string()];
+        assert_eq!(parse_elist::<Point>(&values).unwrap(), HashSet::new());
+        assert!(parse_list::<Point>(&values).is_err());
+    }
+
+    #[test]
+    pub fn parse_lines_normalized() {
+        let values = vec!["dd:aa".to_string(), "bb:cc".to_string()];
+        let expected: HashSet<_> = vec![
+            (Point { x: 0, y: 0 }, Point { x: 3, y: 3 }),
+            (Point { x: 1, y: 1 }, Point { x: 2, y: 2 }),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(parse_list_lines::<Point>(&values).unwrap(), expected);
+
+        let values = vec!["aa:bb".to_string(), "bb:aa".to_string()];
+        assert!(parse_list_lines::<Point>(&values).is_err());
+    }
+
+    #[test]
+    pub fn split_compose_escapes() {
+        let split = |value| {
+            split_compose(value)
+                .ok()
+                .map(|(first, second)| (first.to_string(), second.to_string()))
+        };
+        assert_eq!(split("aa:bb"), Some(("aa".to_string(), "bb".to_string())));
+        assert_eq!(
+            split("aa:with \\: colon"),
+            Some(("aa".to_string(), "with : colon".to_string()))
+        );
+        assert_eq!(
+            split("My\\: App:1\\\\2\\]"),
+            Some(("My: App".to_string(), "1\\2]".to_string()))
+        );
+        assert_eq!(
+            split("slash\\\\:colon"),
+            Some(("slash\\".to_string(), "colon".to_string()))
+        );
+        assert!(split("aa:bb:cc").is_none());
+        assert!(split("aa\\:bb").is_none());
+    }
 }
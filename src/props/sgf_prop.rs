@@ -1,6 +1,8 @@
 use std::fmt::{Debug, Display};
 
+use super::parse::has_compose_values;
 use super::{PropertyType, ToSgf};
+use crate::lexer::unescape;
 use crate::InvalidNodeError;
 
 /// A type that can be used for properties in an [`SgfNode`](`crate::SgfNode`).
@@ -13,6 +15,10 @@ pub trait SgfProp: Debug + Display + Sized + Clone + Eq + private::Sealed {
 
     /// Returns a new property parsed from the provided identifier and values
     ///
+    /// Values should be unescaped. Compose values like those of `LB` or `AP` are split on their
+    /// first `:`, so any later ones are part of the second half. Use [`SgfProp::new_escaped`] for
+    /// values with SGF escapes.
+    ///
     /// # Examples
     /// ```
     /// use sgf_parse::SgfProp;
@@ -20,12 +26,60 @@ pub trait SgfProp: Debug + Display + Sized + Clone + Eq + private::Sealed {
     ///
     /// // Prop::B(Point{ x: 2, y: 3 }
     /// let prop = Prop::new("B".to_string(), vec!["cd".to_string()]);
-    /// // Prop::AB(vec![Point{ x: 2, y: 3 }, Point { x: 3, y: 3 }])
+    /// // Prop::AB(vec![Stone(Point{ x: 2, y: 3 }), Stone(Point { x: 3, y: 3 })])
     /// let prop = Prop::new("AB".to_string(), vec!["cd".to_string(), "dd".to_string()]);
     /// // Prop::Unknown("FOO", vec!["Text"])
     /// let prop = Prop::new("FOO".to_string(), vec!["Text".to_string()]);
+    /// let prop = Prop::new("AP".to_string(), vec!["My App:1:0".to_string()]);
+    /// assert_eq!(prop, Prop::AP(("My App".into(), "1:0".into())));
     /// ```
-    fn new(identifier: String, values: Vec<String>) -> Self;
+    fn new(identifier: String, values: Vec<String>) -> Self {
+        let values = if has_compose_values(&identifier) {
+            // Escape everything but the first `:`, which separates the halves.
+            values
+                .into_iter()
+                .map(|value| {
+                    let value = value.replace('\\', "\\\\");
+                    match value.split_once(':') {
+                        Some((first, second)) => {
+                            format!("{}:{}", first, second.replace(':', "\\:"))
+                        }
+                        None => value,
+                    }
+                })
+                .collect()
+        } else {
+            values
+        };
+        Self::from_values(identifier, values)
+    }
+
+    /// Returns a new property parsed from the provided identifier and values as they appear in
+    /// SGF text, with escapes and soft line breaks.
+    ///
+    /// Unlike with [`SgfProp::new`], an escaped `\:` in either half of a compose value is kept
+    /// as part of the value rather than separating the halves.
+    ///
+    /// # Examples
+    /// ```
+    /// use sgf_parse::SgfProp;
+    /// use sgf_parse::go::Prop;
+    ///
+    /// let prop = Prop::new_escaped("AP".to_string(), vec!["My\\: App:1.0".to_string()]);
+    /// assert_eq!(prop, Prop::AP(("My: App".into(), "1.0".into())));
+    /// let prop = Prop::new_escaped("C".to_string(), vec!["[1\\]".to_string()]);
+    /// assert_eq!(prop, Prop::new("C".to_string(), vec!["[1]".to_string()]));
+    /// ```
+    fn new_escaped(identifier: String, values: Vec<String>) -> Self {
+        let keep_escapes = has_compose_values(&identifier);
+        let values = values
+            .iter()
+            .map(|value| {
+                String::from_utf8_lossy(&unescape(value.as_bytes(), keep_escapes)).into_owned()
+            })
+            .collect();
+        Self::from_values(identifier, values)
+    }
 
     /// Returns a the identifier associated with the [`SgfProp`].
     ///
@@ -41,6 +95,41 @@ pub trait SgfProp: Debug + Display + Sized + Clone + Eq + private::Sealed {
     /// ```
     fn identifier(&self) -> String;
 
+    /// Returns the identifier associated with the [`SgfProp`] as an [`Identifier`].
+    ///
+    /// Known identifiers are shared static strings, so this doesn't allocate for them.
+    ///
+    /// # Examples
+    /// ```
+    /// use sgf_parse::{Identifier, SgfProp};
+    /// use sgf_parse::go::Prop;
+    ///
+    /// let prop = Prop::new("W".to_string(), vec!["de".to_string()]);
+    /// assert_eq!(prop.shared_identifier(), Identifier::from_static("W"));
+    /// ```
+    ///
+    /// [`Identifier`]: crate::Identifier
+    fn shared_identifier(&self) -> crate::Identifier {
+        crate::Identifier::from(self.identifier_str())
+    }
+
+    /// Returns the identifier associated with the [`SgfProp`] as a string slice.
+    ///
+    /// Unlike [`SgfProp::identifier`] this never allocates, so it's the cheapest way to compare
+    /// identifiers. Known identifiers are `'static` strings.
+    ///
+    /// # Examples
+    /// ```
+    /// use sgf_parse::SgfProp;
+    /// use sgf_parse::go::Prop;
+    ///
+    /// let prop = Prop::new("W".to_string(), vec!["de".to_string()]);
+    /// assert_eq!(prop.identifier_str(), "W");
+    /// let prop = Prop::new("FOO".to_string(), vec!["de".to_string()]);
+    /// assert_eq!(prop.identifier_str(), "FOO");
+    /// ```
+    fn identifier_str(&self) -> &str;
+
     /// Returns the [`PropertyType`] associated with the property.
     ///
     /// # Examples
@@ -55,6 +144,31 @@ pub trait SgfProp: Debug + Display + Sized + Clone + Eq + private::Sealed {
     /// ```
     fn property_type(&self) -> Option<PropertyType>;
 
+    /// Returns `true` if the property isn't defined by the SGF specification for the game.
+    ///
+    /// # Examples
+    /// ```
+    /// use sgf_parse::SgfProp;
+    /// use sgf_parse::go::Prop;
+    ///
+    /// assert!(Prop::new("FOO".to_string(), vec!["de".to_string()]).is_unknown());
+    /// assert!(!Prop::new("KM".to_string(), vec!["6.5".to_string()]).is_unknown());
+    /// ```
+    fn is_unknown(&self) -> bool;
+
+    /// Returns `true` if the property is defined by the SGF specification for the game, but its
+    /// values are invalid.
+    ///
+    /// # Examples
+    /// ```
+    /// use sgf_parse::SgfProp;
+    /// use sgf_parse::go::Prop;
+    ///
+    /// assert!(Prop::new("KM".to_string(), vec!["lots".to_string()]).is_invalid());
+    /// assert!(!Prop::new("FOO".to_string(), vec!["de".to_string()]).is_invalid());
+    /// ```
+    fn is_invalid(&self) -> bool;
+
     /// Validates a set of properties.
     ///
     /// # Errors
@@ -67,9 +181,18 @@ pub trait SgfProp: Debug + Display + Sized + Clone + Eq + private::Sealed {
 // a new game outside the crate is a mess.
 //
 // If you'd like to implement this trait for a new game, PR's are very welcome!
+//
+// The sealed trait also holds the constructor used by the parser, which works on values as they're
+// stored in `Unknown` and `Invalid` properties.
 mod private {
-    pub trait Sealed {}
-    impl Sealed for crate::go::Prop {}
-    impl Sealed for crate::unknown_game::Prop {}
-    impl<'a, T> Sealed for &'a T where T: ?Sized + Sealed {}
+    pub trait Sealed {
+        // Returns a new property from unescaped values, except for compose values which keep their
+        // escapes (see `has_compose_values`).
+        fn from_values(identifier: String, values: Vec<String>) -> Self;
+
+        // Returns the property's values in the form taken by `from_values`.
+        fn raw_values(&self) -> Vec<String>;
+    }
 }
+
+pub(crate) use private::Sealed;
@@ -14,13 +14,19 @@ impl ToSgf for Vec<String> {
 
 impl<P: ToSgf> ToSgf for HashSet<P> {
     fn to_sgf(&self) -> String {
-        self.iter()
-            .map(|x| x.to_sgf())
-            .collect::<Vec<String>>()
-            .join("][")
+        list_values(self).join("][")
     }
 }
 
+// Returns the serialized values of a list in the order they're written.
+pub fn list_values<P: ToSgf>(list: &HashSet<P>) -> Vec<String> {
+    #[allow(unused_mut)]
+    let mut values = list.iter().map(|x| x.to_sgf()).collect::<Vec<String>>();
+    #[cfg(feature = "sorted-serialization")]
+    values.sort_unstable();
+    values
+}
+
 impl<A: ToSgf, B: ToSgf> ToSgf for (A, B) {
     fn to_sgf(&self) -> String {
         format!("{}:{}", self.0.to_sgf(), self.1.to_sgf())
@@ -84,6 +90,33 @@ impl ToSgf for SimpleText {
     }
 }
 
+// Escapes the values of an `Unknown` or `Invalid` property.
+//
+// Compose values already have their escapes (see `has_compose_values`), so only unescaped `]` need
+// escaping.
+pub fn escape_raw_values(identifier: &str, values: &[String]) -> String {
+    let escape = |value: &String| {
+        if !super::parse::has_compose_values(identifier) {
+            return value.replace('\\', "\\\\").replace(']', "\\]");
+        }
+        let mut escaped_value = String::with_capacity(value.len());
+        let mut escaped = false;
+        for c in value.chars() {
+            match c {
+                '\\' if !escaped => escaped = true,
+                ']' if !escaped => escaped_value.push('\\'),
+                _ => escaped = false,
+            }
+            escaped_value.push(c);
+        }
+        if escaped {
+            escaped_value.push('\\');
+        }
+        escaped_value
+    };
+    values.iter().map(escape).collect::<Vec<_>>().join("][")
+}
+
 fn escape_string(s: &str) -> String {
     s.replace('\\', "\\\\")
         .replace(']', "\\]")
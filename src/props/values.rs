@@ -3,7 +3,7 @@ use std::str::FromStr;
 use super::SgfPropError;
 
 /// An SGF [Color](https://www.red-bean.com/sgf/sgf4.html#types) value.
-#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
 pub enum Color {
     Black,
     White,
@@ -115,6 +115,63 @@ impl FromStr for Text {
     }
 }
 
+impl SimpleText {
+    /// Returns the formatted text made safe for display from untrusted sources.
+    ///
+    /// Control characters and bidirectional formatting characters are removed, and if
+    /// `escape_html` is set, HTML special characters are escaped.
+    ///
+    /// # Examples
+    /// ```
+    /// use sgf_parse::SimpleText;
+    ///
+    /// let text = SimpleText::from("<b>Bold\u{7}</b>");
+    /// assert_eq!(text.sanitized(true), "&lt;b&gt;Bold&lt;/b&gt;");
+    /// ```
+    pub fn sanitized(&self, escape_html: bool) -> String {
+        sanitize(&self.to_string(), escape_html)
+    }
+}
+
+impl Text {
+    /// Returns the formatted text made safe for display from untrusted sources.
+    ///
+    /// Control characters (other than line breaks) and bidirectional formatting characters are
+    /// removed, and if `escape_html` is set, HTML special characters are escaped.
+    ///
+    /// # Examples
+    /// ```
+    /// use sgf_parse::Text;
+    ///
+    /// let text = Text::from("Line one\n\u{202e}<script>");
+    /// assert_eq!(text.sanitized(true), "Line one\n&lt;script&gt;");
+    /// assert_eq!(text.sanitized(false), "Line one\n<script>");
+    /// ```
+    pub fn sanitized(&self, escape_html: bool) -> String {
+        sanitize(&self.to_string(), escape_html)
+    }
+}
+
+// Removes control and bidirectional formatting characters (keeping line breaks and tabs) and
+// optionally escapes HTML special characters.
+pub(crate) fn sanitize(text: &str, escape_html: bool) -> String {
+    let mut output = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\n' | '\r' | '\t' => output.push(c),
+            '\u{202a}'..='\u{202e}' | '\u{2066}'..='\u{2069}' | '\u{200e}' | '\u{200f}' => {}
+            _ if c.is_control() => {}
+            '&' if escape_html => output.push_str("&amp;"),
+            '<' if escape_html => output.push_str("&lt;"),
+            '>' if escape_html => output.push_str("&gt;"),
+            '"' if escape_html => output.push_str("&quot;"),
+            '\'' if escape_html => output.push_str("&#39;"),
+            _ => output.push(c),
+        }
+    }
+    output
+}
+
 impl std::fmt::Display for SimpleText {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let text = format_text(&self.text)
@@ -197,4 +254,14 @@ mod test {
 
         assert_eq!(format!("{}", text), expected);
     }
+
+    #[test]
+    pub fn sanitize() {
+        let text = "Tab\tand\r\nbreaks\u{0}\u{1b}[31m\u{2066}&\"'";
+        assert_eq!(super::sanitize(text, false), "Tab\tand\r\nbreaks[31m&\"'");
+        assert_eq!(
+            super::sanitize(text, true),
+            "Tab\tand\r\nbreaks[31m&amp;&quot;&#39;"
+        );
+    }
 }
@@ -1,4 +1,64 @@
-use crate::GameTree;
+use crate::parser::{charset_from_label, decode_cp1252, Charset};
+use crate::{property_spec, GameTree, GameType, SgfNode, SgfProp, ValueType};
+
+/// Options for serializing SGF files.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SerializeOptions {
+    /// Maximum line length to aim for in the output.
+    ///
+    /// Lines are broken between properties and nodes, and inside Text and SimpleText values
+    /// with [soft line breaks](https://www.red-bean.com/sgf/sgf4.html#text) which readers
+    /// remove. Other values can't be broken, so lines containing long values may still exceed
+    /// the limit. Defaults to `None` (no line breaks added).
+    pub max_line_length: Option<usize>,
+    /// Line ending to use for line breaks added while serializing.
+    ///
+    /// Line breaks inside text values are written as they are. Defaults to [`LineEnding::Lf`].
+    pub line_ending: LineEnding,
+    /// Whether to put a blank line between game trees. Defaults to `false`.
+    pub blank_line_between_games: bool,
+    /// Whether to start the output with a UTF-8 byte order mark.
+    ///
+    /// Some Windows editors expect one. Defaults to `false`.
+    pub byte_order_mark: bool,
+    /// Whether to write any root `CA` property as `CA[UTF-8]`.
+    ///
+    /// Serialized text is Unicode regardless of the charset it was parsed from, so set this when
+    /// writing the output as UTF-8. Otherwise `CA` is written as it is. Defaults to `false`.
+    ///
+    /// # Examples
+    /// ```
+    /// use sgf_parse::{parse_bytes, serialize_with_options, SerializeOptions};
+    ///
+    /// let gametrees = parse_bytes(b"(;CA[ISO-8859-1]C[Caf\xe9])").unwrap();
+    /// let options = SerializeOptions {
+    ///     declare_utf8: true,
+    ///     ..SerializeOptions::default()
+    /// };
+    /// let serialized = serialize_with_options(&gametrees, &options);
+    /// assert_eq!(serialized, "(;CA[UTF-8]C[Café])");
+    /// ```
+    pub declare_utf8: bool,
+}
+
+/// Line ending convention used in [`SerializeOptions::line_ending`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LineEnding {
+    /// Unix style line endings (`\n`).
+    #[default]
+    Lf,
+    /// Windows style line endings (`\r\n`).
+    CrLf,
+}
+
+impl LineEnding {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Lf => "\n",
+            Self::CrLf => "\r\n",
+        }
+    }
+}
 
 /// Returns the serialized SGF text from a collection of [`GameTree`] objects.
 ///
@@ -34,10 +94,331 @@ pub fn serialize<'a>(gametrees: impl IntoIterator<Item = &'a GameTree>) -> Strin
         .join("")
 }
 
+/// Returns the serialized SGF text from a collection of [`GameTree`] objects.
+///
+/// # Examples
+/// ```
+/// use sgf_parse::{parse, serialize_with_options, LineEnding, SerializeOptions};
+///
+/// let gametrees = parse("(;C[A long comment];B[de];W[fe])").unwrap();
+/// let options = SerializeOptions {
+///     max_line_length: Some(12),
+///     ..SerializeOptions::default()
+/// };
+/// let serialized = serialize_with_options(&gametrees, &options);
+/// assert_eq!(serialized, "(;C[A long \\\ncomment];\nB[de];W[fe])");
+/// assert_eq!(parse(&serialized).unwrap(), gametrees);
+///
+/// let gametrees = parse("(;B[de])(;B[dd])").unwrap();
+/// let options = SerializeOptions {
+///     line_ending: LineEnding::CrLf,
+///     blank_line_between_games: true,
+///     byte_order_mark: true,
+///     ..SerializeOptions::default()
+/// };
+/// let serialized = serialize_with_options(&gametrees, &options);
+/// assert_eq!(serialized, "\u{feff}(;B[de])\r\n\r\n(;B[dd])");
+/// ```
+pub fn serialize_with_options<'a>(
+    gametrees: impl IntoIterator<Item = &'a GameTree>,
+    options: &SerializeOptions,
+) -> String {
+    let newline = options.line_ending.as_str();
+    let mut output = String::new();
+    if options.byte_order_mark {
+        output.push('\u{feff}');
+    }
+    for (i, gametree) in gametrees.into_iter().enumerate() {
+        if i > 0 && options.blank_line_between_games {
+            output.push_str(newline);
+            output.push_str(newline);
+        }
+        let text = match gametree {
+            GameTree::GoGame(node) if options.declare_utf8 => declaring_utf8(node),
+            GameTree::Unknown(node) if options.declare_utf8 => declaring_utf8(node),
+            _ => gametree.to_string(),
+        };
+        match options.max_line_length {
+            Some(max_line_length) => output.push_str(&wrap_lines(&text, max_line_length, newline)),
+            None => output.push_str(&text),
+        }
+    }
+    output
+}
+
+// Adds line breaks to serialized SGF text to keep lines under `max_line_length` where possible.
+fn wrap_lines(text: &str, max_line_length: usize, newline: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut output = String::with_capacity(text.len());
+    let mut line_length = 0;
+    let mut identifier = String::new();
+    let mut in_value = false;
+    let mut escaped = false;
+    for (i, &c) in chars.iter().enumerate() {
+        if !in_value {
+            let in_identifier =
+                c.is_ascii_alphabetic() && i > 0 && chars[i - 1].is_ascii_alphabetic();
+            if !in_identifier {
+                if c.is_ascii_alphabetic() {
+                    identifier = chars[i..]
+                        .iter()
+                        .take_while(|c| c.is_ascii_alphabetic())
+                        .collect();
+                }
+                let length = token_length(&chars[i..], is_text_property(&identifier));
+                if line_length > 0 && line_length + length > max_line_length {
+                    output.push_str(newline);
+                    line_length = 0;
+                }
+            }
+            in_value = c == '[';
+        } else if escaped {
+            escaped = false;
+        } else if c == ']' {
+            in_value = false;
+        } else {
+            // Leave room for the character, the soft break, and the escaped character if any.
+            let needed = if c == '\\' { 3 } else { 2 };
+            if is_text_property(&identifier)
+                && line_length > 0
+                && line_length + needed > max_line_length
+            {
+                output.push('\\');
+                output.push_str(newline);
+                line_length = 0;
+            }
+            escaped = c == '\\';
+        }
+        output.push(c);
+        line_length = if c == '\n' { 0 } else { line_length + 1 };
+    }
+    output
+}
+
+fn is_text_property(identifier: &str) -> bool {
+    matches!(
+        property_spec(GameType::Unknown, identifier).map(|spec| spec.value_type),
+        Some(ValueType::Text | ValueType::SimpleText)
+    )
+}
+
+// Returns the length of the text starting at a token which can't be broken by a line break.
+//
+// Values can't be broken except in text properties, where the first character after `[` is kept
+// with it.
+fn token_length(chars: &[char], is_text: bool) -> usize {
+    let value_start = match chars.iter().position(|&c| c == '[') {
+        Some(i) if chars[..i].iter().all(|c| c.is_ascii_alphabetic()) => i,
+        _ => return 1,
+    };
+    if is_text {
+        return value_start + 2;
+    }
+    let mut escaped = false;
+    for (i, &c) in chars.iter().enumerate().skip(value_start + 1) {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            ']' => return i + 1,
+            _ => {}
+        }
+    }
+    chars.len()
+}
+
+/// Writes the serialized SGF text from a collection of [`GameTree`] objects to a writer.
+///
+/// Output is streamed to the writer without building the full text first, which saves memory
+/// on large collections. Consider wrapping unbuffered writers in a
+/// [`BufWriter`](`std::io::BufWriter`).
+///
+/// # Errors
+/// Returns an error if writing fails.
+///
+/// # Examples
+/// ```
+/// use sgf_parse::{parse, serialize_to};
+///
+/// let sgf = "(;SZ[9:9];B[de])(;C[A comment])";
+/// let gametrees = parse(sgf).unwrap();
+/// let mut output = vec![];
+/// serialize_to(&gametrees, &mut output).unwrap();
+/// assert_eq!(output, sgf.as_bytes());
+/// ```
+pub fn serialize_to<'a, W: std::io::Write>(
+    gametrees: impl IntoIterator<Item = &'a GameTree>,
+    writer: &mut W,
+) -> std::io::Result<()> {
+    let mut adapter = IoAdapter {
+        writer,
+        error: None,
+    };
+    for gametree in gametrees {
+        let result = match gametree {
+            GameTree::GoGame(node) => node.write_sgf(&mut adapter),
+            GameTree::Unknown(node) => node.write_sgf(&mut adapter),
+            GameTree::Custom(node) => std::fmt::Write::write_str(&mut adapter, &node.serialize()),
+        };
+        if result.is_err() {
+            return Err(adapter
+                .error
+                .take()
+                .unwrap_or_else(|| std::io::Error::other("formatter error")));
+        }
+    }
+    Ok(())
+}
+
+/// Saves a collection of [`GameTree`] objects to a file.
+///
+/// The text is written to a temporary file in the same directory which is then renamed over
+/// `path`, so the file is never left partially written. Each game tree is encoded in the charset
+/// declared by its root `CA` property. ISO-8859-1 is always supported, and other charsets are
+/// supported with the `encoding` feature. UTF-8 is used when there's no `CA` property, or if the
+/// charset isn't supported, in which case `CA` is written as `CA[UTF-8]` as with
+/// [`SerializeOptions::declare_utf8`].
+///
+/// # Errors
+/// Returns an error if the text can't be represented in a game tree's charset, or if writing the
+/// file fails. The file at `path` is unchanged in either case.
+///
+/// # Examples
+/// ```
+/// use sgf_parse::{parse, parse_bytes, save_to};
+///
+/// let gametrees = parse("(;CA[ISO-8859-1]C[Café])(;C[Café])").unwrap();
+/// let path = std::env::temp_dir().join("sgf_parse_save_to_example.sgf");
+/// save_to(&gametrees, &path).unwrap();
+/// let data = std::fs::read(&path).unwrap();
+/// assert_eq!(data, b"(;CA[ISO-8859-1]C[Caf\xe9])(;C[Caf\xc3\xa9])");
+/// assert_eq!(parse_bytes(&data).unwrap(), gametrees);
+/// # std::fs::remove_file(&path).unwrap();
+/// ```
+pub fn save_to<'a>(
+    gametrees: impl IntoIterator<Item = &'a GameTree>,
+    path: impl AsRef<std::path::Path>,
+) -> std::io::Result<()> {
+    let mut data = vec![];
+    for gametree in gametrees {
+        data.extend(encode_gametree(gametree)?);
+    }
+    write_atomically(path.as_ref(), &data)
+}
+
+// Serialize a gametree and encode it in the charset declared by its `CA` property.
+fn encode_gametree(gametree: &GameTree) -> std::io::Result<Vec<u8>> {
+    let label = match gametree {
+        GameTree::GoGame(node) => charset_label(node),
+        GameTree::Unknown(node) => charset_label(node),
+        GameTree::Custom(node) => return Ok(node.serialize().into_bytes()),
+    };
+    let unencodable = || {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!(
+                "text can't be encoded as {}",
+                label.as_deref().unwrap_or("")
+            ),
+        )
+    };
+    match label.as_deref().map(charset_from_label) {
+        Some(Charset::Latin1) => gametree
+            .to_string()
+            .chars()
+            .map(encode_cp1252)
+            .collect::<Option<_>>()
+            .ok_or_else(unencodable),
+        #[cfg(feature = "encoding")]
+        Some(Charset::Encoding(encoding)) if encoding.output_encoding() == encoding => {
+            let text = gametree.to_string();
+            let (bytes, _, had_errors) = encoding.encode(&text);
+            if had_errors {
+                Err(unencodable())
+            } else {
+                Ok(bytes.into_owned())
+            }
+        }
+        _ => Ok(match gametree {
+            GameTree::GoGame(node) => declaring_utf8(node),
+            GameTree::Unknown(node) => declaring_utf8(node),
+            GameTree::Custom(node) => node.serialize(),
+        }
+        .into_bytes()),
+    }
+}
+
+fn charset_label<Prop: SgfProp>(node: &SgfNode<Prop>) -> Option<String> {
+    let prop = node.get_property("CA")?;
+    prop.raw_values().into_iter().next()
+}
+
+fn declaring_utf8<Prop: SgfProp>(node: &SgfNode<Prop>) -> String {
+    let mut text = String::new();
+    // Writing to a `String` can't fail.
+    node.write_sgf_declaring_utf8(&mut text).unwrap();
+    text
+}
+
+// Encode a character as Windows-1252, which ISO-8859-1 is treated as when parsing.
+fn encode_cp1252(c: char) -> Option<u8> {
+    (0x00..=0xFF).find(|&byte| decode_cp1252(byte) == c)
+}
+
+// Write data to a temporary file next to `path` and rename it into place.
+fn write_atomically(path: &std::path::Path, data: &[u8]) -> std::io::Result<()> {
+    use std::io::Write;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    static TEMP_FILE_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+    let file_name = path.file_name().ok_or_else(|| {
+        std::io::Error::new(std::io::ErrorKind::InvalidInput, "path has no file name")
+    })?;
+    let mut temp_name = std::ffi::OsString::from(".");
+    temp_name.push(file_name);
+    temp_name.push(format!(
+        ".{}.{}.tmp",
+        std::process::id(),
+        TEMP_FILE_COUNT.fetch_add(1, Ordering::Relaxed)
+    ));
+    let temp_path = path.with_file_name(temp_name);
+    let result = std::fs::File::create(&temp_path)
+        .and_then(|mut file| {
+            file.write_all(data)?;
+            file.sync_all()
+        })
+        .and_then(|_| std::fs::rename(&temp_path, path));
+    if result.is_err() {
+        let _ = std::fs::remove_file(&temp_path);
+    }
+    result
+}
+
+// Adapts an `io::Write` for use with `fmt::Write` methods, keeping the underlying error.
+struct IoAdapter<'a, W: std::io::Write> {
+    writer: &'a mut W,
+    error: Option<std::io::Error>,
+}
+
+impl<W: std::io::Write> std::fmt::Write for IoAdapter<'_, W> {
+    fn write_str(&mut self, s: &str) -> std::fmt::Result {
+        self.writer.write_all(s.as_bytes()).map_err(|e| {
+            self.error = Some(e);
+            std::fmt::Error
+        })
+    }
+}
+
 #[cfg(test)]
 mod test {
-    use super::serialize;
-    use crate::parse;
+    use super::{
+        save_to, serialize, serialize_to, serialize_with_options, LineEnding, SerializeOptions,
+    };
+    use crate::{parse, parse_bytes};
+
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("sgf_parse_{}_{}.sgf", name, std::process::id()))
+    }
 
     #[test]
     fn simple_sgf() {
@@ -46,4 +427,87 @@ mod test {
         let result = serialize(&game_trees);
         assert_eq!(result, sgf);
     }
+
+    #[cfg(feature = "sorted-serialization")]
+    #[test]
+    fn sorted_list_values() {
+        let game_trees = parse("(;AB[dd][aa][cc][bb]LB[dd:x][aa:y]AR[bb:aa][aa:bb])").unwrap();
+        assert_eq!(
+            serialize(&game_trees),
+            "(;AB[aa][bb][cc][dd]LB[aa:y][dd:x]AR[aa:bb][bb:aa])"
+        );
+    }
+
+    #[test]
+    fn serialize_to_matches_serialize() {
+        let sgf = "(;C[Some comment];B[de](;W[fe](;B[aa])(;B[bb]))(;W[ff]))(;B[de];W[ff])";
+        let game_trees = parse(sgf).unwrap();
+        let mut output = vec![];
+        serialize_to(&game_trees, &mut output).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), serialize(&game_trees));
+    }
+
+    #[test]
+    fn wrapped_lines_round_trip() {
+        let sgf = "(;GC[Game comment with an escaped \\] bracket and\nlines]AB[aa][bb][cc]\
+                   LB[aa:A long label](;B[de]N[Name])(;W[ee]C[Text]))";
+        let game_trees = parse(sgf).unwrap();
+        for max_line_length in 1..20 {
+            for line_ending in [LineEnding::Lf, LineEnding::CrLf] {
+                let options = SerializeOptions {
+                    max_line_length: Some(max_line_length),
+                    line_ending,
+                    blank_line_between_games: true,
+                    byte_order_mark: true,
+                    declare_utf8: false,
+                };
+                let wrapped = serialize_with_options(&game_trees, &options);
+                assert_eq!(parse(&wrapped).unwrap(), game_trees, "{}", wrapped);
+            }
+        }
+    }
+
+    #[test]
+    fn save_unencodable_text() {
+        let path = temp_path("save_unencodable_text");
+        let gametrees = parse("(;CA[latin1]C[Café])").unwrap();
+        save_to(&gametrees, &path).unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), b"(;CA[latin1]C[Caf\xe9])");
+
+        let unencodable = parse("(;CA[latin1]C[\u{68cb}])").unwrap();
+        let error = save_to(&unencodable, &path).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+        assert_eq!(
+            parse_bytes(&std::fs::read(&path).unwrap()).unwrap(),
+            gametrees
+        );
+
+        let unsupported = parse("(;CA[EBCDIC-ish]C[\u{68cb}])").unwrap();
+        save_to(&unsupported, &path).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "(;CA[UTF-8]C[\u{68cb}])"
+        );
+        std::fs::remove_file(&path).unwrap();
+        let leftovers = std::fs::read_dir(std::env::temp_dir())
+            .unwrap()
+            .filter(|entry| {
+                let name = entry.as_ref().unwrap().file_name();
+                name.to_string_lossy().contains("save_unencodable_text")
+            })
+            .count();
+        assert_eq!(leftovers, 0);
+    }
+
+    #[cfg(feature = "encoding")]
+    #[test]
+    fn save_with_encoding() {
+        let path = temp_path("save_with_encoding");
+        let gametrees = parse_bytes(b"(;CA[Shift_JIS]C[\x82\xa0])").unwrap();
+        save_to(&gametrees, &path).unwrap();
+        let data = std::fs::read(&path).unwrap();
+        assert_eq!(data, b"(;CA[Shift_JIS]C[\x82\xa0])");
+        assert_eq!(parse_bytes(&data).unwrap(), gametrees);
+        std::fs::remove_file(&path).unwrap();
+    }
 }
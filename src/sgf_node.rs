@@ -1,4 +1,5 @@
 use crate::props::{PropertyType, SgfProp};
+use crate::{NodePath, TraversalPath};
 
 /// A node in an SGF Game Tree.
 ///
@@ -6,11 +7,149 @@ use crate::props::{PropertyType, SgfProp};
 /// All game-specific information is encoded in the `Prop` type. Use
 /// [`go::Prop`](`crate::go::Prop`) for go games, and
 /// [`unknown_game::Prop`](`crate::unknown_game::Prop`) for all other games.
-#[derive(Clone, Debug, PartialEq)]
+///
+/// Whether a node is a root node is tracked internally. Nodes returned by the parser are marked as
+/// roots, and nodes added with [`SgfNode::add_child`] are unmarked automatically. Validation only
+/// ever treats the node it's called on as a potential root, so a root node moved under another
+/// node won't accept root properties.
+///
+/// Cloning, comparing, debug formatting and dropping nodes don't recurse, so even very deep trees
+/// can be handled safely.
 pub struct SgfNode<Prop: SgfProp> {
     pub properties: Vec<Prop>,
     pub children: Vec<Self>,
-    pub is_root: bool,
+    pub(crate) is_root: bool,
+    // Serialized properties paired with their original text, for properties parsed with
+    // `ParseOptions::keep_raw_unknown_game_values` which wouldn't otherwise round trip.
+    raw_properties: Vec<(String, String)>,
+}
+
+impl<Prop: SgfProp> Clone for SgfNode<Prop> {
+    fn clone(&self) -> Self {
+        // Nodes in depth-first order along with their parent's index.
+        let mut nodes: Vec<(&Self, usize)> = vec![];
+        let mut stack = vec![(self, usize::MAX)];
+        while let Some((node, parent)) = stack.pop() {
+            let index = nodes.len();
+            nodes.push((node, parent));
+            for child in node.children.iter().rev() {
+                stack.push((child, index));
+            }
+        }
+
+        // Build copies from the leaves up. Walking backwards visits each parent's children last
+        // to first, so they're reversed once complete.
+        let mut children: Vec<Vec<Self>> = (0..nodes.len()).map(|_| vec![]).collect();
+        let mut root = None;
+        for (index, (node, parent)) in nodes.iter().enumerate().rev() {
+            let mut node_children = std::mem::take(&mut children[index]);
+            node_children.reverse();
+            let copy = Self {
+                properties: node.properties.clone(),
+                children: node_children,
+                is_root: node.is_root,
+                raw_properties: node.raw_properties.clone(),
+            };
+            match children.get_mut(*parent) {
+                Some(siblings) => siblings.push(copy),
+                None => root = Some(copy),
+            }
+        }
+        root.unwrap()
+    }
+}
+
+impl<Prop: SgfProp> Drop for SgfNode<Prop> {
+    fn drop(&mut self) {
+        // Dropping nested children recursively can overflow the stack, so move all descendants
+        // into a flat list first.
+        let mut nodes = std::mem::take(&mut self.children);
+        while let Some(mut node) = nodes.pop() {
+            nodes.append(&mut node.children);
+        }
+    }
+}
+
+impl<Prop: SgfProp> PartialEq for SgfNode<Prop> {
+    fn eq(&self, other: &Self) -> bool {
+        let mut stack = vec![(self, other)];
+        while let Some((node, other)) = stack.pop() {
+            if node.is_root != other.is_root
+                || node.properties != other.properties
+                || node.children.len() != other.children.len()
+            {
+                return false;
+            }
+            stack.extend(node.children.iter().zip(&other.children));
+        }
+        true
+    }
+}
+
+impl<Prop: SgfProp> std::fmt::Debug for SgfNode<Prop> {
+    // Written out by hand in the same shape as the derived output, to avoid recursion.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        enum Step<'a, Prop: SgfProp> {
+            Open(&'a SgfNode<Prop>, usize, bool),
+            Close(&'a SgfNode<Prop>, usize),
+        }
+
+        let pretty = f.alternate();
+        let indent = |depth: usize| " ".repeat(8 * depth);
+        let mut stack = vec![Step::Open(self, 0, true)];
+        while let Some(step) = stack.pop() {
+            match step {
+                Step::Open(node, depth, first) => {
+                    if pretty {
+                        if depth > 0 {
+                            write!(f, "\n{}", indent(depth))?;
+                        }
+                        write!(f, "SgfNode {{\n{}    properties: [", indent(depth))?;
+                        for prop in &node.properties {
+                            write!(f, "\n{}        {:?},", indent(depth), prop)?;
+                        }
+                        if !node.properties.is_empty() {
+                            write!(f, "\n{}    ", indent(depth))?;
+                        }
+                        write!(f, "],\n{}    children: [", indent(depth))?;
+                    } else {
+                        if !first {
+                            f.write_str(", ")?;
+                        }
+                        write!(
+                            f,
+                            "SgfNode {{ properties: {:?}, children: [",
+                            node.properties
+                        )?;
+                    }
+                    stack.push(Step::Close(node, depth));
+                    for (i, child) in node.children.iter().enumerate().rev() {
+                        stack.push(Step::Open(child, depth + 1, i == 0));
+                    }
+                }
+                Step::Close(node, depth) => {
+                    if pretty {
+                        if !node.children.is_empty() {
+                            write!(f, "\n{}    ", indent(depth))?;
+                        }
+                        write!(
+                            f,
+                            "],\n{}    is_root: {:?},\n{}}}",
+                            indent(depth),
+                            node.is_root,
+                            indent(depth)
+                        )?;
+                        if depth > 0 {
+                            f.write_str(",")?;
+                        }
+                    } else {
+                        write!(f, "], is_root: {:?} }}", node.is_root)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
 }
 
 impl<Prop: SgfProp> Default for SgfNode<Prop> {
@@ -19,6 +158,7 @@ impl<Prop: SgfProp> Default for SgfNode<Prop> {
             properties: vec![],
             children: vec![],
             is_root: false,
+            raw_properties: vec![],
         }
     }
 }
@@ -45,11 +185,295 @@ impl<Prop: SgfProp> SgfNode<Prop> {
             properties,
             children,
             is_root,
+            raw_properties: vec![],
+        }
+    }
+
+    /// Returns whether this node is the root node of a game tree.
+    ///
+    /// # Examples
+    /// ```
+    /// use sgf_parse::go::parse;
+    ///
+    /// let node = parse("(;SZ[13:13];B[de])").unwrap().into_iter().next().unwrap();
+    /// assert!(node.is_root());
+    /// assert!(!node.children().next().unwrap().is_root());
+    /// ```
+    pub fn is_root(&self) -> bool {
+        self.is_root
+    }
+
+    /// Marks or unmarks this node as the root node of a game tree.
+    ///
+    /// # Examples
+    /// ```
+    /// use sgf_parse::SgfNode;
+    /// use sgf_parse::go::Prop;
+    ///
+    /// let mut node = SgfNode::<Prop>::default();
+    /// node.set_root(true);
+    /// assert!(node.is_root());
+    /// ```
+    pub fn set_root(&mut self, is_root: bool) {
+        self.is_root = is_root;
+    }
+
+    /// Adds a child to the end of this node's children and returns a reference to it.
+    ///
+    /// The child is unmarked as a root node, so a game tree can safely be grafted as a variation.
+    ///
+    /// # Examples
+    /// ```
+    /// use sgf_parse::go::parse;
+    ///
+    /// let mut node = parse("(;SZ[13:13];B[de])").unwrap().into_iter().next().unwrap();
+    /// let other = parse("(;SZ[13:13];B[dd])").unwrap().into_iter().next().unwrap();
+    /// let child = node.add_child(other);
+    /// assert!(!child.is_root());
+    /// assert!(node.validate().is_err());
+    /// ```
+    pub fn add_child(&mut self, mut child: Self) -> &mut Self {
+        child.is_root = false;
+        self.children.push(child);
+        self.children.last_mut().unwrap()
+    }
+
+    /// Adds a child like [`SgfNode::add_child`] if the child's subtree is valid as a variation.
+    ///
+    /// # Errors
+    /// Returns an error without changing the tree if [`SgfNode::validate`] fails for the child as
+    /// a non-root node.
+    ///
+    /// # Examples
+    /// ```
+    /// use sgf_parse::go::parse;
+    ///
+    /// let mut node = parse("(;SZ[9];B[ee])").unwrap().remove(0);
+    /// let variation = parse("(;B[cc];W[gg])").unwrap().remove(0);
+    /// assert!(node.try_add_child(variation).is_ok());
+    /// let other_game = parse("(;SZ[9];B[dd])").unwrap().remove(0);
+    /// assert!(node.try_add_child(other_game).is_err());
+    /// assert_eq!(node.children.len(), 2);
+    /// ```
+    pub fn try_add_child(&mut self, child: Self) -> Result<&mut Self, InvalidNodeError> {
+        child.validate_helper(false)?;
+        Ok(self.add_child(child))
+    }
+
+    /// Removes the child with the provided index and returns it, or `None` if there's no such
+    /// child.
+    ///
+    /// Later children shift down to fill the gap.
+    ///
+    /// # Examples
+    /// ```
+    /// use sgf_parse::go::parse;
+    ///
+    /// let mut node = parse("(;SZ[9](;B[ee])(;B[cc])(;B[gg]))").unwrap().remove(0);
+    /// let removed = node.remove_child(1).unwrap();
+    /// assert_eq!(removed.serialize(), "(;B[cc])");
+    /// assert!(node.remove_child(2).is_none());
+    /// assert_eq!(node.serialize(), "(;SZ[9:9](;B[ee])(;B[gg]))");
+    /// ```
+    pub fn remove_child(&mut self, index: usize) -> Option<Self> {
+        if index < self.children.len() {
+            Some(self.children.remove(index))
+        } else {
+            None
+        }
+    }
+
+    /// Moves the child with the provided index one place earlier among its siblings.
+    ///
+    /// Returns `false` without changing anything if there's no such child or it's already the
+    /// first child.
+    ///
+    /// # Examples
+    /// ```
+    /// use sgf_parse::go::parse;
+    ///
+    /// let mut node = parse("(;GM[1](;B[ee])(;B[cc])(;B[gg]))").unwrap().remove(0);
+    /// assert!(node.promote_child(2));
+    /// assert_eq!(node.serialize(), "(;GM[1](;B[ee])(;B[gg])(;B[cc]))");
+    /// assert!(!node.promote_child(0));
+    /// ```
+    pub fn promote_child(&mut self, index: usize) -> bool {
+        if index == 0 || index >= self.children.len() {
+            return false;
+        }
+        self.children.swap(index - 1, index);
+        true
+    }
+
+    /// Moves the child with the provided index one place later among its siblings.
+    ///
+    /// Returns `false` without changing anything if there's no such child or it's already the
+    /// last child.
+    ///
+    /// # Examples
+    /// ```
+    /// use sgf_parse::go::parse;
+    ///
+    /// let mut node = parse("(;GM[1](;B[ee])(;B[cc])(;B[gg]))").unwrap().remove(0);
+    /// assert!(node.demote_child(0));
+    /// assert_eq!(node.serialize(), "(;GM[1](;B[cc])(;B[ee])(;B[gg]))");
+    /// assert!(!node.demote_child(2));
+    /// ```
+    pub fn demote_child(&mut self, index: usize) -> bool {
+        if index + 1 >= self.children.len() {
+            return false;
+        }
+        self.children.swap(index, index + 1);
+        true
+    }
+
+    /// Reorders children so the node at `path` is on the main variation.
+    ///
+    /// Each child along the path is moved to the front of its siblings, keeping the order of the
+    /// other siblings. Only the order of children changes, so properties like `ST` which describe
+    /// how variations are shown still apply. Returns `false` without changing anything if there's
+    /// no node at the path.
+    ///
+    /// # Examples
+    /// ```
+    /// use sgf_parse::go::parse;
+    ///
+    /// let mut node = parse("(;GM[1](;B[ee];W[cc])(;B[gg](;W[dd])(;W[ff])))").unwrap().remove(0);
+    /// assert!(node.set_main_variation(&"1.1".parse().unwrap()));
+    /// assert_eq!(node.serialize(), "(;GM[1](;B[gg](;W[ff])(;W[dd]))(;B[ee];W[cc]))");
+    /// assert!(!node.set_main_variation(&"0.2".parse().unwrap()));
+    /// ```
+    pub fn set_main_variation(&mut self, path: &NodePath) -> bool {
+        if self.node_at(path).is_none() {
+            return false;
+        }
+        let mut node = self;
+        for &index in path.iter() {
+            let child = node.children.remove(index);
+            node.children.insert(0, child);
+            node = &mut node.children[0];
+        }
+        true
+    }
+
+    /// Keeps only the children for which the predicate returns `true`, preserving their order.
+    ///
+    /// # Examples
+    /// ```
+    /// use sgf_parse::go::parse;
+    ///
+    /// let mut node = parse("(;GM[1](;B[ee];W[cc])(;B[gg]C[bad])(;B[dd]))").unwrap().remove(0);
+    /// node.retain_children(|child| child.get_property("C").is_none());
+    /// assert_eq!(node.serialize(), "(;GM[1](;B[ee];W[cc])(;B[dd]))");
+    /// ```
+    pub fn retain_children<F>(&mut self, predicate: F)
+    where
+        F: FnMut(&Self) -> bool,
+    {
+        self.children.retain(predicate);
+    }
+
+    /// Removes every variation except the main one, leaving only the nodes returned by
+    /// [`SgfNode::main_variation`].
+    ///
+    /// # Examples
+    /// ```
+    /// use sgf_parse::go::parse;
+    ///
+    /// let mut node = parse("(;GM[1](;B[ee](;W[cc])(;W[dd]))(;B[gg]))").unwrap().remove(0);
+    /// node.prune_to_main_variation();
+    /// assert_eq!(node.serialize(), "(;GM[1];B[ee];W[cc])");
+    /// ```
+    pub fn prune_to_main_variation(&mut self) {
+        let mut node = self;
+        loop {
+            node.children.truncate(1);
+            match node.children.first_mut() {
+                Some(child) => node = child,
+                None => return,
+            }
+        }
+    }
+
+    /// Inserts a new node with the provided properties between this node and its parent.
+    ///
+    /// The new node takes this node's place in the tree (including its root status), and this node
+    /// becomes its only child.
+    ///
+    /// # Errors
+    /// Returns an error without changing the tree if the properties aren't valid for the new node,
+    /// or if this node is a root node with properties (like `SZ`) which are only valid at the root.
+    ///
+    /// # Examples
+    /// ```
+    /// use sgf_parse::go::{parse, Prop};
+    /// use sgf_parse::SgfProp;
+    ///
+    /// let mut node = parse("(;GM[1]SZ[9];B[ee];W[cc])").unwrap().remove(0);
+    /// let comment = Prop::new("C".to_string(), vec!["Before the move".to_string()]);
+    /// node.children[0].insert_parent(vec![comment]).unwrap();
+    /// assert_eq!(node.serialize(), "(;GM[1]SZ[9:9];C[Before the move];B[ee];W[cc])");
+    ///
+    /// let root_property = Prop::new("FF".to_string(), vec!["4".to_string()]);
+    /// assert!(node.children[0].insert_parent(vec![root_property]).is_err());
+    /// ```
+    pub fn insert_parent(&mut self, properties: Vec<Prop>) -> Result<(), InvalidNodeError> {
+        Prop::validate_properties(&properties, self.is_root)?;
+        if self.is_root {
+            Prop::validate_properties(&self.properties, false)?;
         }
+        let parent = Self::new(properties, vec![], self.is_root);
+        let mut node = std::mem::replace(self, parent);
+        node.is_root = false;
+        self.children.push(node);
+        Ok(())
+    }
+
+    /// Splits this node in two at the provided property index and returns the new node.
+    ///
+    /// Properties from `index` onwards move to a new node which takes over this node's children
+    /// and becomes its only child. This is useful for separating setup properties from a move.
+    ///
+    /// # Errors
+    /// Returns an error without changing the tree if either node's properties would be invalid,
+    /// for instance if root properties would move to the new node.
+    ///
+    /// # Panics
+    /// Panics if `index` is greater than the number of properties.
+    ///
+    /// # Examples
+    /// ```
+    /// use sgf_parse::go::parse;
+    ///
+    /// let mut node = parse("(;SZ[9];AB[cc]B[ee]C[Hmm];W[gg])").unwrap().remove(0);
+    /// assert!(node.validate().is_err());
+    /// let setup = &mut node.children[0];
+    /// let new_node = setup.split_at(1).unwrap();
+    /// assert_eq!(new_node.serialize(), "(;B[ee]C[Hmm];W[gg])");
+    /// assert!(node.validate().is_ok());
+    /// assert!(node.split_at(0).is_err());
+    /// ```
+    pub fn split_at(&mut self, index: usize) -> Result<&mut Self, InvalidNodeError> {
+        assert!(
+            index <= self.properties.len(),
+            "split index {} out of range for {} properties",
+            index,
+            self.properties.len()
+        );
+        Prop::validate_properties(&self.properties[..index], self.is_root)?;
+        Prop::validate_properties(&self.properties[index..], false)?;
+        let properties = self.properties.split_off(index);
+        let children = std::mem::take(&mut self.children);
+        self.children.push(Self::new(properties, children, false));
+        Ok(&mut self.children[0])
     }
 
     /// Returns the property with the provided identifier for the node (if present).
     ///
+    /// Nodes for [`go::Prop`](crate::go::Prop) and [`unknown_game::Prop`](crate::unknown_game::Prop)
+    /// also have a typed getter for each property with a value, like `get_sz`, which returns the
+    /// property's value directly.
+    ///
     /// # Examples
     /// ```
     /// use sgf_parse::go::{parse, Prop};
@@ -60,11 +484,141 @@ impl<Prop: SgfProp> SgfNode<Prop> {
     ///     None => (19, 19),
     ///     _ => unreachable!(),
     /// };
+    /// assert_eq!(node.get_sz(), Some(&board_size));
     /// ```
     pub fn get_property(&self, identifier: &str) -> Option<&Prop> {
         self.properties
             .iter()
-            .find(|&prop| prop.identifier() == identifier)
+            .find(|&prop| prop.identifier_str() == identifier)
+    }
+
+    /// Replaces the property with the same identifier as `prop` in place, returning the old
+    /// property.
+    ///
+    /// Unlike removing the old property and pushing the new one, the property keeps its position so
+    /// serialized output stays in the same order. If no property with the identifier is present,
+    /// `prop` is added to the end of the node's properties and `None` is returned.
+    ///
+    /// # Examples
+    /// ```
+    /// use sgf_parse::go::{parse, Prop};
+    ///
+    /// let mut node = parse("(;C[Old]B[de]N[Name])").unwrap().into_iter().next().unwrap();
+    /// let old = node.replace_property(Prop::C("New".into()));
+    /// assert_eq!(old, Some(Prop::C("Old".into())));
+    /// assert_eq!(node.serialize(), "(;C[New]B[de]N[Name])");
+    /// assert_eq!(node.replace_property(Prop::V(1.0)), None);
+    /// assert_eq!(node.serialize(), "(;C[New]B[de]N[Name]V[1])");
+    /// ```
+    pub fn replace_property(&mut self, prop: Prop) -> Option<Prop> {
+        match self
+            .properties
+            .iter_mut()
+            .find(|existing| existing.identifier_str() == prop.identifier_str())
+        {
+            Some(existing) => Some(std::mem::replace(existing, prop)),
+            None => {
+                self.properties.push(prop);
+                None
+            }
+        }
+    }
+
+    /// Sets a property, replacing any properties with the same identifier.
+    ///
+    /// Like [`SgfNode::replace_property`], the first existing property keeps its position. Any
+    /// later properties with the same identifier are removed, so the node ends up with exactly
+    /// one. Returns the first replaced property, if any.
+    ///
+    /// # Examples
+    /// ```
+    /// use sgf_parse::go::{parse, Prop};
+    ///
+    /// let mut node = parse("(;C[Old]B[de]C[Duplicate])").unwrap().remove(0);
+    /// let old = node.set_property(Prop::C("New".into()));
+    /// assert_eq!(old, Some(Prop::C("Old".into())));
+    /// assert_eq!(node.serialize(), "(;C[New]B[de])");
+    /// ```
+    pub fn set_property(&mut self, prop: Prop) -> Option<Prop> {
+        let identifier = prop.identifier();
+        let old = self.replace_property(prop);
+        if old.is_some() {
+            let mut seen = false;
+            self.properties.retain(|existing| {
+                if existing.identifier_str() != &identifier[..] {
+                    return true;
+                }
+                !std::mem::replace(&mut seen, true)
+            });
+        }
+        old
+    }
+
+    /// Removes all properties with the provided identifier.
+    ///
+    /// Returns `true` if any properties were removed.
+    ///
+    /// # Examples
+    /// ```
+    /// use sgf_parse::go::parse;
+    ///
+    /// let mut node = parse("(;C[One]B[de]C[Two])").unwrap().remove(0);
+    /// assert!(node.remove_property("C"));
+    /// assert_eq!(node.serialize(), "(;B[de])");
+    /// assert!(!node.remove_property("C"));
+    /// ```
+    pub fn remove_property(&mut self, identifier: &str) -> bool {
+        let len = self.properties.len();
+        self.properties
+            .retain(|prop| prop.identifier_str() != identifier);
+        self.properties.len() != len
+    }
+
+    /// Removes all properties with the provided identifier and returns the first one.
+    ///
+    /// # Examples
+    /// ```
+    /// use sgf_parse::go::{parse, Prop};
+    ///
+    /// let mut node = parse("(;C[One]B[de]C[Two])").unwrap().remove(0);
+    /// assert_eq!(node.take_property("C"), Some(Prop::C("One".into())));
+    /// assert_eq!(node.serialize(), "(;B[de])");
+    /// assert_eq!(node.take_property("C"), None);
+    /// ```
+    pub fn take_property(&mut self, identifier: &str) -> Option<Prop> {
+        let index = self
+            .properties
+            .iter()
+            .position(|prop| prop.identifier_str() == identifier)?;
+        let prop = self.properties.remove(index);
+        self.remove_property(identifier);
+        Some(prop)
+    }
+
+    /// Removes all properties with any of the provided identifiers from this node and all its
+    /// descendants.
+    ///
+    /// Returns the number of properties removed.
+    ///
+    /// # Examples
+    /// ```
+    /// use sgf_parse::go::parse;
+    ///
+    /// let mut node = parse("(;C[Start](;B[de]TR[aa]C[Hmm])(;B[dd]LB[cc:A]))").unwrap().remove(0);
+    /// assert_eq!(node.remove_properties_recursive(&["C", "LB", "TR"]), 4);
+    /// assert_eq!(node.serialize(), "(;(;B[de])(;B[dd]))");
+    /// ```
+    pub fn remove_properties_recursive(&mut self, identifiers: &[&str]) -> usize {
+        let mut removed = 0;
+        let mut stack = vec![self];
+        while let Some(node) = stack.pop() {
+            let len = node.properties.len();
+            node.properties
+                .retain(|prop| !identifiers.contains(&prop.identifier_str()));
+            removed += len - node.properties.len();
+            stack.extend(node.children.iter_mut());
+        }
+        removed
     }
 
     /// Returns an iterator over the children of this node.
@@ -84,6 +638,23 @@ impl<Prop: SgfProp> SgfNode<Prop> {
         self.children.iter()
     }
 
+    /// Returns an iterator that allows modifying the children of this node.
+    ///
+    /// # Examples
+    /// ```
+    /// use sgf_parse::go::{parse, Prop};
+    /// use sgf_parse::Text;
+    ///
+    /// let mut node = parse("(;GM[1](;B[ee])(;B[cc]))").unwrap().remove(0);
+    /// for child in node.children_mut() {
+    ///     child.properties.push(Prop::C(Text { text: "Opening".to_string() }));
+    /// }
+    /// assert_eq!(node.serialize(), "(;GM[1](;B[ee]C[Opening])(;B[cc]C[Opening]))");
+    /// ```
+    pub fn children_mut(&mut self) -> impl Iterator<Item = &mut Self> {
+        self.children.iter_mut()
+    }
+
     /// Returns an iterator over the properties of this node.
     ///
     /// # Examples
@@ -111,6 +682,11 @@ impl<Prop: SgfProp> SgfNode<Prop> {
 
     /// Returns the serialized SGF for this SgfNode as a complete GameTree.
     ///
+    /// Any root `CA` property is written as it is. Set [`SerializeOptions::declare_utf8`] to
+    /// replace it when writing the text out as UTF-8.
+    ///
+    /// [`SerializeOptions::declare_utf8`]: crate::SerializeOptions::declare_utf8
+    ///
     /// # Examples
     /// ```
     /// use sgf_parse::go::parse;
@@ -123,6 +699,99 @@ impl<Prop: SgfProp> SgfNode<Prop> {
         format!("({})", self)
     }
 
+    /// Writes the serialized SGF for this SgfNode as a complete GameTree to a writer.
+    ///
+    /// Unlike [`SgfNode::serialize`] this streams output without building the full text first.
+    ///
+    /// # Errors
+    /// Returns an error if writing fails.
+    ///
+    /// # Examples
+    /// ```
+    /// use sgf_parse::go::parse;
+    ///
+    /// let sgf = "(;SZ[13:13];B[de](;W[ee])(;W[ff]))";
+    /// let node = parse(sgf).unwrap().into_iter().next().unwrap();
+    /// let mut output = String::new();
+    /// node.write_sgf(&mut output).unwrap();
+    /// assert_eq!(output, sgf);
+    /// ```
+    pub fn write_sgf<W: std::fmt::Write>(&self, writer: &mut W) -> std::fmt::Result {
+        self.write_nodes(writer, true, false)
+    }
+
+    // Writes the serialized SGF as a complete GameTree, replacing any root `CA` property with
+    // `CA[UTF-8]` for callers which write the text out as UTF-8.
+    pub(crate) fn write_sgf_declaring_utf8<W: std::fmt::Write>(
+        &self,
+        writer: &mut W,
+    ) -> std::fmt::Result {
+        self.write_nodes(writer, true, true)
+    }
+
+    // Keep the original text of a property to write instead of its serialized form.
+    pub(crate) fn keep_raw_property(&mut self, serialized: String, raw: String) {
+        self.raw_properties.push((serialized, raw));
+    }
+
+    // Returns the number of bytes owned by the original text of properties.
+    pub(crate) fn raw_properties_heap_size(&self) -> usize {
+        self.raw_properties.capacity() * std::mem::size_of::<(String, String)>()
+            + self
+                .raw_properties
+                .iter()
+                .map(|(serialized, raw)| serialized.capacity() + raw.capacity())
+                .sum::<usize>()
+    }
+
+    // Writes the node and its descendants, optionally wrapped in parentheses.
+    fn write_nodes<W: std::fmt::Write>(
+        &self,
+        writer: &mut W,
+        wrap: bool,
+        declare_utf8: bool,
+    ) -> std::fmt::Result {
+        enum Item<'a, Prop: SgfProp> {
+            Node(&'a SgfNode<Prop>, bool),
+            Close,
+        }
+
+        let mut stack = vec![Item::Node(self, wrap)];
+        while let Some(item) = stack.pop() {
+            let (node, wrap) = match item {
+                Item::Node(node, wrap) => (node, wrap),
+                Item::Close => {
+                    writer.write_char(')')?;
+                    continue;
+                }
+            };
+            if wrap {
+                writer.write_char('(')?;
+                stack.push(Item::Close);
+            }
+            writer.write_char(';')?;
+            for prop in node.properties() {
+                if declare_utf8 && node.is_root && prop.identifier_str() == "CA" {
+                    writer.write_str("CA[UTF-8]")?;
+                } else if node.raw_properties.is_empty() {
+                    write!(writer, "{}", prop)?;
+                } else {
+                    // Properties changed since parsing no longer match their original text.
+                    let text = prop.to_string();
+                    match node.raw_properties.iter().find(|(s, _)| *s == text) {
+                        Some((_, raw)) => writer.write_str(raw)?,
+                        None => writer.write_str(&text)?,
+                    }
+                }
+            }
+            let wrap_children = node.children.len() > 1;
+            for child in node.children.iter().rev() {
+                stack.push(Item::Node(child, wrap_children));
+            }
+        }
+        Ok(())
+    }
+
     /// Returns `Ok` if the node's properties are valid according to the SGF FF\[4\] spec.
     ///
     /// # Errors
@@ -139,27 +808,111 @@ impl<Prop: SgfProp> SgfNode<Prop> {
     /// ```
     pub fn validate(&self) -> Result<(), InvalidNodeError> {
         // TODO: Implement this non-recursively
-        self.validate_helper()?;
+        self.validate_helper(self.is_root)?;
         Ok(())
     }
 
     // Helper that returns whether a child has any game info in its descendents.
-    fn validate_helper(&self) -> Result<bool, InvalidNodeError> {
-        Prop::validate_properties(&self.properties, self.is_root)?;
+    //
+    // Children are always validated as non-root nodes regardless of their own flag.
+    fn validate_helper(&self, is_root: bool) -> Result<bool, InvalidNodeError> {
+        Prop::validate_properties(&self.properties, is_root)?;
         let has_game_info = self.has_game_info();
         let mut child_has_game_info = false;
         for child in self.children() {
-            child_has_game_info |= child.validate_helper()?;
+            child_has_game_info |= child.validate_helper(false)?;
         }
         if child_has_game_info && has_game_info {
-            return Err(InvalidNodeError::UnexpectedGameInfo(format!(
-                "{:?}",
-                self.properties
+            return Err(InvalidNodeError::UnexpectedGameInfo(error_context(
+                &self.properties,
             )));
         }
         Ok(has_game_info)
     }
 
+    /// Returns all validation errors in the tree, using up to `threads` threads.
+    ///
+    /// Unlike [`SgfNode::validate`] this doesn't stop at the first error. Each node is checked
+    /// with the same rules, and any error is returned along with the path of child indices from
+    /// this node to the invalid node. Results are ordered by path regardless of the number of
+    /// threads.
+    ///
+    /// # Examples
+    /// ```
+    /// use sgf_parse::InvalidNodeError;
+    /// use sgf_parse::go::parse;
+    ///
+    /// let sgf = "(;SZ[9];B[de]W[ee](;C[a]C[b])(;B[aa]))";
+    /// let node = parse(sgf).unwrap().into_iter().next().unwrap();
+    /// let errors = node.validate_parallel(4);
+    /// assert_eq!(errors.len(), 2);
+    /// assert_eq!(errors[0].0.to_string(), "0");
+    /// assert!(matches!(errors[0].1, InvalidNodeError::MultipleMoves(_)));
+    /// assert_eq!(errors[1].0.to_string(), "0.0");
+    /// assert!(matches!(errors[1].1, InvalidNodeError::RepeatedIdentifier(_)));
+    /// ```
+    pub fn validate_parallel(&self, threads: usize) -> Vec<(NodePath, InvalidNodeError)>
+    where
+        Prop: Sync,
+    {
+        // Nodes in depth-first order along with their parent's index and their child index.
+        let mut nodes: Vec<(&Self, usize, usize)> = vec![];
+        let mut stack = vec![(self, usize::MAX, 0)];
+        while let Some((node, parent, child_index)) = stack.pop() {
+            let index = nodes.len();
+            nodes.push((node, parent, child_index));
+            for (i, child) in node.children.iter().enumerate().rev() {
+                stack.push((child, index, i));
+            }
+        }
+
+        let validate_node = |index: usize| {
+            let node = nodes[index].0;
+            let is_root = index == 0 && self.is_root;
+            Prop::validate_properties(&node.properties, is_root).and_then(|()| {
+                if node.has_game_info() && node.children.iter().any(|c| c.has_game_info()) {
+                    Err(InvalidNodeError::UnexpectedGameInfo(error_context(
+                        &node.properties,
+                    )))
+                } else {
+                    Ok(())
+                }
+            })
+        };
+        let chunk_size = nodes.len().div_ceil(threads.max(1));
+        let errors: Vec<(usize, InvalidNodeError)> = std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..nodes.len())
+                .step_by(chunk_size)
+                .map(|start| {
+                    let end = (start + chunk_size).min(nodes.len());
+                    scope.spawn(move || {
+                        (start..end)
+                            .filter_map(|i| validate_node(i).err().map(|e| (i, e)))
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .flat_map(|handle| handle.join().expect("validation worker panicked"))
+                .collect()
+        });
+
+        errors
+            .into_iter()
+            .map(|(mut index, error)| {
+                let mut path = vec![];
+                while index != 0 {
+                    let (_, parent, child_index) = nodes[index];
+                    path.push(child_index);
+                    index = parent;
+                }
+                path.reverse();
+                (NodePath::from(path), error)
+            })
+            .collect()
+    }
+
     /// Returns an iterator over the nodes of the main variation.
     ///
     /// This is a convenience method for iterating through the first child of each node until the
@@ -198,6 +951,161 @@ impl<Prop: SgfProp> SgfNode<Prop> {
         }
     }
 
+    /// Returns an iterator over every node in the tree along with its path from this node.
+    ///
+    /// Paths are relative to this node. They're yielded as [`TraversalPath`] values rather than
+    /// [`NodePath`] values, since a `NodePath` copies every index from the root and building one
+    /// for each node would take time proportional to its depth. A `TraversalPath` shares its
+    /// ancestors' indices, so a traversal takes constant time per node. Use
+    /// [`TraversalPath::to_node_path`] for the nodes you need.
+    ///
+    /// [`TraversalOrder::BreadthFirst`] visits shallower nodes first, which makes it suitable for
+    /// finding the shallowest node matching some condition.
+    ///
+    /// # Examples
+    /// ```
+    /// use sgf_parse::go::parse;
+    /// use sgf_parse::TraversalOrder;
+    ///
+    /// let node = parse("(;B[aa](;W[bb];B[cc])(;W[dd]C[Hi]))").unwrap().remove(0);
+    /// let paths: Vec<_> = node
+    ///     .traverse(TraversalOrder::DepthFirst)
+    ///     .map(|(path, _)| path.to_string())
+    ///     .collect();
+    /// assert_eq!(paths, vec!["", "0", "0.0", "1"]);
+    /// let paths: Vec<_> = node
+    ///     .traverse(TraversalOrder::BreadthFirst)
+    ///     .map(|(path, _)| path.to_string())
+    ///     .collect();
+    /// assert_eq!(paths, vec!["", "0", "1", "0.0"]);
+    ///
+    /// let (path, _) = node
+    ///     .traverse(TraversalOrder::BreadthFirst)
+    ///     .find(|(_, n)| n.get_property("C").is_some())
+    ///     .unwrap();
+    /// assert_eq!(path.to_node_path().as_slice(), [1]);
+    /// ```
+    pub fn traverse(&self, order: TraversalOrder) -> impl Iterator<Item = (TraversalPath, &Self)> {
+        TraverseIter {
+            pending: std::iter::once((TraversalPath::root(), self)).collect(),
+            order,
+        }
+    }
+
+    /// Returns the first node in the main variation with game info properties (if any).
+    ///
+    /// Game info properties are usually on the root node, but may appear on any node, for
+    /// instance when several games are merged into one game tree.
+    ///
+    /// # Examples
+    /// ```
+    /// use sgf_parse::go::parse;
+    ///
+    /// let node = parse("(;GM[1];B[dd];PB[Lee]KM[6.5];W[pp])").unwrap().remove(0);
+    /// let game_info_node = node.game_info_node().unwrap();
+    /// assert_eq!(game_info_node.serialize(), "(;PB[Lee]KM[6.5];W[pp])");
+    /// ```
+    pub fn game_info_node(&self) -> Option<&Self> {
+        self.main_variation().find(|node| {
+            node.properties()
+                .any(|prop| prop.property_type() == Some(PropertyType::GameInfo))
+        })
+    }
+
+    /// Returns the game info node in effect for the node at `path`.
+    ///
+    /// This is the nearest node with game info properties on the way from this node to the node
+    /// at `path` (inclusive), which is the game info node for the position at `path`. Files
+    /// containing several games, like study files, may have game info on variation nodes, so
+    /// different variations can belong to different games. The path is relative to this node.
+    ///
+    /// Returns `None` if there's no node at `path`, or no game info on the way to it.
+    ///
+    /// # Examples
+    /// ```
+    /// use sgf_parse::go::parse;
+    /// use sgf_parse::NodePath;
+    ///
+    /// let sgf = "(;GM[1](;PB[Lee]PW[Gu];B[dd];W[pp])(;PB[Cho]PW[Iyama];B[pd]))";
+    /// let node = parse(sgf).unwrap().remove(0);
+    /// let info = node.effective_game_info(&"1.0".parse().unwrap()).unwrap();
+    /// assert_eq!(info.get_pb().unwrap().to_string(), "Cho");
+    /// let info = node.effective_game_info(&"0.0.0".parse().unwrap()).unwrap();
+    /// assert_eq!(info.get_pb().unwrap().to_string(), "Lee");
+    /// assert!(node.effective_game_info(&NodePath::root()).is_none());
+    /// ```
+    pub fn effective_game_info(&self, path: &NodePath) -> Option<&Self> {
+        let mut node = self;
+        let mut game_info_node = Some(self).filter(|node| node.has_game_info());
+        for &index in path.iter() {
+            node = node.children.get(index)?;
+            if node.has_game_info() {
+                game_info_node = Some(node);
+            }
+        }
+        game_info_node
+    }
+
+    /// Returns the node at `path`, or `None` if there's no such node.
+    ///
+    /// The path is relative to this node, so the root path returns this node.
+    ///
+    /// # Examples
+    /// ```
+    /// use sgf_parse::go::parse;
+    /// use sgf_parse::NodePath;
+    ///
+    /// let node = parse("(;SZ[9](;B[ee];W[ce])(;B[gc]))").unwrap().remove(0);
+    /// let path = NodePath::from(vec![0, 0]);
+    /// assert_eq!(node.node_at(&path).unwrap().serialize(), "(;W[ce])");
+    /// assert!(node.node_at(&NodePath::from(vec![1, 0])).is_none());
+    /// ```
+    pub fn node_at(&self, path: &NodePath) -> Option<&Self> {
+        let mut node = self;
+        for &index in path.iter() {
+            node = node.children.get(index)?;
+        }
+        Some(node)
+    }
+
+    /// Returns a mutable reference to the node at `path`, or `None` if there's no such node.
+    ///
+    /// See [`SgfNode::node_at`].
+    pub fn node_at_mut(&mut self, path: &NodePath) -> Option<&mut Self> {
+        let mut node = self;
+        for &index in path.iter() {
+            node = node.children.get_mut(index)?;
+        }
+        Some(node)
+    }
+
+    /// Returns the path from this node to `node`, or `None` if `node` isn't this node or one of
+    /// its descendants.
+    ///
+    /// Nodes are compared by identity rather than by value, so this finds the path of a
+    /// reference obtained from the tree even when other nodes have the same contents.
+    ///
+    /// # Examples
+    /// ```
+    /// use sgf_parse::go::parse;
+    ///
+    /// let node = parse("(;SZ[9](;B[ee])(;B[ee]))").unwrap().remove(0);
+    /// let second = &node.children[1];
+    /// let path = node.path_of(second).unwrap();
+    /// assert_eq!(path.as_slice(), [1]);
+    /// assert!(std::ptr::eq(node.node_at(&path).unwrap(), second));
+    /// ```
+    pub fn path_of(&self, node: &Self) -> Option<NodePath> {
+        let (path, _) = self.find_first(|candidate| {
+            if std::ptr::eq(candidate, node) {
+                SearchControl::Match
+            } else {
+                SearchControl::Continue
+            }
+        })?;
+        Some(path)
+    }
+
     /// Returns the move property (if present) on the node.
     ///
     /// # Examples
@@ -216,7 +1124,246 @@ impl<Prop: SgfProp> SgfNode<Prop> {
             .find(|p| p.property_type() == Some(PropertyType::Move))
     }
 
-    fn has_game_info(&self) -> bool {
+    /// Returns the differences between this node's properties and another node's properties.
+    ///
+    /// Properties are matched by identifier. The returned [`PropDiff`] treats `self` as the old
+    /// node and `other` as the new node.
+    ///
+    /// # Examples
+    /// ```
+    /// use sgf_parse::go::{parse, Prop};
+    ///
+    /// let old = parse("(;B[de]C[A comment]N[Name])").unwrap().into_iter().next().unwrap();
+    /// let new = parse("(;B[de]C[Changed]SQ[aa])").unwrap().into_iter().next().unwrap();
+    /// let diff = old.diff_properties(&new);
+    /// assert_eq!(diff.added, vec![Prop::SQ(vec!["aa".parse().unwrap()].into_iter().collect())]);
+    /// assert_eq!(diff.removed, vec![Prop::N("Name".into())]);
+    /// assert_eq!(diff.changed, vec![(Prop::C("A comment".into()), Prop::C("Changed".into()))]);
+    /// ```
+    pub fn diff_properties(&self, other: &Self) -> PropDiff<Prop> {
+        let mut diff = PropDiff {
+            added: vec![],
+            removed: vec![],
+            changed: vec![],
+        };
+        for prop in self.properties() {
+            match other.get_property(prop.identifier_str()) {
+                None => diff.removed.push(prop.clone()),
+                Some(new_prop) if new_prop != prop => {
+                    diff.changed.push((prop.clone(), new_prop.clone()))
+                }
+                Some(_) => {}
+            }
+        }
+        for prop in other.properties() {
+            if self.get_property(prop.identifier_str()).is_none() {
+                diff.added.push(prop.clone());
+            }
+        }
+        diff
+    }
+
+    /// Returns the first node in depth-first order accepted by `predicate`, along with its path.
+    ///
+    /// The path is relative to this node. The predicate decides for each node whether it's a
+    /// match, whether to keep searching its descendants, or whether to skip them. The search stops
+    /// at the first match, so targeted lookups in large trees only visit the nodes they need to.
+    ///
+    /// # Examples
+    /// ```
+    /// use sgf_parse::go::parse;
+    /// use sgf_parse::SearchControl;
+    ///
+    /// let sgf = "(;C[root](;B[aa]C[skip me];C[found])(;W[bb];C[found]))";
+    /// let node = parse(sgf).unwrap().remove(0);
+    /// let (path, found) = node
+    ///     .find_first(|node| {
+    ///         if node.get_property("B").is_some() {
+    ///             SearchControl::SkipChildren
+    ///         } else if node.get_property("C").is_some() && node.children.is_empty() {
+    ///             SearchControl::Match
+    ///         } else {
+    ///             SearchControl::Continue
+    ///         }
+    ///     })
+    ///     .unwrap();
+    /// assert_eq!(path.to_string(), "1.0");
+    /// assert_eq!(found.serialize(), "(;C[found])");
+    /// ```
+    pub fn find_first<F>(&self, mut predicate: F) -> Option<(NodePath, &Self)>
+    where
+        F: FnMut(&Self) -> SearchControl,
+    {
+        let mut path = vec![];
+        // Nodes to visit along with their depth and index in their parent's children.
+        let mut stack = vec![(self, 0usize, 0)];
+        while let Some((node, depth, index)) = stack.pop() {
+            path.truncate(depth.saturating_sub(1));
+            if depth > 0 {
+                path.push(index);
+            }
+            match predicate(node) {
+                SearchControl::Match => return Some((NodePath::from(path), node)),
+                SearchControl::SkipChildren => {}
+                SearchControl::Continue => {
+                    for (i, child) in node.children.iter().enumerate().rev() {
+                        stack.push((child, depth + 1, i));
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    /// Returns every node accepted by `predicate` in depth-first order, along with their paths.
+    ///
+    /// Paths are relative to this node. Use [`SgfNode::find_first`] to stop at the first match or
+    /// skip parts of the tree.
+    ///
+    /// # Examples
+    /// ```
+    /// use sgf_parse::go::parse;
+    ///
+    /// let sgf = "(;C[Opening];B[dd](;W[pp]C[A tesuji!])(;W[dp];B[pd]C[Not a tesuji]))";
+    /// let node = parse(sgf).unwrap().remove(0);
+    /// let matches = node.find_nodes(|node| {
+    ///     node.get_c().map_or(false, |comment| comment.text.contains("tesuji"))
+    /// });
+    /// let paths: Vec<_> = matches.iter().map(|(path, _)| path.to_string()).collect();
+    /// assert_eq!(paths, vec!["0.0", "0.1.0"]);
+    /// ```
+    pub fn find_nodes<F>(&self, mut predicate: F) -> Vec<(NodePath, &Self)>
+    where
+        F: FnMut(&Self) -> bool,
+    {
+        self.traverse(TraversalOrder::DepthFirst)
+            .filter(|(_, node)| predicate(node))
+            .map(|(path, node)| (path.to_node_path(), node))
+            .collect()
+    }
+
+    /// Returns a hash of the node's properties and all its descendants.
+    ///
+    /// The hash is stable across runs and platforms, so it can be stored alongside cached data
+    /// to detect which subtrees changed after an edit. Property order and child order are
+    /// significant, but the order of values in list properties isn't. Whether the node is a root
+    /// node isn't included.
+    ///
+    /// # Examples
+    /// ```
+    /// use sgf_parse::go::parse;
+    ///
+    /// let node = parse("(;AB[aa][bb];B[cc](;W[dd])(;W[ee]))").unwrap().remove(0);
+    /// let same = parse("(;AB[bb][aa];B[cc](;W[dd])(;W[ee]))").unwrap().remove(0);
+    /// let edited = parse("(;AB[aa][bb];B[cc](;W[dd])(;W[ff]))").unwrap().remove(0);
+    /// assert_eq!(node.content_hash(), same.content_hash());
+    /// assert_ne!(node.content_hash(), edited.content_hash());
+    /// assert_eq!(
+    ///     node.children[0].children[0].content_hash(),
+    ///     edited.children[0].children[0].content_hash()
+    /// );
+    /// ```
+    pub fn content_hash(&self) -> u64 {
+        // Nodes in depth-first order along with their parent's index.
+        let mut nodes: Vec<(&Self, usize)> = vec![];
+        let mut stack = vec![(self, usize::MAX)];
+        while let Some((node, parent)) = stack.pop() {
+            let index = nodes.len();
+            nodes.push((node, parent));
+            for child in node.children.iter().rev() {
+                stack.push((child, index));
+            }
+        }
+
+        // Hash nodes from the leaves up so each hash covers the node's children. Walking
+        // backwards visits each parent's children last to first.
+        let mut child_hashes: Vec<Vec<u64>> = vec![vec![]; nodes.len()];
+        let mut hash = 0;
+        for (index, (node, parent)) in nodes.iter().enumerate().rev() {
+            let mut hasher = StableHasher::new();
+            hasher.write_usize(node.properties.len());
+            for prop in node.properties.iter() {
+                let values = canonical_values(prop);
+                hasher.write_str(prop.identifier_str());
+                hasher.write_usize(values.len());
+                for value in values.iter() {
+                    hasher.write_str(value);
+                }
+            }
+            hasher.write_usize(node.children.len());
+            for child_hash in child_hashes[index].iter().rev() {
+                hasher.write_u64(*child_hash);
+            }
+            hash = hasher.finish();
+            if let Some(siblings) = child_hashes.get_mut(*parent) {
+                siblings.push(hash);
+            }
+        }
+        hash
+    }
+
+    /// Returns the number of nodes in the tree, including this node.
+    ///
+    /// # Examples
+    /// ```
+    /// use sgf_parse::go::parse;
+    ///
+    /// let node = parse("(;B[dd](;W[pp];B[pd])(;W[dp]))").unwrap().remove(0);
+    /// assert_eq!(node.node_count(), 4);
+    /// ```
+    pub fn node_count(&self) -> usize {
+        self.tree_metrics().0
+    }
+
+    /// Returns the depth of the deepest node in the tree.
+    ///
+    /// Depth is the number of moves down from this node, as in [`Cursor::depth`](crate::Cursor::depth),
+    /// so a node without children has a depth of 0.
+    ///
+    /// # Examples
+    /// ```
+    /// use sgf_parse::go::parse;
+    ///
+    /// let node = parse("(;B[dd](;W[pp];B[pd])(;W[dp]))").unwrap().remove(0);
+    /// assert_eq!(node.max_depth(), 2);
+    /// ```
+    pub fn max_depth(&self) -> usize {
+        self.tree_metrics().1
+    }
+
+    /// Returns the number of variations in the tree.
+    ///
+    /// This is the number of nodes without children, since each ends a distinct variation.
+    ///
+    /// # Examples
+    /// ```
+    /// use sgf_parse::go::parse;
+    ///
+    /// let node = parse("(;B[dd](;W[pp];B[pd])(;W[dp](;B[pp])(;B[pd])))").unwrap().remove(0);
+    /// assert_eq!(node.variation_count(), 3);
+    /// ```
+    pub fn variation_count(&self) -> usize {
+        self.tree_metrics().2
+    }
+
+    // Returns the node count, max depth, and variation count in a single pass.
+    pub(crate) fn tree_metrics(&self) -> (usize, usize, usize) {
+        let mut node_count = 0;
+        let mut max_depth = 0;
+        let mut variation_count = 0;
+        let mut stack = vec![(self, 0)];
+        while let Some((node, depth)) = stack.pop() {
+            node_count += 1;
+            max_depth = max_depth.max(depth);
+            if node.children.is_empty() {
+                variation_count += 1;
+            }
+            stack.extend(node.children.iter().map(|child| (child, depth + 1)));
+        }
+        (node_count, max_depth, variation_count)
+    }
+
+    pub(crate) fn has_game_info(&self) -> bool {
         for prop in self.properties() {
             if let Some(PropertyType::GameInfo) = prop.property_type() {
                 return true;
@@ -226,25 +1373,126 @@ impl<Prop: SgfProp> SgfNode<Prop> {
     }
 }
 
+/// Returns the child with the provided index.
+///
+/// # Panics
+/// Panics if the node doesn't have a child with the index.
+///
+/// # Examples
+/// ```
+/// use sgf_parse::go::parse;
+///
+/// let mut node = parse("(;SZ[9];B[ee](;W[ce])(;W[gc];B[gd]))").unwrap().remove(0);
+/// assert_eq!(node[0][1][0].serialize(), "(;B[gd])");
+/// node[0][1].children.clear();
+/// assert_eq!(node[0][1].serialize(), "(;W[gc])");
+/// ```
+impl<Prop: SgfProp> std::ops::Index<usize> for SgfNode<Prop> {
+    type Output = Self;
+
+    fn index(&self, index: usize) -> &Self {
+        &self.children[index]
+    }
+}
+
+impl<Prop: SgfProp> std::ops::IndexMut<usize> for SgfNode<Prop> {
+    fn index_mut(&mut self, index: usize) -> &mut Self {
+        &mut self.children[index]
+    }
+}
+
+/// Iterates over the children of the node.
+///
+/// # Examples
+/// ```
+/// use sgf_parse::go::parse;
+///
+/// let node = parse("(;SZ[9](;B[ee])(;B[cc]))").unwrap().remove(0);
+/// let moves: Vec<_> = (&node).into_iter().map(|child| child.serialize()).collect();
+/// assert_eq!(moves, vec!["(;B[ee])", "(;B[cc])"]);
+/// ```
+impl<'a, Prop: SgfProp> IntoIterator for &'a SgfNode<Prop> {
+    type Item = &'a SgfNode<Prop>;
+    type IntoIter = std::slice::Iter<'a, SgfNode<Prop>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.children.iter()
+    }
+}
+
+impl<'a, Prop: SgfProp> IntoIterator for &'a mut SgfNode<Prop> {
+    type Item = &'a mut SgfNode<Prop>;
+    type IntoIter = std::slice::IterMut<'a, SgfNode<Prop>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.children.iter_mut()
+    }
+}
+
 impl<Prop: SgfProp> std::fmt::Display for SgfNode<Prop> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        // TODO: Implement this non-recursively
-        let prop_string = self
-            .properties()
-            .map(|x| x.to_string())
-            .collect::<Vec<_>>()
-            .join("");
-        let child_count = self.children().count();
-        let child_string = match child_count {
-            0 => "".to_string(),
-            1 => self.children().next().unwrap().to_string(),
-            _ => self
-                .children()
-                .map(|x| format!("({})", x))
-                .collect::<Vec<_>>()
-                .join(""),
-        };
-        write!(f, ";{}{}", prop_string, child_string)
+        self.write_nodes(f, false, false)
+    }
+}
+
+/// Differences between the properties of two nodes as returned by [`SgfNode::diff_properties`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct PropDiff<Prop: SgfProp> {
+    /// Properties only present in the new node.
+    pub added: Vec<Prop>,
+    /// Properties only present in the old node.
+    pub removed: Vec<Prop>,
+    /// Properties present in both nodes with different values as `(old, new)` pairs.
+    pub changed: Vec<(Prop, Prop)>,
+}
+
+impl<Prop: SgfProp> PropDiff<Prop> {
+    /// Returns `true` if there are no differences.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+// Returns a property's raw values, sorted unless their order is significant, so equal properties
+// have equal values regardless of how their sets are ordered.
+pub(crate) fn canonical_values<Prop: SgfProp>(prop: &Prop) -> Vec<String> {
+    let mut values = prop.raw_values();
+    if !prop.is_unknown() && !prop.is_invalid() {
+        values.sort_unstable();
+    }
+    values
+}
+
+// 64 bit FNV-1a, which unlike `DefaultHasher` is guaranteed not to change between releases.
+pub(crate) struct StableHasher(u64);
+
+impl StableHasher {
+    pub(crate) fn new() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+
+    pub(crate) fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= u64::from(*byte);
+            self.0 = self.0.wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+
+    fn write_u64(&mut self, value: u64) {
+        self.write(&value.to_le_bytes());
+    }
+
+    fn write_usize(&mut self, value: usize) {
+        self.write_u64(value as u64);
+    }
+
+    fn write_str(&mut self, value: &str) {
+        self.write_usize(value.len());
+        self.write(value.as_bytes());
+    }
+
+    pub(crate) fn finish(&self) -> u64 {
+        self.0
     }
 }
 
@@ -267,6 +1515,53 @@ impl<'a, Prop: SgfProp> Iterator for MainVariationIter<'a, Prop> {
     }
 }
 
+/// What [`SgfNode::find_first`] should do after visiting a node.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SearchControl {
+    /// The node is a match, so stop searching.
+    Match,
+    /// Keep searching, including the node's descendants.
+    Continue,
+    /// Keep searching, but skip the node's descendants.
+    SkipChildren,
+}
+
+/// Order of nodes returned by [`SgfNode::traverse`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TraversalOrder {
+    /// Visit each node before its children, exhausting each variation before the next.
+    DepthFirst,
+    /// Visit all nodes at each depth before any deeper nodes.
+    BreadthFirst,
+}
+
+#[derive(Debug)]
+struct TraverseIter<'a, Prop: SgfProp> {
+    pending: std::collections::VecDeque<(TraversalPath, &'a SgfNode<Prop>)>,
+    order: TraversalOrder,
+}
+
+impl<'a, Prop: SgfProp> Iterator for TraverseIter<'a, Prop> {
+    type Item = (TraversalPath, &'a SgfNode<Prop>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (path, node) = match self.order {
+            TraversalOrder::DepthFirst => self.pending.pop_back()?,
+            TraversalOrder::BreadthFirst => self.pending.pop_front()?,
+        };
+        let children = node
+            .children
+            .iter()
+            .enumerate()
+            .map(|(i, child)| (path.child(i), child));
+        match self.order {
+            TraversalOrder::DepthFirst => self.pending.extend(children.rev()),
+            TraversalOrder::BreadthFirst => self.pending.extend(children),
+        }
+        Some((path, node))
+    }
+}
+
 /// Err type for [`SgfNode::validate`].
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum InvalidNodeError {
@@ -281,48 +1576,139 @@ pub enum InvalidNodeError {
     UnexpectedMoveAnnotation(String),
     MultipleExclusiveAnnotations(String),
     InvalidProperty(String),
+    ConflictingSetup(String),
+    RepeatedLabel(String),
+    LabelTooLong(String),
+}
+
+impl InvalidNodeError {
+    /// Returns the serialized properties involved in the error.
+    ///
+    /// Property values can be arbitrarily large, so only a bounded preview is kept. Previews which
+    /// were cut short end with `...`.
+    ///
+    /// # Examples
+    /// ```
+    /// use sgf_parse::go::parse;
+    ///
+    /// let comment = "x".repeat(10_000);
+    /// let sgf = format!("(;B[de]C[{}]C[Another])", comment);
+    /// let node = parse(&sgf).unwrap().remove(0);
+    /// let error = node.validate().unwrap_err();
+    /// assert!(error.context().starts_with("B[de]C[xxx"));
+    /// assert!(error.context().ends_with("..."));
+    /// assert!(error.to_string().len() < 300);
+    /// ```
+    pub fn context(&self) -> &str {
+        match self {
+            Self::UnexpectedRootProperties(context)
+            | Self::UnexpectedGameInfo(context)
+            | Self::RepeatedMarkup(context)
+            | Self::MultipleMoves(context)
+            | Self::RepeatedIdentifier(context)
+            | Self::SetupAndMove(context)
+            | Self::KoWithoutMove(context)
+            | Self::MultipleMoveAnnotations(context)
+            | Self::UnexpectedMoveAnnotation(context)
+            | Self::MultipleExclusiveAnnotations(context)
+            | Self::InvalidProperty(context)
+            | Self::ConflictingSetup(context)
+            | Self::RepeatedLabel(context)
+            | Self::LabelTooLong(context) => context,
+        }
+    }
+}
+
+const MAX_ERROR_CONTEXT_LENGTH: usize = 200;
+
+// Serializes properties for an `InvalidNodeError`, stopping once the preview is too long.
+pub(crate) fn error_context<Prop: SgfProp>(properties: &[Prop]) -> String {
+    use std::fmt::Write;
+    let mut writer = BoundedWriter {
+        text: String::new(),
+        remaining: MAX_ERROR_CONTEXT_LENGTH,
+    };
+    for prop in properties {
+        if write!(writer, "{}", prop).is_err() {
+            writer.text.push_str("...");
+            break;
+        }
+    }
+    writer.text
+}
+
+// Collects text until `remaining` bytes have been written, then fails any further writes.
+struct BoundedWriter {
+    text: String,
+    remaining: usize,
+}
+
+impl std::fmt::Write for BoundedWriter {
+    fn write_str(&mut self, s: &str) -> std::fmt::Result {
+        if s.len() <= self.remaining {
+            self.text.push_str(s);
+            self.remaining -= s.len();
+            return Ok(());
+        }
+        let end = (0..=self.remaining)
+            .rev()
+            .find(|&i| s.is_char_boundary(i))
+            .unwrap_or(0);
+        self.text.push_str(&s[..end]);
+        self.remaining = 0;
+        Err(std::fmt::Error)
+    }
 }
 
 impl std::fmt::Display for InvalidNodeError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             InvalidNodeError::UnexpectedRootProperties(context) => {
-                write!(f, "Root properties in non-root node: {:?}", context)
+                write!(f, "Root properties in non-root node: {}", context)
             }
             InvalidNodeError::UnexpectedGameInfo(context) => {
-                write!(f, "GameInfo properties in node and a child {:?}", context)
+                write!(f, "GameInfo properties in node and a child: {}", context)
             }
             InvalidNodeError::RepeatedMarkup(context) => {
-                write!(f, "Multiple markup properties on same point {:?}", context)
+                write!(f, "Multiple markup properties on same point: {}", context)
             }
             InvalidNodeError::MultipleMoves(context) => {
-                write!(f, "B and W moves in same node {:?}", context)
+                write!(f, "B and W moves in same node: {}", context)
             }
             InvalidNodeError::RepeatedIdentifier(context) => {
-                write!(f, "Identifier repeated in node {:?}", context)
+                write!(f, "Identifier repeated in node: {}", context)
             }
             InvalidNodeError::SetupAndMove(context) => {
-                write!(f, "Setup and move properties in same node {:?}", context)
+                write!(f, "Setup and move properties in same node: {}", context)
             }
             InvalidNodeError::KoWithoutMove(context) => {
-                write!(f, "Ko in node without B or W {:?}", context)
+                write!(f, "Ko in node without B or W: {}", context)
             }
             InvalidNodeError::MultipleMoveAnnotations(context) => {
-                write!(f, "Multiple move annotations in same node {:?}", context)
+                write!(f, "Multiple move annotations in same node: {}", context)
             }
             InvalidNodeError::UnexpectedMoveAnnotation(context) => {
-                write!(f, "Move annotation without move in node {:?}", context)
+                write!(f, "Move annotation without move in node: {}", context)
             }
             InvalidNodeError::MultipleExclusiveAnnotations(context) => {
                 write!(
                     f,
-                    "Multiple DM, UC, GW or GB properties in node {:?}",
+                    "Multiple DM, UC, GW or GB properties in node: {}",
                     context
                 )
             }
             InvalidNodeError::InvalidProperty(context) => {
                 write!(f, "Invalid property: {}", context)
             }
+            InvalidNodeError::ConflictingSetup(context) => {
+                write!(f, "Point in more than one of AB, AW or AE: {}", context)
+            }
+            InvalidNodeError::RepeatedLabel(context) => {
+                write!(f, "Multiple labels on same point: {}", context)
+            }
+            InvalidNodeError::LabelTooLong(context) => {
+                write!(f, "Label too long: {}", context)
+            }
         }
     }
 }
@@ -333,6 +1719,282 @@ impl std::error::Error for InvalidNodeError {}
 mod tests {
     use super::InvalidNodeError;
     use crate::go::parse;
+    use crate::NodePath;
+
+    #[test]
+    fn effective_game_info() {
+        let sgf = "(;GM[1]PB[Root];B[aa](;PB[Variation];W[bb])(;W[cc]))";
+        let node = parse(sgf).unwrap().remove(0);
+        let black = |path: Vec<usize>| {
+            node.effective_game_info(&NodePath::from(path))
+                .and_then(|info| info.get_pb())
+                .map(|text| text.to_string())
+        };
+        assert_eq!(black(vec![]).as_deref(), Some("Root"));
+        assert_eq!(black(vec![0, 0, 0]).as_deref(), Some("Variation"));
+        assert_eq!(black(vec![0, 1]).as_deref(), Some("Root"));
+        assert_eq!(black(vec![0, 2]), None);
+    }
+
+    #[test]
+    fn child_indexing_and_iteration() {
+        let mut node = parse("(;C[a](;C[b];C[c])(;C[d]))").unwrap().remove(0);
+        assert!(std::ptr::eq(&node[0][0], &node.children[0].children[0]));
+        for child in &mut node {
+            child.children.clear();
+        }
+        let count = (&node)
+            .into_iter()
+            .filter(|child| child.children.is_empty())
+            .count();
+        assert_eq!(count, 2);
+        let children: Vec<_> = (&node).into_iter().map(|child| child.serialize()).collect();
+        assert_eq!(children[1], "(;C[d])");
+    }
+
+    #[test]
+    #[should_panic]
+    fn child_index_out_of_bounds() {
+        let node = parse("(;C[a];C[b])").unwrap().remove(0);
+        let _ = &node[0][0];
+    }
+
+    #[test]
+    fn tree_editing() {
+        let mut node = parse("(;GM[1]SZ[9];B[ee](;W[cc])(;W[gg]))")
+            .unwrap()
+            .remove(0);
+        assert!(node.insert_parent(vec![]).is_err());
+        let mut comment = parse("(;C[Start];B[ee])").unwrap().remove(0);
+        comment.insert_parent(vec![]).unwrap();
+        assert!(comment.is_root());
+        assert!(!comment.children[0].is_root());
+        assert_eq!(comment.serialize(), "(;;C[Start];B[ee])");
+        assert!(comment.validate().is_ok());
+        assert!(node.validate().is_ok());
+
+        let edited = node.clone();
+        assert!(node.split_at(1).is_err());
+        let root_property = crate::SgfProp::new("FF".to_string(), vec!["4".to_string()]);
+        assert!(node[0].insert_parent(vec![root_property]).is_err());
+        assert_eq!(node, edited);
+    }
+
+    #[test]
+    fn reorder_children() {
+        let mut node = parse("(;C[a](;C[b])(;C[c](;C[d])(;C[e])(;C[f])))")
+            .unwrap()
+            .remove(0);
+        let original = node.clone();
+        assert!(!node.demote_child(1));
+        assert!(!node.promote_child(2));
+        assert!(!node.set_main_variation(&NodePath::from(vec![1, 3])));
+        assert_eq!(node, original);
+
+        assert!(node.set_main_variation(&NodePath::from(vec![1, 2])));
+        let main_line: Vec<_> = node.main_variation().map(|n| n.serialize()).collect();
+        assert_eq!(
+            main_line,
+            vec![
+                node.serialize(),
+                "(;C[c](;C[f])(;C[d])(;C[e]))".to_string(),
+                "(;C[f])".to_string()
+            ]
+        );
+        assert!(node.demote_child(0));
+        assert!(node[1].demote_child(0));
+        assert!(node[1].promote_child(2));
+        assert_eq!(node, original);
+        assert!(node.set_main_variation(&NodePath::root()));
+        assert_eq!(node, original);
+    }
+
+    #[test]
+    fn prune_variations() {
+        let mut node = parse("(;C[a](;C[b](;C[c])(;C[d]))(;C[e](;C[f])(;C[g])))")
+            .unwrap()
+            .remove(0);
+        node.children[1]
+            .retain_children(|child| child.get_property("C").unwrap().to_string() != "C[f]");
+        assert_eq!(node.serialize(), "(;C[a](;C[b](;C[c])(;C[d]))(;C[e];C[g]))");
+        let main_line: Vec<_> = node
+            .main_variation()
+            .map(|n| n.properties.clone())
+            .collect();
+        node.prune_to_main_variation();
+        assert_eq!(node.serialize(), "(;C[a];C[b];C[c])");
+        assert_eq!(node.node_count(), main_line.len());
+        assert!(node
+            .main_variation()
+            .map(|n| &n.properties)
+            .eq(main_line.iter()));
+    }
+
+    #[test]
+    fn property_mutation() {
+        let mut node = parse("(;B[aa]XX[1]C[a]XX[2]XX[3])").unwrap().remove(0);
+        let xx = |value: &str| crate::SgfProp::new("XX".to_string(), vec![value.to_string()]);
+        assert_eq!(node.set_property(xx("4")), Some(xx("1")));
+        assert_eq!(node.serialize(), "(;B[aa]XX[4]C[a])");
+        assert_eq!(node.set_property(xx("5")), Some(xx("4")));
+        assert_eq!(
+            node.take_property("B")
+                .map(|prop| prop.to_string())
+                .as_deref(),
+            Some("B[aa]")
+        );
+        assert!(node.remove_property("C"));
+        assert_eq!(node.serialize(), "(;XX[5])");
+        assert_eq!(node.set_property(crate::go::Prop::V(1.5)), None);
+        assert_eq!(node.serialize(), "(;XX[5]V[1.5])");
+    }
+
+    #[test]
+    fn remove_properties_recursive() {
+        let mut node = parse("(;C[a]XX[1](;B[aa]C[b]C[c];W[bb]XX[2])(;B[cc]))")
+            .unwrap()
+            .remove(0);
+        assert_eq!(node.remove_properties_recursive(&[]), 0);
+        assert_eq!(node.remove_properties_recursive(&["C", "XX"]), 5);
+        assert_eq!(node.serialize(), "(;(;B[aa];W[bb])(;B[cc]))");
+        assert_eq!(node.remove_properties_recursive(&["C", "XX"]), 0);
+    }
+
+    #[test]
+    fn tree_metrics() {
+        let node = parse("(;C[a](;C[b];C[c];C[d])(;C[e](;C[f])(;C[g])))")
+            .unwrap()
+            .remove(0);
+        assert_eq!(node.tree_metrics(), (7, 3, 3));
+        assert_eq!(node.children[1].tree_metrics(), (3, 1, 2));
+        let leaf = parse("(;B[aa])").unwrap().remove(0);
+        assert_eq!(leaf.tree_metrics(), (1, 0, 1));
+    }
+
+    #[test]
+    fn node_paths_round_trip() {
+        let sgf = "(;C[a](;C[b];C[c])(;C[d](;C[e])(;C[f])))";
+        let mut node = parse(sgf).unwrap().remove(0);
+        for (path, found) in node.traverse(super::TraversalOrder::DepthFirst) {
+            let node_path = node.path_of(found).unwrap();
+            assert_eq!(node_path, path.to_node_path());
+            assert!(std::ptr::eq(node.node_at(&node_path).unwrap(), found));
+        }
+        let other = node.clone();
+        assert!(node.path_of(&other.children[0]).is_none());
+        assert!(node.node_at(&NodePath::from(vec![1, 2])).is_none());
+
+        let path: NodePath = "1.1".parse().unwrap();
+        node.node_at_mut(&path).unwrap().children.clear();
+        let reloaded = parse(&node.serialize()).unwrap().remove(0);
+        assert_eq!(reloaded.node_at(&path).unwrap().serialize(), "(;C[f])");
+    }
+
+    #[test]
+    fn find_nodes_by_move() {
+        let node = parse("(;B[aa];W[bb](;B[cc];W[dd])(;B[ee]))")
+            .unwrap()
+            .remove(0);
+        let black_moves: Vec<(Vec<usize>, String)> = node
+            .find_nodes(|node| node.get_property("B").is_some())
+            .into_iter()
+            .map(|(path, found)| (path.into(), found.serialize()))
+            .collect();
+        assert_eq!(
+            black_moves,
+            vec![
+                (vec![], "(;B[aa];W[bb](;B[cc];W[dd])(;B[ee]))".to_string()),
+                (vec![0, 0], "(;B[cc];W[dd])".to_string()),
+                (vec![0, 1], "(;B[ee])".to_string()),
+            ]
+        );
+        assert!(node.find_nodes(|node| node.children.len() > 2).is_empty());
+    }
+
+    #[test]
+    fn find_first_prunes() {
+        use super::SearchControl;
+
+        let node = parse("(;C[a](;C[b];C[c])(;C[d](;C[e])(;C[f])))")
+            .unwrap()
+            .remove(0);
+        let mut visited = vec![];
+        let result = node.find_first(|node| {
+            let comment = node.get_property("C").unwrap().to_string();
+            visited.push(comment.clone());
+            match &comment[..] {
+                "C[b]" => SearchControl::SkipChildren,
+                "C[f]" => SearchControl::Match,
+                _ => SearchControl::Continue,
+            }
+        });
+        assert_eq!(result.unwrap().0.as_slice(), [1, 1]);
+        assert_eq!(visited, ["C[a]", "C[b]", "C[d]", "C[e]", "C[f]"]);
+        assert!(node.find_first(|_| SearchControl::Continue).is_none());
+        assert_eq!(
+            node.find_first(|_| SearchControl::Match).unwrap().0,
+            NodePath::root()
+        );
+    }
+
+    #[test]
+    fn content_hash_is_stable() {
+        // Changing this value invalidates hashes users have stored.
+        let sgf = "(;SZ[9]AB[aa][bb];B[cc]C[Hi](;W[dd])(;W[ee]))";
+        let node = parse(sgf).unwrap().remove(0);
+        assert_eq!(node.content_hash(), 0x5e37_32fe_a95d_d4b4);
+    }
+
+    #[test]
+    fn traverse_deep_tree() {
+        let depth = 200_000;
+        let sgf = format!("(;C[root]{})", ";B[aa]".repeat(depth));
+        let node = parse(&sgf).unwrap().remove(0);
+        let (path, _) = node
+            .traverse(super::TraversalOrder::DepthFirst)
+            .last()
+            .unwrap();
+        assert_eq!(path.depth(), depth);
+        assert!(path.to_node_path().iter().all(|&index| index == 0));
+    }
+
+    #[test]
+    fn deep_tree_clone_compare_and_drop() {
+        let depth = 200_000;
+        let sgf = format!("(;C[root]{})", ";B[aa]".repeat(depth));
+        let mut node = parse(&sgf).unwrap().remove(0);
+        let copy = node.clone();
+        assert!(copy == node);
+        assert!(format!("{:?}", copy).ends_with("is_root: false }], is_root: true }"));
+        let mut leaf = &mut node;
+        while !leaf.children.is_empty() {
+            leaf = &mut leaf.children[0];
+        }
+        leaf.properties.clear();
+        assert!(copy != node);
+        drop(node);
+        assert!(copy.is_root());
+        assert_eq!(copy.main_variation().count(), depth + 1);
+        assert!(copy.main_variation().skip(1).all(|node| !node.is_root()));
+    }
+
+    #[test]
+    fn debug_matches_derived_shape() {
+        let node = parse("(;SZ[9](;B[aa])(;B[bb]))").unwrap().remove(0);
+        assert_eq!(
+            format!("{:?}", node),
+            "SgfNode { properties: [SZ((9, 9))], children: [\
+             SgfNode { properties: [B(Move(Point { x: 0, y: 0 }))], children: [], is_root: false }, \
+             SgfNode { properties: [B(Move(Point { x: 1, y: 1 }))], children: [], is_root: false }\
+             ], is_root: true }"
+        );
+        let leaf = &node.children[0];
+        assert_eq!(
+            format!("{:#?}", leaf),
+            "SgfNode {\n    properties: [\n        B(Move(Point { x: 0, y: 0 })),\n    ],\n    \
+             children: [],\n    is_root: false,\n}"
+        );
+    }
 
     #[test]
     fn validate_sample_sgf_valid() {
@@ -360,6 +2022,105 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn error_context_is_bounded() {
+        let comment = "é".repeat(1000);
+        let sgf = format!("(;GM[1]C[{}]C[Two])", comment);
+        let node = parse(&sgf).unwrap().into_iter().next().unwrap();
+        let error = node.validate().unwrap_err();
+        let context = error.context();
+        assert!(matches!(error, InvalidNodeError::RepeatedIdentifier(_)));
+        assert!(context.len() <= 203);
+        assert!(context.starts_with("GM[1]C[éé"));
+        assert!(context.ends_with("..."));
+        assert!(error.to_string().ends_with("..."));
+    }
+
+    #[test]
+    fn validate_parallel_matches_thread_counts() {
+        let sgf = "(;SZ[9]PB[Black](;B[aa]PW[White];W[bb]C[a]C[b])(;B[cc]W[dd];W[ee]DM[1]UC[1]))";
+        let node = parse(sgf).unwrap().pop().unwrap();
+        let expected = node.validate_parallel(1);
+        assert_eq!(
+            expected
+                .iter()
+                .map(|(path, _)| path.to_string())
+                .collect::<Vec<_>>(),
+            vec!["", "0.0", "1", "1.0"]
+        );
+        for threads in 2..8 {
+            assert_eq!(node.validate_parallel(threads), expected);
+        }
+    }
+
+    #[test]
+    fn validate_grafted_root() {
+        let mut node = parse("(;SZ[9]C[Some comment])").unwrap().pop().unwrap();
+        let mut other = parse("(;GM[1])").unwrap().pop().unwrap();
+        other.set_root(true);
+        node.children.push(other);
+        assert!(matches!(
+            node.validate(),
+            Err(InvalidNodeError::UnexpectedRootProperties(_))
+        ));
+    }
+
+    #[test]
+    fn validate_zero_length_arrow() {
+        let point = crate::go::Point { x: 3, y: 3 };
+        let prop = crate::go::Prop::AR(vec![(point, point)].into_iter().collect());
+        let node = crate::SgfNode::new(vec![prop], vec![], true);
+        assert!(matches!(
+            node.validate(),
+            Err(InvalidNodeError::InvalidProperty(_))
+        ));
+    }
+
+    #[test]
+    fn validate_conflicting_setup() {
+        let mut node = parse("(;AB[aa]AW[cc];AE[dd]AW[dd]AB[ee])")
+            .unwrap()
+            .remove(0);
+        assert!(matches!(
+            node.validate(),
+            Err(InvalidNodeError::ConflictingSetup(_))
+        ));
+        assert_eq!(node.resolve_setup_conflicts(), 1);
+        assert_eq!(node.serialize(), "(;AB[aa]AW[cc];AW[dd]AB[ee])");
+        assert!(node.validate().is_ok());
+
+        let mut node = match crate::parse("(;GM[37];AW[x1]AB[x1])").unwrap().remove(0) {
+            crate::GameTree::Unknown(node) => node,
+            _ => unreachable!(),
+        };
+        assert!(node.validate().is_err());
+        assert_eq!(node.resolve_setup_conflicts(), 1);
+        assert!(node.validate().is_ok());
+    }
+
+    #[test]
+    fn validate_repeated_label() {
+        let mut node = parse("(;B[aa];LB[aa:1][bb:2][aa:one][aa:22])")
+            .unwrap()
+            .remove(0);
+        assert!(matches!(
+            node.validate(),
+            Err(InvalidNodeError::RepeatedLabel(_))
+        ));
+        assert_eq!(node.long_labels(2).len(), 1);
+        assert_eq!(node.dedupe_labels(), 2);
+        assert!(node.validate().is_ok());
+        assert!(node.long_labels(2).is_empty());
+        let expected = parse("(;B[aa];LB[aa:1][bb:2])").unwrap().remove(0);
+        assert_eq!(node, expected);
+    }
+
+    #[test]
+    fn diff_identical_properties() {
+        let node = parse("(;SZ[9]C[Some comment])").unwrap().pop().unwrap();
+        assert!(node.diff_properties(&node.clone()).is_empty());
+    }
+
     #[test]
     fn validate_unexpected_game_info() {
         let sgf = "(;SZ[9]KM[3.5]C[Some comment];HA[3])";
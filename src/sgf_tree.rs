@@ -0,0 +1,265 @@
+use crate::{SgfNode, SgfProp};
+
+/// A handle to a node in an [`SgfTree`].
+///
+/// Ids are only meaningful for the tree which returned them.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct NodeId(usize);
+
+/// A game tree stored as a flat arena of nodes addressed by [`NodeId`].
+///
+/// Unlike [`SgfNode`], every node knows its parent, nodes can be referred to by id while the
+/// tree is being modified, and nodes are stored contiguously. This suits analysis workloads which
+/// walk up and down the tree a lot. Use [`SgfTree::from_node`] and [`SgfTree::to_node`] to
+/// convert to and from [`SgfNode`].
+///
+/// Methods taking a [`NodeId`] panic if the id doesn't belong to the tree.
+///
+/// # Examples
+/// ```
+/// use sgf_parse::go::parse;
+/// use sgf_parse::SgfTree;
+///
+/// let node = parse("(;SZ[9];B[ee](;W[ce])(;W[gc]))").unwrap().remove(0);
+/// let mut tree = SgfTree::from_node(&node);
+/// let first_move = tree.children(tree.root())[0];
+/// let variation = tree.children(first_move)[1];
+/// assert_eq!(tree.parent(variation), Some(first_move));
+/// assert_eq!(tree.depth(variation), 2);
+///
+/// // Copy the variation so it also follows the root.
+/// tree.copy_subtree(variation, tree.root());
+/// assert_eq!(
+///     tree.to_node(tree.root()).serialize(),
+///     "(;SZ[9:9](;B[ee](;W[ce])(;W[gc]))(;W[gc]))"
+/// );
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct SgfTree<Prop: SgfProp> {
+    nodes: Vec<TreeNode<Prop>>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct TreeNode<Prop: SgfProp> {
+    properties: Vec<Prop>,
+    parent: Option<NodeId>,
+    children: Vec<NodeId>,
+}
+
+impl<Prop: SgfProp> SgfTree<Prop> {
+    /// Returns a new tree with a single root node with the provided properties.
+    pub fn new(properties: Vec<Prop>) -> Self {
+        Self {
+            nodes: vec![TreeNode {
+                properties,
+                parent: None,
+                children: vec![],
+            }],
+        }
+    }
+
+    /// Returns a tree containing a copy of the provided node and its descendants.
+    ///
+    /// Nodes are stored in depth-first order.
+    pub fn from_node(node: &SgfNode<Prop>) -> Self {
+        let mut tree = Self::new(node.properties.clone());
+        tree.nodes.reserve(node.children.len());
+        let mut stack: Vec<_> = node
+            .children
+            .iter()
+            .rev()
+            .map(|child| (child, tree.root()))
+            .collect();
+        while let Some((node, parent)) = stack.pop() {
+            let id = tree.add_child(parent, node.properties.clone());
+            stack.extend(node.children.iter().rev().map(|child| (child, id)));
+        }
+        tree
+    }
+
+    /// Returns an [`SgfNode`] copy of the node with the provided id and its descendants.
+    ///
+    /// The copy is a root node if `id` is the tree's root.
+    pub fn to_node(&self, id: NodeId) -> SgfNode<Prop> {
+        // Nodes in depth-first order.
+        let mut ids = vec![];
+        let mut stack = vec![id];
+        while let Some(id) = stack.pop() {
+            ids.push(id);
+            stack.extend(self.children(id).iter().rev());
+        }
+
+        // Build nodes from the leaves up. Every node's children come after it, so they're all
+        // complete by the time it's reached.
+        let mut built: Vec<Option<SgfNode<Prop>>> = vec![None; self.nodes.len()];
+        for &id in ids.iter().rev() {
+            let children = self
+                .children(id)
+                .iter()
+                .map(|child| built[child.0].take().unwrap())
+                .collect();
+            built[id.0] = Some(SgfNode::new(
+                self.properties(id).to_vec(),
+                children,
+                id == self.root(),
+            ));
+        }
+        built[id.0].take().unwrap()
+    }
+
+    /// Returns the id of the root node.
+    pub fn root(&self) -> NodeId {
+        NodeId(0)
+    }
+
+    /// Returns the number of nodes in the tree.
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Returns `false`, since a tree always has a root node.
+    pub fn is_empty(&self) -> bool {
+        false
+    }
+
+    /// Returns the properties of a node.
+    pub fn properties(&self, id: NodeId) -> &[Prop] {
+        &self.nodes[id.0].properties
+    }
+
+    /// Returns a mutable reference to the properties of a node.
+    pub fn properties_mut(&mut self, id: NodeId) -> &mut Vec<Prop> {
+        &mut self.nodes[id.0].properties
+    }
+
+    /// Returns the property of a node with the provided identifier (if present).
+    pub fn get_property(&self, id: NodeId, identifier: &str) -> Option<&Prop> {
+        self.properties(id)
+            .iter()
+            .find(|prop| prop.identifier_str() == identifier)
+    }
+
+    /// Returns the parent of a node, or `None` for the root.
+    pub fn parent(&self, id: NodeId) -> Option<NodeId> {
+        self.nodes[id.0].parent
+    }
+
+    /// Returns the children of a node.
+    pub fn children(&self, id: NodeId) -> &[NodeId] {
+        &self.nodes[id.0].children
+    }
+
+    /// Returns the number of nodes between a node and the root.
+    pub fn depth(&self, id: NodeId) -> usize {
+        self.ancestors(id).count()
+    }
+
+    /// Returns an iterator over the ancestors of a node, starting with its parent.
+    pub fn ancestors(&self, id: NodeId) -> impl Iterator<Item = NodeId> + '_ {
+        std::iter::successors(self.parent(id), move |&id| self.parent(id))
+    }
+
+    /// Returns the ids of the nodes from the root to the provided node, inclusive.
+    pub fn path_to(&self, id: NodeId) -> Vec<NodeId> {
+        let mut path: Vec<_> = std::iter::once(id).chain(self.ancestors(id)).collect();
+        path.reverse();
+        path
+    }
+
+    /// Adds a new last child with the provided properties to a node and returns its id.
+    pub fn add_child(&mut self, parent: NodeId, properties: Vec<Prop>) -> NodeId {
+        let id = NodeId(self.nodes.len());
+        self.nodes.push(TreeNode {
+            properties,
+            parent: Some(parent),
+            children: vec![],
+        });
+        self.nodes[parent.0].children.push(id);
+        id
+    }
+
+    /// Copies a node and its descendants to be the new last child of `parent`, returning the id
+    /// of the copy.
+    ///
+    /// Copying a subtree into itself only copies the nodes present before the call.
+    pub fn copy_subtree(&mut self, source: NodeId, parent: NodeId) -> NodeId {
+        // Nodes to copy in depth-first order along with their parent's index, collected first so
+        // the copies aren't copied too.
+        let mut nodes = vec![];
+        let mut stack = vec![(source, usize::MAX)];
+        while let Some((id, parent)) = stack.pop() {
+            let index = nodes.len();
+            nodes.push((id, parent));
+            stack.extend(self.children(id).iter().rev().map(|&child| (child, index)));
+        }
+
+        let mut copies: Vec<NodeId> = Vec::with_capacity(nodes.len());
+        for (id, parent_index) in nodes {
+            let copy_parent = copies.get(parent_index).copied().unwrap_or(parent);
+            copies.push(self.add_child(copy_parent, self.properties(id).to_vec()));
+        }
+        copies[0]
+    }
+}
+
+impl<Prop: SgfProp> From<&SgfNode<Prop>> for SgfTree<Prop> {
+    fn from(node: &SgfNode<Prop>) -> Self {
+        Self::from_node(node)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SgfTree;
+    use crate::go::parse;
+
+    #[test]
+    fn round_trip() {
+        let sgf = "(;GM[1]SZ[9](;B[aa](;W[bb])(;W[cc];B[dd]))(;B[ee]C[Hi]))";
+        let node = parse(sgf).unwrap().remove(0);
+        let tree = SgfTree::from_node(&node);
+        assert_eq!(tree.len(), 6);
+        assert_eq!(tree.to_node(tree.root()), node);
+
+        let subtree = tree.children(tree.root())[0];
+        let expected = &node.children[0];
+        let copy = tree.to_node(subtree);
+        assert!(!copy.is_root());
+        assert_eq!(copy.serialize(), expected.serialize());
+    }
+
+    #[test]
+    fn parent_links() {
+        let node = parse("(;B[aa];W[bb](;B[cc])(;B[dd];W[ee]))")
+            .unwrap()
+            .remove(0);
+        let tree = SgfTree::from_node(&node);
+        let mut leaf = tree.root();
+        while let Some(&child) = tree.children(leaf).last() {
+            leaf = child;
+        }
+        assert_eq!(tree.depth(leaf), 3);
+        let path = tree.path_to(leaf);
+        assert_eq!(path.len(), 4);
+        assert_eq!(path[0], tree.root());
+        for pair in path.windows(2) {
+            assert_eq!(tree.parent(pair[1]), Some(pair[0]));
+            assert!(tree.children(pair[0]).contains(&pair[1]));
+        }
+        assert!(tree.get_property(leaf, "W").is_some());
+    }
+
+    #[test]
+    fn copy_subtree_into_itself() {
+        let node = parse("(;C[root];B[aa];W[bb])").unwrap().remove(0);
+        let mut tree = SgfTree::from_node(&node);
+        let first = tree.children(tree.root())[0];
+        let second = tree.children(first)[0];
+        tree.copy_subtree(first, first);
+        tree.copy_subtree(tree.root(), second);
+        assert_eq!(
+            tree.to_node(tree.root()).serialize(),
+            "(;C[root];B[aa](;W[bb];C[root];B[aa](;W[bb])(;B[aa];W[bb]))(;B[aa];W[bb]))"
+        );
+    }
+}
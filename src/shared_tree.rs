@@ -0,0 +1,363 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::sgf_node::canonical_values;
+use crate::{SgfNode, SgfProp};
+
+/// A node in a game tree whose subtrees may be shared with other trees.
+///
+/// Children are reference counted, so cloning a `SharedNode` or attaching the same subtree to
+/// many parents is cheap. Subtrees are copied on write: [`SharedNode::child_mut`] only clones a
+/// child if it's shared. Use [`SubtreeInterner`] to build trees where identical subtrees share
+/// memory, and [`SharedNode::to_node`] to get back an ordinary [`SgfNode`].
+///
+/// Dropping, comparing and debug formatting nodes don't recurse, so even very deep trees can be
+/// handled safely.
+///
+/// # Examples
+/// ```
+/// use std::sync::Arc;
+/// use sgf_parse::go::parse;
+/// use sgf_parse::SharedNode;
+///
+/// let continuation = Arc::new(SharedNode::from_node(&parse("(;B[dd];W[pp])").unwrap()[0]));
+/// let mut games: Vec<SharedNode<_>> = vec![];
+/// for opening in ["(;GM[1]C[First])", "(;GM[1]C[Second])"].iter() {
+///     let mut game = SharedNode::from_node(&parse(opening).unwrap()[0]);
+///     game.children.push(continuation.clone());
+///     games.push(game);
+/// }
+/// assert!(Arc::ptr_eq(&games[0].children[0], &games[1].children[0]));
+///
+/// // Editing one game's copy leaves the other untouched.
+/// games[0].child_mut(0).unwrap().properties.clear();
+/// assert_eq!(games[0].to_node(true).serialize(), "(;GM[1]C[First];;W[pp])");
+/// assert_eq!(games[1].to_node(true).serialize(), "(;GM[1]C[Second];B[dd];W[pp])");
+/// ```
+#[derive(Clone)]
+pub struct SharedNode<Prop: SgfProp> {
+    /// The node's properties.
+    pub properties: Vec<Prop>,
+    /// The node's children, which may be shared with other nodes.
+    pub children: Vec<Arc<Self>>,
+}
+
+impl<Prop: SgfProp> Drop for SharedNode<Prop> {
+    // Drop unshared descendants iteratively, since long games would overflow the stack otherwise.
+    fn drop(&mut self) {
+        let mut children = std::mem::take(&mut self.children);
+        while let Some(child) = children.pop() {
+            if let Ok(mut child) = Arc::try_unwrap(child) {
+                children.append(&mut child.children);
+            }
+        }
+    }
+}
+
+impl<Prop: SgfProp> PartialEq for SharedNode<Prop> {
+    fn eq(&self, other: &Self) -> bool {
+        let mut stack = vec![(self, other)];
+        while let Some((node, other)) = stack.pop() {
+            if std::ptr::eq(node, other) {
+                continue;
+            }
+            if node.properties != other.properties || node.children.len() != other.children.len() {
+                return false;
+            }
+            stack.extend(
+                node.children
+                    .iter()
+                    .zip(&other.children)
+                    .map(|(a, b)| (a.as_ref(), b.as_ref())),
+            );
+        }
+        true
+    }
+}
+
+impl<Prop: SgfProp> std::fmt::Debug for SharedNode<Prop> {
+    // Written out by hand in the same shape as the derived output, to avoid recursion.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        enum Step<'a, Prop: SgfProp> {
+            Open(&'a SharedNode<Prop>, usize, bool),
+            Close(&'a SharedNode<Prop>, usize),
+        }
+
+        let pretty = f.alternate();
+        let indent = |depth: usize| " ".repeat(8 * depth);
+        let mut stack = vec![Step::Open(self, 0, true)];
+        while let Some(step) = stack.pop() {
+            match step {
+                Step::Open(node, depth, first) => {
+                    if pretty {
+                        if depth > 0 {
+                            write!(f, "\n{}", indent(depth))?;
+                        }
+                        write!(f, "SharedNode {{\n{}    properties: [", indent(depth))?;
+                        for prop in &node.properties {
+                            write!(f, "\n{}        {:?},", indent(depth), prop)?;
+                        }
+                        if !node.properties.is_empty() {
+                            write!(f, "\n{}    ", indent(depth))?;
+                        }
+                        write!(f, "],\n{}    children: [", indent(depth))?;
+                    } else {
+                        if !first {
+                            f.write_str(", ")?;
+                        }
+                        write!(
+                            f,
+                            "SharedNode {{ properties: {:?}, children: [",
+                            node.properties
+                        )?;
+                    }
+                    stack.push(Step::Close(node, depth));
+                    for (i, child) in node.children.iter().enumerate().rev() {
+                        stack.push(Step::Open(child, depth + 1, i == 0));
+                    }
+                }
+                Step::Close(node, depth) => {
+                    if pretty {
+                        if !node.children.is_empty() {
+                            write!(f, "\n{}    ", indent(depth))?;
+                        }
+                        write!(f, "],\n{}}}", indent(depth))?;
+                        if depth > 0 {
+                            f.write_str(",")?;
+                        }
+                    } else {
+                        f.write_str("] }")?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<Prop: SgfProp> SharedNode<Prop> {
+    /// Returns a new node.
+    pub fn new(properties: Vec<Prop>, children: Vec<Arc<Self>>) -> Self {
+        Self {
+            properties,
+            children,
+        }
+    }
+
+    /// Returns a shared copy of the provided node and its descendants.
+    ///
+    /// Identical subtrees within the node are shared.
+    pub fn from_node(node: &SgfNode<Prop>) -> Self {
+        let mut interner = SubtreeInterner::new();
+        let children = node
+            .children
+            .iter()
+            .map(|child| interner.intern(child))
+            .collect();
+        Self::new(node.properties.clone(), children)
+    }
+
+    /// Returns a mutable reference to a child, cloning it first if it's shared.
+    ///
+    /// Returns `None` if there is no child with the provided index.
+    pub fn child_mut(&mut self, index: usize) -> Option<&mut Self> {
+        self.children.get_mut(index).map(Arc::make_mut)
+    }
+
+    /// Returns an ordinary [`SgfNode`] copy of this node and its descendants.
+    ///
+    /// Shared subtrees are copied into each place they appear.
+    pub fn to_node(&self, is_root: bool) -> SgfNode<Prop> {
+        // Nodes in depth-first order along with their parent's index.
+        let mut nodes: Vec<(&Self, usize)> = vec![];
+        let mut stack = vec![(self, usize::MAX)];
+        while let Some((node, parent)) = stack.pop() {
+            let index = nodes.len();
+            nodes.push((node, parent));
+            for child in node.children.iter().rev() {
+                stack.push((child, index));
+            }
+        }
+
+        // Build nodes from the leaves up. Walking backwards visits each parent's children last to
+        // first, so they're reversed once complete.
+        let mut built: Vec<Option<SgfNode<Prop>>> = vec![None; nodes.len()];
+        let mut children: Vec<Vec<SgfNode<Prop>>> = vec![vec![]; nodes.len()];
+        for (index, (node, parent)) in nodes.iter().enumerate().rev() {
+            let mut node_children = std::mem::take(&mut children[index]);
+            node_children.reverse();
+            let node = SgfNode::new(node.properties.clone(), node_children, false);
+            if *parent == usize::MAX {
+                built[index] = Some(node);
+            } else {
+                children[*parent].push(node);
+            }
+        }
+        let mut node = built[0].take().unwrap();
+        node.set_root(is_root);
+        node
+    }
+}
+
+/// Converts [`SgfNode`] subtrees to [`SharedNode`] subtrees, sharing identical subtrees.
+///
+/// Every subtree interned by the same interner is compared with those seen before, so identical
+/// continuations attached to many games are only stored once.
+///
+/// # Examples
+/// ```
+/// use std::sync::Arc;
+/// use sgf_parse::go::parse;
+/// use sgf_parse::SubtreeInterner;
+///
+/// let mut interner = SubtreeInterner::new();
+/// let first = interner.intern(&parse("(;B[dd];W[pp];B[dp])").unwrap()[0]);
+/// let second = interner.intern(&parse("(;B[pd];W[pp];B[dp])").unwrap()[0]);
+/// assert!(!Arc::ptr_eq(&first, &second));
+/// assert!(Arc::ptr_eq(&first.children[0], &second.children[0]));
+/// assert_eq!(interner.len(), 4);
+/// ```
+#[derive(Debug)]
+pub struct SubtreeInterner<Prop: SgfProp> {
+    subtrees: HashMap<SubtreeKey, Arc<SharedNode<Prop>>>,
+}
+
+// A subtree's properties as identifiers and values, and the addresses of its children.
+type SubtreeKey = (Vec<(String, Vec<String>)>, Vec<usize>);
+
+impl<Prop: SgfProp> Default for SubtreeInterner<Prop> {
+    fn default() -> Self {
+        Self {
+            subtrees: HashMap::new(),
+        }
+    }
+}
+
+impl<Prop: SgfProp> SubtreeInterner<Prop> {
+    /// Returns a new empty interner.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns a shared copy of the provided subtree, reusing any identical subtrees.
+    pub fn intern(&mut self, node: &SgfNode<Prop>) -> Arc<SharedNode<Prop>> {
+        // Nodes in depth-first order along with their parent's index.
+        let mut nodes: Vec<(&SgfNode<Prop>, usize)> = vec![];
+        let mut stack = vec![(node, usize::MAX)];
+        while let Some((node, parent)) = stack.pop() {
+            let index = nodes.len();
+            nodes.push((node, parent));
+            for child in node.children.iter().rev() {
+                stack.push((child, index));
+            }
+        }
+
+        let mut children: Vec<Vec<Arc<SharedNode<Prop>>>> = vec![vec![]; nodes.len()];
+        let mut root = None;
+        for (index, (node, parent)) in nodes.iter().enumerate().rev() {
+            let mut node_children = std::mem::take(&mut children[index]);
+            node_children.reverse();
+            let shared = self.intern_node(node.properties.clone(), node_children);
+            if *parent == usize::MAX {
+                root = Some(shared);
+            } else {
+                children[*parent].push(shared);
+            }
+        }
+        root.unwrap()
+    }
+
+    /// Returns the number of distinct subtrees stored.
+    pub fn len(&self) -> usize {
+        self.subtrees.len()
+    }
+
+    /// Returns `true` if no subtrees are stored.
+    pub fn is_empty(&self) -> bool {
+        self.subtrees.is_empty()
+    }
+
+    fn intern_node(
+        &mut self,
+        properties: Vec<Prop>,
+        children: Vec<Arc<SharedNode<Prop>>>,
+    ) -> Arc<SharedNode<Prop>> {
+        // Children are already interned, so identical children are the same allocation.
+        let key = (
+            properties
+                .iter()
+                .map(|prop| (prop.identifier_str().to_string(), canonical_values(prop)))
+                .collect(),
+            children
+                .iter()
+                .map(|child| Arc::as_ptr(child) as usize)
+                .collect(),
+        );
+        match self.subtrees.get(&key) {
+            Some(existing) if existing.properties == properties => existing.clone(),
+            // Values should identify properties, but don't share if they don't.
+            Some(_) => Arc::new(SharedNode::new(properties, children)),
+            None => {
+                let shared = Arc::new(SharedNode::new(properties, children));
+                self.subtrees.insert(key, shared.clone());
+                shared
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::{SharedNode, SubtreeInterner};
+    use crate::go::parse;
+
+    #[test]
+    fn round_trip() {
+        let sgf = "(;GM[1]SZ[9](;B[aa](;W[bb])(;W[cc];B[dd]))(;B[ee]C[Hi]))";
+        let node = parse(sgf).unwrap().remove(0);
+        let shared = SharedNode::from_node(&node);
+        assert_eq!(shared.to_node(true), node);
+    }
+
+    #[test]
+    fn shares_identical_subtrees() {
+        let mut interner = SubtreeInterner::new();
+        let tails: Vec<_> = ["(;B[aa];W[bb];B[cc])", "(;B[dd];W[bb];B[cc])"]
+            .iter()
+            .map(|sgf| interner.intern(&parse(sgf).unwrap()[0]))
+            .collect();
+        let again = interner.intern(&parse("(;B[aa];W[bb];B[cc])").unwrap()[0]);
+        assert!(Arc::ptr_eq(&tails[0], &again));
+        assert!(Arc::ptr_eq(&tails[0].children[0], &tails[1].children[0]));
+        assert_eq!(interner.len(), 4);
+
+        // Identical properties with different children aren't shared.
+        let other = interner.intern(&parse("(;B[aa];W[bb])").unwrap()[0]);
+        assert!(!Arc::ptr_eq(&tails[0], &other));
+    }
+
+    #[test]
+    fn shares_sets_in_any_order() {
+        let mut interner = SubtreeInterner::new();
+        let first = interner.intern(&parse("(;AB[aa][bb][cc][dd][ee][ff])").unwrap()[0]);
+        let second = interner.intern(&parse("(;AB[ff][ee][dd][cc][bb][aa])").unwrap()[0]);
+        assert!(Arc::ptr_eq(&first, &second));
+    }
+
+    #[test]
+    fn deep_tree_compare_format_and_drop() {
+        let depth = 200_000;
+        let sgf = format!("(;C[root]{})", ";B[aa]".repeat(depth));
+        let node = parse(&sgf).unwrap().remove(0);
+        // Separate interners so the trees share nothing.
+        let shared = SharedNode::from_node(&node);
+        let mut other = SharedNode::from_node(&node);
+        drop(node);
+        assert!(shared == other);
+        assert!(format!("{:?}", shared).starts_with("SharedNode { properties: [C("));
+        other.properties.clear();
+        assert!(shared != other);
+    }
+}
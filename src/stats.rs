@@ -0,0 +1,248 @@
+use std::collections::BTreeMap;
+
+use crate::{GameTree, SgfNode, SgfProp};
+
+/// Counts of the unknown and invalid properties in a collection of game trees.
+///
+/// Returned by [`stats`]. Reports from two crawls of the same sources can be compared with
+/// [`PropertyStats::compare`] to notice when servers start emitting new properties or new kinds
+/// of broken values.
+///
+/// Custom games are counted in [`PropertyStats::gametrees`], but their properties aren't
+/// inspected.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PropertyStats {
+    /// Number of game trees included in the report.
+    pub gametrees: usize,
+    /// Number of uses of each property identifier not defined for the game.
+    pub unknown_properties: BTreeMap<String, usize>,
+    /// Number of uses of each pattern of invalid values for properties defined for the game.
+    pub invalid_values: BTreeMap<InvalidValuePattern, usize>,
+}
+
+/// The shape of the values of an invalid property.
+///
+/// Values are reduced to a pattern so that, for instance, `KM[6,5]` and `KM[7,5]` are counted
+/// together. Runs of ASCII digits become `9`, runs of lowercase ASCII letters become `a`, runs of
+/// uppercase ASCII letters become `A`, and everything else is kept as is.
+///
+/// # Examples
+/// ```
+/// use sgf_parse::InvalidValuePattern;
+///
+/// let pattern = InvalidValuePattern::new("KM", &["6,5".to_string()]);
+/// assert_eq!(pattern.to_string(), "KM[9,9]");
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct InvalidValuePattern {
+    /// The property identifier.
+    pub identifier: String,
+    /// The pattern of each of the property's values.
+    pub values: Vec<String>,
+}
+
+impl InvalidValuePattern {
+    /// Returns the pattern for a property with the provided identifier and raw values.
+    pub fn new(identifier: &str, values: &[String]) -> Self {
+        Self {
+            identifier: identifier.to_string(),
+            values: values.iter().map(|value| value_pattern(value)).collect(),
+        }
+    }
+}
+
+impl std::fmt::Display for InvalidValuePattern {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.identifier)?;
+        for value in self.values.iter() {
+            write!(f, "[{}]", value)?;
+        }
+        Ok(())
+    }
+}
+
+/// Properties and value patterns found in one [`PropertyStats`] report but not in another.
+///
+/// Returned by [`PropertyStats::compare`]. Entries are sorted and include their number of uses
+/// in the newer report.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct StatsDrift {
+    /// Unknown property identifiers which didn't appear in the previous report.
+    pub new_unknown_properties: Vec<(String, usize)>,
+    /// Invalid value patterns which didn't appear in the previous report.
+    pub new_invalid_values: Vec<(InvalidValuePattern, usize)>,
+}
+
+impl StatsDrift {
+    /// Returns `true` if nothing new was found.
+    pub fn is_empty(&self) -> bool {
+        self.new_unknown_properties.is_empty() && self.new_invalid_values.is_empty()
+    }
+}
+
+impl std::fmt::Display for StatsDrift {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_empty() {
+            return f.write_str("No new property quirks");
+        }
+        let lines = self
+            .new_unknown_properties
+            .iter()
+            .map(|(identifier, count)| {
+                format!("new unknown property {} ({} uses)", identifier, count)
+            })
+            .chain(self.new_invalid_values.iter().map(|(pattern, count)| {
+                format!("new invalid values {} ({} uses)", pattern, count)
+            }));
+        for (i, line) in lines.enumerate() {
+            if i > 0 {
+                f.write_str("\n")?;
+            }
+            f.write_str(&line)?;
+        }
+        Ok(())
+    }
+}
+
+impl PropertyStats {
+    /// Returns an empty report.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the properties in a game tree to the report.
+    ///
+    /// This allows building a report from game trees parsed one at a time, for instance with
+    /// [`SgfReader`](crate::SgfReader).
+    pub fn add(&mut self, gametree: &GameTree) {
+        self.gametrees += 1;
+        match gametree {
+            GameTree::GoGame(node) => self.add_node(node),
+            GameTree::Unknown(node) => self.add_node(node),
+            GameTree::Custom(_) => {}
+        }
+    }
+
+    /// Returns the unknown properties and invalid value patterns in `self` which don't appear in
+    /// `previous`.
+    ///
+    /// # Examples
+    /// ```
+    /// use sgf_parse::{parse, stats};
+    ///
+    /// let last_month = stats(&parse("(;GM[1]XX[1]KM[6,5];B[dd])").unwrap());
+    /// let this_month = stats(&parse("(;GM[1]XX[2]YY[]KM[7,5];B[dd]MN[-])").unwrap());
+    /// let drift = this_month.compare(&last_month);
+    /// assert_eq!(
+    ///     drift.to_string(),
+    ///     "new unknown property YY (1 uses)\nnew invalid values MN[-] (1 uses)"
+    /// );
+    /// ```
+    pub fn compare(&self, previous: &Self) -> StatsDrift {
+        StatsDrift {
+            new_unknown_properties: self
+                .unknown_properties
+                .iter()
+                .filter(|(identifier, _)| !previous.unknown_properties.contains_key(*identifier))
+                .map(|(identifier, count)| (identifier.clone(), *count))
+                .collect(),
+            new_invalid_values: self
+                .invalid_values
+                .iter()
+                .filter(|(pattern, _)| !previous.invalid_values.contains_key(*pattern))
+                .map(|(pattern, count)| (pattern.clone(), *count))
+                .collect(),
+        }
+    }
+
+    fn add_node<Prop: SgfProp>(&mut self, node: &SgfNode<Prop>) {
+        let mut stack = vec![node];
+        while let Some(node) = stack.pop() {
+            for prop in node.properties() {
+                if prop.is_unknown() {
+                    *self
+                        .unknown_properties
+                        .entry(prop.identifier_str().to_string())
+                        .or_insert(0) += 1;
+                } else if prop.is_invalid() {
+                    let values = prop.raw_values();
+                    let pattern = InvalidValuePattern::new(prop.identifier_str(), &values);
+                    *self.invalid_values.entry(pattern).or_insert(0) += 1;
+                }
+            }
+            stack.extend(node.children());
+        }
+    }
+}
+
+/// Returns counts of the unknown and invalid properties in the game trees.
+///
+/// # Examples
+/// ```
+/// use sgf_parse::{parse, stats};
+///
+/// let gametrees = parse("(;GM[1]XX[a];B[dd]XX[b])(;GM[1]SZ[big])").unwrap();
+/// let stats = stats(&gametrees);
+/// assert_eq!(stats.gametrees, 2);
+/// assert_eq!(stats.unknown_properties["XX"], 2);
+/// let (pattern, count) = stats.invalid_values.iter().next().unwrap();
+/// assert_eq!((pattern.to_string(), *count), ("SZ[a]".to_string(), 1));
+/// ```
+pub fn stats(gametrees: &[GameTree]) -> PropertyStats {
+    let mut stats = PropertyStats::new();
+    for gametree in gametrees {
+        stats.add(gametree);
+    }
+    stats
+}
+
+fn value_pattern(value: &str) -> String {
+    let mut pattern = String::new();
+    let mut last_class = None;
+    for c in value.chars() {
+        let class = if c.is_ascii_digit() {
+            Some('9')
+        } else if c.is_ascii_lowercase() {
+            Some('a')
+        } else if c.is_ascii_uppercase() {
+            Some('A')
+        } else {
+            None
+        };
+        match class {
+            Some(class) if last_class == Some(class) => {}
+            Some(class) => pattern.push(class),
+            None => pattern.push(c),
+        }
+        last_class = class;
+    }
+    pattern
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{stats, value_pattern, InvalidValuePattern};
+    use crate::parse;
+
+    #[test]
+    fn value_patterns() {
+        assert_eq!(value_pattern("19x19"), "9a9");
+        assert_eq!(value_pattern("W+Resign"), "A+Aa");
+        assert_eq!(value_pattern("6.5 pts"), "9.9 a");
+        assert_eq!(value_pattern(""), "");
+    }
+
+    #[test]
+    fn compare_reports() {
+        let previous = stats(&parse("(;GM[1]XX[1]SZ[19x19];B[dd])").unwrap());
+        let current = stats(&parse("(;GM[1]SZ[9x9]XX[1];B[dd]YY[](;W[z1])(;W[z1]))").unwrap());
+        assert_eq!(current.gametrees, 1);
+        let drift = current.compare(&previous);
+        assert_eq!(drift.new_unknown_properties, vec![("YY".to_string(), 1)]);
+        assert_eq!(
+            drift.new_invalid_values,
+            vec![(InvalidValuePattern::new("W", &["z1".to_string()]), 2)]
+        );
+        assert!(previous.compare(&previous).is_empty());
+    }
+}
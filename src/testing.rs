@@ -0,0 +1,133 @@
+//! Utilities for testing code which produces SGF.
+
+#[cfg(feature = "fixtures")]
+pub mod fixtures;
+pub mod generate;
+
+use crate::{parse, GameTree, SgfNode, SgfProp};
+
+/// Returns a description of the first structural difference between two SGF collections.
+///
+/// Returns `None` if the collections are equivalent. Property order within nodes is ignored.
+/// Nodes are identified by their game index and the path of child indices from the root.
+///
+/// # Examples
+/// ```
+/// use sgf_parse::testing::sgf_diff;
+///
+/// assert_eq!(sgf_diff("(;B[de]C[Hi];W[ee])", "(;C[Hi]B[de];W[ee])"), None);
+/// assert_eq!(
+///     sgf_diff("(;B[de];W[ee])", "(;B[de];W[ef])").unwrap(),
+///     "game 0, node [0]: W changed from W[ee] to W[ef]"
+/// );
+/// ```
+pub fn sgf_diff(left: &str, right: &str) -> Option<String> {
+    let left = match parse(left) {
+        Ok(gametrees) => gametrees,
+        Err(e) => return Some(format!("left failed to parse: {}", e)),
+    };
+    let right = match parse(right) {
+        Ok(gametrees) => gametrees,
+        Err(e) => return Some(format!("right failed to parse: {}", e)),
+    };
+    if left.len() != right.len() {
+        return Some(format!(
+            "game count differs: {} != {}",
+            left.len(),
+            right.len()
+        ));
+    }
+    for (i, (left, right)) in left.iter().zip(right.iter()).enumerate() {
+        let difference = match (left, right) {
+            (GameTree::GoGame(left), GameTree::GoGame(right)) => node_diff(left, right),
+            (GameTree::Unknown(left), GameTree::Unknown(right)) => node_diff(left, right),
+            _ => Some(format!(
+                "game type differs: {} != {}",
+                left.gametype(),
+                right.gametype()
+            )),
+        };
+        if let Some(difference) = difference {
+            return Some(format!("game {}, {}", i, difference));
+        }
+    }
+    None
+}
+
+/// Asserts that two SGF collections are structurally equal.
+///
+/// # Panics
+/// Panics with the first difference found by [`sgf_diff`] if the collections differ.
+///
+/// # Examples
+/// ```
+/// use sgf_parse::testing::assert_sgf_eq;
+///
+/// assert_sgf_eq("(;SZ[19]B[de])", "(;B[de]SZ[19:19])");
+/// ```
+#[track_caller]
+pub fn assert_sgf_eq(left: &str, right: &str) {
+    if let Some(difference) = sgf_diff(left, right) {
+        panic!(
+            "SGF mismatch: {}\n  left: {}\n right: {}",
+            difference, left, right
+        );
+    }
+}
+
+fn node_diff<Prop: SgfProp>(left: &SgfNode<Prop>, right: &SgfNode<Prop>) -> Option<String> {
+    let mut stack = vec![(left, right, vec![])];
+    while let Some((left, right, path)) = stack.pop() {
+        let diff = left.diff_properties(right);
+        let difference = if let Some((old, new)) = diff.changed.first() {
+            Some(format!(
+                "{} changed from {} to {}",
+                old.identifier(),
+                old,
+                new
+            ))
+        } else if let Some(prop) = diff.removed.first() {
+            Some(format!("{} missing from right", prop))
+        } else if let Some(prop) = diff.added.first() {
+            Some(format!("{} missing from left", prop))
+        } else if left.children.len() != right.children.len() {
+            Some(format!(
+                "child count differs: {} != {}",
+                left.children.len(),
+                right.children.len()
+            ))
+        } else {
+            None
+        };
+        if let Some(difference) = difference {
+            return Some(format!("node {:?}: {}", path, difference));
+        }
+        for (i, (left, right)) in left
+            .children
+            .iter()
+            .zip(right.children.iter())
+            .enumerate()
+            .rev()
+        {
+            let mut path = path.clone();
+            path.push(i);
+            stack.push((left, right, path));
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::sgf_diff;
+
+    #[test]
+    fn reports_first_difference_depth_first() {
+        let left = "(;C[Root](;B[aa];W[bb])(;B[cc]))";
+        let right = "(;C[Root](;B[aa];W[bb]C[New])(;B[dd]))";
+        assert_eq!(
+            sgf_diff(left, right).unwrap(),
+            "game 0, node [0, 0]: C[New] missing from left"
+        );
+    }
+}
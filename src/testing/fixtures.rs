@@ -0,0 +1,124 @@
+//! Sample SGF files bundled with the crate for use in test suites.
+//!
+//! These are the inputs `sgf-parse` uses for its own regression tests, exposed so crates built on
+//! it can check their behaviour against the same files. Enable the `fixtures` feature in
+//! `[dev-dependencies]` to use them.
+//!
+//! # Examples
+//! ```
+//! use sgf_parse::parse_bytes;
+//! use sgf_parse::testing::fixtures::{fixtures, Category};
+//!
+//! for fixture in fixtures(Category::Valid) {
+//!     assert!(parse_bytes(fixture.data).is_ok(), "{}", fixture.name);
+//! }
+//! ```
+
+/// How a [`Fixture`] is expected to behave when parsed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Category {
+    /// Parses with strict options and validates.
+    Valid,
+    /// Parses and validates with the default lenient options, but not with strict options.
+    Recoverable,
+    /// Fails to parse or to validate.
+    Invalid,
+}
+
+/// A bundled sample SGF file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Fixture {
+    /// File name of the fixture without its extension.
+    pub name: &'static str,
+    /// Expected behaviour when parsed.
+    pub category: Category,
+    /// Raw contents of the file.
+    pub data: &'static [u8],
+}
+
+macro_rules! fixture {
+    ($path:literal, $name:literal, $category:ident) => {
+        Fixture {
+            name: $name,
+            category: Category::$category,
+            data: include_bytes!(concat!(
+                env!("CARGO_MANIFEST_DIR"),
+                "/resources/test/",
+                $path,
+                ".sgf"
+            )),
+        }
+    };
+}
+
+static FIXTURES: &[Fixture] = &[
+    fixture!("ff4_ex", "ff4_ex", Valid),
+    fixture!("corpus/unknown_game", "unknown_game", Valid),
+    fixture!("corpus/ff3_mixed_case", "ff3_mixed_case", Recoverable),
+    fixture!("corpus/invalid_move", "invalid_move", Invalid),
+    fixture!("corpus/repeated_identifier", "repeated_identifier", Invalid),
+    fixture!("corpus/truncated", "truncated", Invalid),
+];
+
+/// Returns all bundled fixtures.
+pub fn all_fixtures() -> &'static [Fixture] {
+    FIXTURES
+}
+
+/// Returns an iterator over the bundled fixtures in the provided category.
+pub fn fixtures(category: Category) -> impl Iterator<Item = &'static Fixture> {
+    FIXTURES
+        .iter()
+        .filter(move |fixture| fixture.category == category)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::{all_fixtures, Category};
+    use crate::{parse_bytes_with_options, GameTree, ParseOptions};
+
+    fn parses_valid(data: &[u8], options: &ParseOptions) -> bool {
+        match parse_bytes_with_options(data, options) {
+            Ok(gametrees) => gametrees.iter().all(|gametree| match gametree {
+                GameTree::GoGame(node) => node.validate().is_ok(),
+                GameTree::Unknown(node) => node.validate().is_ok(),
+                GameTree::Custom(_) => true,
+            }),
+            Err(_) => false,
+        }
+    }
+
+    #[test]
+    fn categories_match_behaviour() {
+        let strict = ParseOptions {
+            convert_mixed_case_identifiers: false,
+            ..ParseOptions::default()
+        };
+        for fixture in all_fixtures() {
+            let lenient = parses_valid(fixture.data, &ParseOptions::default());
+            let strict = parses_valid(fixture.data, &strict);
+            let expected = match fixture.category {
+                Category::Valid => (true, true),
+                Category::Recoverable => (true, false),
+                Category::Invalid => (false, false),
+            };
+            assert_eq!((lenient, strict), expected, "{}", fixture.name);
+        }
+    }
+
+    #[test]
+    fn corpus_is_bundled() {
+        let mut path = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("resources/test/corpus");
+        let names: HashSet<_> = all_fixtures().iter().map(|fixture| fixture.name).collect();
+        for entry in std::fs::read_dir(path).unwrap() {
+            let path = entry.unwrap().path();
+            if path.extension().is_some_and(|ext| ext == "sgf") {
+                let name = path.file_stem().unwrap().to_str().unwrap().to_string();
+                assert!(names.contains(name.as_str()), "{} not bundled", name);
+            }
+        }
+    }
+}
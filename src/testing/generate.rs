@@ -0,0 +1,124 @@
+//! Deterministic generators for synthetic SGF collections.
+//!
+//! These are used by the crate's benchmarks and are useful as a shared yardstick for
+//! performance work. Output only depends on the arguments, so results are comparable between
+//! runs and machines.
+
+use std::fmt::Write;
+
+use crate::rng::Rng;
+
+// Returns a random SGF point string on a 19x19 board.
+fn random_point(rng: &mut Rng) -> String {
+    let x = (b'a' + rng.below(19) as u8) as char;
+    let y = (b'a' + rng.below(19) as u8) as char;
+    format!("{}{}", x, y)
+}
+
+fn game(seed: u64, moves: usize, mut extra: impl FnMut(&mut Rng, usize, &mut String)) -> String {
+    let mut rng = Rng::new(seed);
+    let mut sgf = "(;GM[1]FF[4]SZ[19]PB[Black]PW[White]KM[6.5]RE[B+R]".to_string();
+    extra(&mut rng, 0, &mut sgf);
+    for i in 1..=moves {
+        let color = if i % 2 == 1 { 'B' } else { 'W' };
+        write!(sgf, ";{}[{}]", color, random_point(&mut rng)).unwrap();
+        extra(&mut rng, i, &mut sgf);
+    }
+    sgf.push(')');
+    sgf
+}
+
+/// Returns a single Go game with the given number of moves.
+///
+/// # Examples
+/// ```
+/// use sgf_parse::go;
+/// use sgf_parse::testing::generate::large_game;
+///
+/// let node = go::parse(&large_game(300)).unwrap().pop().unwrap();
+/// assert_eq!(node.main_variation().count(), 301);
+/// ```
+pub fn large_game(moves: usize) -> String {
+    game(0, moves, |_, _, _| {})
+}
+
+/// Returns a collection of `count` Go games with the given number of moves each.
+pub fn many_small_games(count: usize, moves: usize) -> String {
+    (0..count as u64)
+        .map(|seed| game(seed, moves, |_, _, _| {}))
+        .collect()
+}
+
+/// Returns a Go game with a comment of about `comment_length` characters on every node.
+///
+/// Comments include escaped characters and line breaks.
+pub fn comment_heavy_game(moves: usize, comment_length: usize) -> String {
+    const WORDS: &[&str] = &[
+        "move", "good", "\\]", "slack", "a:b", "\n", "tesuji", "\\\\",
+    ];
+    game(0, moves, |rng, _, sgf| {
+        sgf.push_str("C[");
+        let start = sgf.len();
+        while sgf.len() - start < comment_length {
+            sgf.push_str(WORDS[rng.below(WORDS.len() as u64) as usize]);
+            sgf.push(' ');
+        }
+        sgf.push(']');
+    })
+}
+
+/// Returns a Go game with several markup properties on every node.
+pub fn markup_heavy_game(moves: usize) -> String {
+    game(0, moves, |rng, i, sgf| {
+        for identifier in ["CR", "TR", "SQ", "MA"] {
+            write!(sgf, "{}[{}]", identifier, random_point(rng)).unwrap();
+        }
+        write!(sgf, "LB[{}:{}]", random_point(rng), i).unwrap();
+        write!(
+            sgf,
+            "LN[aa:{}]AR[ss:{}]",
+            random_point(rng),
+            random_point(rng)
+        )
+        .unwrap();
+    })
+}
+
+/// Returns a collection of `count` games with problems only lenient parsing accepts.
+///
+/// Games use FF\[3\] style mixed case identifiers and have properties before the first node.
+/// Parse with [`ParseOptions::recover_missing_nodes`](`crate::ParseOptions::recover_missing_nodes`)
+/// set.
+///
+/// # Examples
+/// ```
+/// use sgf_parse::{parse_with_options, ParseOptions};
+/// use sgf_parse::testing::generate::broken_games;
+///
+/// let options = ParseOptions {
+///     recover_missing_nodes: true,
+///     ..ParseOptions::default()
+/// };
+/// assert_eq!(parse_with_options(&broken_games(3, 10), &options).unwrap().len(), 3);
+/// ```
+pub fn broken_games(count: usize, moves: usize) -> String {
+    (0..count as u64)
+        .map(|seed| {
+            let mut rng = Rng::new(seed);
+            let mut sgf = "(GaMe[1]SiZe[19]PlayerBlack[Black]".to_string();
+            for i in 1..=moves {
+                let color = if i % 2 == 1 { "Black" } else { "White" };
+                write!(
+                    sgf,
+                    ";{}[{}]Comment[Move {}]",
+                    color,
+                    random_point(&mut rng),
+                    i
+                )
+                .unwrap();
+            }
+            sgf.push(')');
+            sgf
+        })
+        .collect()
+}
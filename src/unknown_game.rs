@@ -5,16 +5,25 @@
 //! properties](https://www.red-bean.com/sgf/properties.html), but any game
 //! specific property will parse as [`Prop::Unknown`].
 //!
-//! SGF Move, Point, and Stone values are all simply stored as strings.
+//! SGF Move, Point, Stone, and board size values are all simply stored
+//! as strings. The [`decode_point`] helper can be used to heuristically
+//! interpret point strings from common coordinate systems.
 
 use crate::props::parse::FromCompressedList;
-use crate::props::{PropertyType, SgfPropError, ToSgf};
+use crate::props::{PropertyType, Sealed, SgfPropError, ToSgf};
 use crate::{InvalidNodeError, SgfProp};
 use std::collections::HashSet;
 
 sgf_prop! {
-    Prop, String, String, String,
-    { }
+    Prop, String, String, String, String
+}
+
+// Board sizes vary too much between games to interpret, so keep the raw value.
+fn parse_size(values: &[String]) -> Result<String, SgfPropError> {
+    match values {
+        [value] if !value.is_empty() => Ok(value.clone()),
+        _ => Err(SgfPropError {}),
+    }
 }
 
 /// An SGF [Point](https://www.red-bean.com/sgf/go.html#types) value for an unknown game.
@@ -26,24 +35,112 @@ pub type Stone = String;
 /// An SGF [Move](https://www.red-bean.com/sgf/go.html#types) value for an unknown game.
 pub type Move = String;
 
+/// An SGF [SZ](https://www.red-bean.com/sgf/properties.html#SZ) value for an unknown game.
+///
+/// The value is kept exactly as written, since board sizes aren't necessarily numeric or
+/// small enough for a `u8` in other games.
+///
+/// # Examples
+/// ```
+/// use sgf_parse::{parse, GameTree};
+/// use sgf_parse::unknown_game::Prop;
+///
+/// let gametree = parse("(;GM[5]SZ[300])").unwrap().into_iter().next().unwrap();
+/// let node = match gametree {
+///     GameTree::Unknown(node) => node,
+///     _ => unreachable!(),
+/// };
+/// assert_eq!(node.get_property("SZ"), Some(&Prop::SZ("300".to_string())));
+/// assert_eq!(node.serialize(), "(;GM[5]SZ[300])");
+/// ```
+pub type Size = String;
+
+/// A heuristic interpretation of an unknown game [`Point`] as returned by [`decode_point`].
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum DecodedPoint {
+    /// Go style coordinates with two letters (e.g. `"cd"`).
+    Letters(crate::go::Point),
+    /// A non-negative integer (e.g. `"24"`).
+    Numeric(u64),
+    /// Chess style coordinates with a file letter and a rank number (e.g. `"e4"`).
+    ///
+    /// The file is zero-indexed from `a`, and the rank is left as written.
+    Chess { file: u8, rank: u64 },
+    /// Any value not matching a known coordinate system.
+    Unrecognized(String),
+}
+
+/// Returns a [`DecodedPoint`] for the provided point string.
+///
+/// Go style, numeric, and chess style decodings are attempted in that order.
+///
+/// # Examples
+/// ```
+/// use sgf_parse::go;
+/// use sgf_parse::unknown_game::{decode_point, DecodedPoint};
+///
+/// assert_eq!(decode_point("cd"), DecodedPoint::Letters(go::Point { x: 2, y: 3 }));
+/// assert_eq!(decode_point("24"), DecodedPoint::Numeric(24));
+/// assert_eq!(decode_point("e4"), DecodedPoint::Chess { file: 4, rank: 4 });
+/// assert_eq!(decode_point("a:b"), DecodedPoint::Unrecognized("a:b".to_string()));
+/// ```
+pub fn decode_point(point: &str) -> DecodedPoint {
+    if let Ok(point) = point.parse() {
+        return DecodedPoint::Letters(point);
+    }
+    if !point.is_empty() && point.chars().all(|c| c.is_ascii_digit()) {
+        if let Ok(n) = point.parse() {
+            return DecodedPoint::Numeric(n);
+        }
+    }
+    let mut chars = point.chars();
+    if let Some(c) = chars.next().filter(|c| c.is_ascii_lowercase()) {
+        let rest = chars.as_str();
+        if !rest.is_empty() && rest.chars().all(|c| c.is_ascii_digit()) {
+            if let Ok(rank) = rest.parse() {
+                return DecodedPoint::Chess {
+                    file: c as u8 - b'a',
+                    rank,
+                };
+            }
+        }
+    }
+    DecodedPoint::Unrecognized(point.to_string())
+}
+
+impl Sealed for Prop {
+    fn from_values(identifier: String, values: Vec<String>) -> Self {
+        Self::parse_prop(identifier, values)
+    }
+
+    fn raw_values(&self) -> Vec<String> {
+        self.prop_values()
+    }
+}
+
 impl SgfProp for Prop {
     type Point = Point;
     type Stone = Stone;
     type Move = Move;
 
-    fn new(identifier: String, values: Vec<String>) -> Self {
-        Self::parse_general_prop(identifier, values)
+    fn identifier(&self) -> String {
+        self.identifier_str().to_string()
     }
 
-    fn identifier(&self) -> String {
-        match self.general_identifier() {
-            Some(identifier) => identifier,
-            None => panic!("Unimplemented identifier for {:?}", self),
-        }
+    fn identifier_str(&self) -> &str {
+        self.prop_identifier()
     }
 
     fn property_type(&self) -> Option<PropertyType> {
-        self.general_property_type()
+        self.prop_type()
+    }
+
+    fn is_unknown(&self) -> bool {
+        matches!(self, Self::Unknown(_, _))
+    }
+
+    fn is_invalid(&self) -> bool {
+        matches!(self, Self::Invalid(_, _))
     }
 
     fn validate_properties(properties: &[Self], is_root: bool) -> Result<(), InvalidNodeError> {
@@ -53,11 +150,12 @@ impl SgfProp for Prop {
 
 impl std::fmt::Display for Prop {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let prop_string = match self.serialize_prop_value() {
-            Some(s) => s,
-            None => panic!("Unimplemented identifier for {:?}", self),
-        };
-        write!(f, "{}[{}]", self.identifier(), prop_string)
+        write!(
+            f,
+            "{}[{}]",
+            self.identifier_str(),
+            self.serialize_prop_value()
+        )
     }
 }
 
@@ -0,0 +1,83 @@
+//! Regression harness for the SGF fixtures in `resources/test/corpus`.
+//!
+//! Every `.sgf` file in the corpus is parsed in lenient mode (default options) and strict mode
+//! (no FF\[3\] identifier conversion), and each parsed game is validated. The resulting
+//! diagnostics are compared against the `.snap` file next to the fixture.
+//!
+//! Run with `UPDATE_SNAPSHOTS=1` to write new snapshots after an intentional change.
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+use sgf_parse::{parse_bytes_with_options, GameTree, ParseOptions};
+
+fn corpus_dir() -> PathBuf {
+    let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    path.push("resources/test/corpus");
+    path
+}
+
+fn diagnostics(data: &[u8], options: &ParseOptions) -> String {
+    let gametrees = match parse_bytes_with_options(data, options) {
+        Ok(gametrees) => gametrees,
+        Err(e) => return format!("parse error: {}", e),
+    };
+    let mut output = format!("{} game(s)", gametrees.len());
+    for (i, gametree) in gametrees.iter().enumerate() {
+        let result = match gametree {
+            GameTree::GoGame(node) => node.validate(),
+            GameTree::Unknown(node) => node.validate(),
+            _ => Ok(()),
+        };
+        match result {
+            Ok(()) => write!(output, "; game {} ({}): valid", i, gametree.gametype()),
+            Err(e) => write!(output, "; game {} ({}): {}", i, gametree.gametype(), e),
+        }
+        .unwrap();
+    }
+    output
+}
+
+fn snapshot(path: &Path) -> String {
+    let data = std::fs::read(path).unwrap();
+    let strict_options = ParseOptions {
+        convert_mixed_case_identifiers: false,
+        ..ParseOptions::default()
+    };
+    format!(
+        "lenient: {}\nstrict: {}\n",
+        diagnostics(&data, &ParseOptions::default()),
+        diagnostics(&data, &strict_options),
+    )
+}
+
+#[test]
+fn corpus_snapshots() {
+    let update = std::env::var_os("UPDATE_SNAPSHOTS").is_some();
+    let mut fixtures: Vec<PathBuf> = std::fs::read_dir(corpus_dir())
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "sgf"))
+        .collect();
+    fixtures.sort();
+    assert!(!fixtures.is_empty());
+
+    let mut failures = vec![];
+    for fixture in fixtures {
+        let actual = snapshot(&fixture);
+        let snapshot_path = fixture.with_extension("snap");
+        if update {
+            std::fs::write(&snapshot_path, &actual).unwrap();
+            continue;
+        }
+        let expected = std::fs::read_to_string(&snapshot_path).unwrap_or_default();
+        if actual != expected {
+            failures.push(format!(
+                "{}:\n  expected:\n{}  actual:\n{}",
+                fixture.display(),
+                expected,
+                actual
+            ));
+        }
+    }
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}